@@ -38,9 +38,9 @@ fn main() {
         .blacklist_type("max_align_t")
         //.generate_inline_functions(true) 
         // ai_allocate
-        .whitelist_function("AiMalloc")
-        .whitelist_function("AiRealloc")
-        .whitelist_function("AiFree")
+        .whitelist_function("AiMalloc_func")
+        .whitelist_function("AiRealloc_func")
+        .whitelist_function("AiFree_func")
         .whitelist_function("AiAddMemUsage")
         // ai_array
         .whitelist_type("AtArray")
@@ -48,6 +48,9 @@ fn main() {
         // ai_bbox
         .blacklist_type("AtBBox.*") // reimplemented in ai_bbox.rs
         .whitelist_function("AiBBox.*")
+        // ai_cameras
+        .whitelist_type("AtCamera.*")
+        .whitelist_function("AiCamera.*")
         // ai_color
         .whitelist_type("AtRGB.*")
         .whitelist_function("AiRGB.*")
@@ -76,9 +79,17 @@ fn main() {
         // ai_drivers
         .whitelist_function("AiFindDriverType")
         .whitelist_function("AiDriverExtension")
+        .whitelist_function("AiDriverInitialize")
+        .whitelist_type("AtDriverNodeMethods")
+        .whitelist_function("AiOutputIterator.*")
         // ai_enum
         .whitelist_type("AtEnum")
         .whitelist_function("AiEnum.*")
+        // ai_filters
+        .whitelist_type("AtFilterNodeMethods")
+        .whitelist_function("AiFilter.*")
+        .whitelist_type("AtAOVSampleIterator")
+        .whitelist_function("AiAOVSampleIterator.*")
         // ai_license
         .whitelist_type("AtLicenseInfo")
         .whitelist_function("AiLicenseGetInfo")
@@ -115,6 +126,10 @@ fn main() {
         // ai_plugins
         .whitelist_type("AtNodeLib")
         .whitelist_function("AiLoadPlugins")
+        // ai_procedural
+        .whitelist_type("AtProceduralNodeMethods")
+        .whitelist_type("AtProcViewportMode")
+        .whitelist_function("AiProceduralViewport")
         // ai_ray
         .whitelist_type("AtRay")
         .whitelist_function("AiMakeRay")
@@ -134,6 +149,11 @@ fn main() {
         // ai_shader_radiance
         .whitelist_function("AiIrradiance")
         .whitelist_function("AiRadiance")
+        // ai_shaderglobals
+        .whitelist_type("AtShaderGlobals")
+        .whitelist_function("AiShaderEvalParamFunc.*")
+        // ai_shaders
+        .whitelist_type("AtShaderNodeMethods")
         // ai_stats
         .whitelist_function("AiStats.+")
         .whitelist_function("AiProfile.+")
@@ -0,0 +1,309 @@
+//! Memory Allocation API
+//!
+//! [`ArnoldAllocator`](struct.ArnoldAllocator.html) allocates through AiMalloc(), so Rust data lives in the same heap as Arnold's own data. It can be installed as the global allocator:
+//! ```
+//! #[global_allocator]
+//! static ALLOCATOR: ArnoldAllocator = ArnoldAllocator;
+//! ```
+//!
+//! [`ArrayBuffer`](struct.ArrayBuffer.html) is a growable buffer stored directly in an AtArray. Large geometry can be pushed into it and handed to a node without converting it with AiArrayConvert():
+//! ```
+//! let mut vlist = ArrayBuffer::with_capacity(&session, points.len());
+//! vlist.extend(points.iter().map(|p| AtVector { x: p[0], y: p[1], z: p[2] }));
+//! AiNodeSetArray(&mesh, AtString::try_from("vlist")?, vlist.into_array());
+//! ```
+
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+
+use ai_array::{AiArrayAllocate, AiArrayResize, Array, ArrayElement};
+use ai_bindings;
+use ai_render::Session;
+use error::ArnoldError;
+
+use std::alloc::{GlobalAlloc, Layout};
+use std::ffi::CString;
+use std::ops::{Deref, DerefMut};
+use std::os::raw::{c_char, c_void};
+use std::{ptr, slice};
+
+/// Source location passed to the AiMalloc() family, as static C strings so that allocating never allocates.
+macro_rules! location {
+    () => {
+        (
+            concat!(file!(), "\0").as_ptr() as *const c_char,
+            line!() as i32,
+            concat!(module_path!(), "\0").as_ptr() as *const c_char,
+        )
+    };
+}
+
+/// Allocate a block of memory.
+///
+/// # Parameters
+/// * `size` - size in bytes of the requested memory block
+/// # Returns
+/// pointer to the allocated memory block, or NULL if the allocation failed
+pub fn AiMalloc(size: usize) -> *mut c_void {
+    let (file, line, func) = location!();
+    unsafe { ai_bindings::AiMalloc_func(size, file, line, func) }
+}
+
+/// Reallocate a block of memory.
+///
+/// # Safety
+/// `addr` must be NULL or a block returned by AiMalloc() or AiRealloc() that has not been freed.
+/// # Parameters
+/// * `addr` - address of the memory block to reallocate
+/// * `size` - new size in bytes of the memory block
+/// # Returns
+/// pointer to the reallocated memory block, or NULL if the reallocation failed
+pub unsafe fn AiRealloc(addr: *mut c_void, size: usize) -> *mut c_void {
+    let (file, line, func) = location!();
+    ai_bindings::AiRealloc_func(addr, size, file, line, func)
+}
+
+/// Free a block of memory.
+///
+/// # Safety
+/// `addr` must be NULL or a block returned by AiMalloc() or AiRealloc() that has not been freed.
+/// # Parameters
+/// * `addr` - address of the memory block to free
+pub unsafe fn AiFree(addr: *mut c_void) {
+    let (file, line, func) = location!();
+    ai_bindings::AiFree_func(addr, file, line, func)
+}
+
+/// Report memory allocated outside of Arnold, so that it shows up in Arnold's memory statistics.
+///
+/// # Parameters
+/// * `_session` - the active session
+/// * `size` - number of bytes allocated, or a negative number for bytes released
+/// * `category` - category the memory is reported under
+/// # Returns
+/// an error if `category` contains a NUL byte
+pub fn AiAddMemUsage(_session: &Session, size: i64, category: &str) -> Result<(), ArnoldError> {
+    let category = CString::new(category)?;
+    unsafe { ai_bindings::AiAddMemUsage(size, category.as_ptr()) }
+    Ok(())
+}
+
+/// Alignment guaranteed by AiMalloc(), larger alignments are handled by over-allocating.
+const MALLOC_ALIGN: usize = 8;
+
+/// A global allocator backed by AiMalloc(), AiRealloc() and AiFree().
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ArnoldAllocator;
+
+unsafe impl GlobalAlloc for ArnoldAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if layout.align() <= MALLOC_ALIGN {
+            return AiMalloc(layout.size()) as *mut u8;
+        }
+        // Over-allocate, and keep the block address just before the aligned pointer.
+        let block = AiMalloc(layout.size() + layout.align()) as *mut u8;
+        if block.is_null() {
+            return block;
+        }
+        let offset = layout.align() - (block as usize) % layout.align();
+        let aligned = block.add(offset);
+        (aligned as *mut *mut u8).sub(1).write_unaligned(block);
+        aligned
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if layout.align() <= MALLOC_ALIGN {
+            AiFree(ptr as *mut c_void);
+        } else {
+            AiFree((ptr as *mut *mut u8).sub(1).read_unaligned() as *mut c_void);
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if layout.align() <= MALLOC_ALIGN {
+            return AiRealloc(ptr as *mut c_void, new_size) as *mut u8;
+        }
+        let new_ptr = self.alloc(Layout::from_size_align_unchecked(new_size, layout.align()));
+        if !new_ptr.is_null() {
+            ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+            self.dealloc(ptr, layout);
+        }
+        new_ptr
+    }
+}
+
+/// A growable buffer of `T` elements, stored in an AtArray with a single motion key.
+///
+/// Unlike a `Vec`, the storage already belongs to an array, so [`into_array`](#method.into_array) hands it to Arnold without converting the elements.
+/// The storage comes from AiArrayAllocate(), so Arnold's memory statistics already count it.
+pub struct ArrayBuffer<T: ArrayElement> {
+    array: Array<T>,
+    len: usize,
+}
+
+impl<T: ArrayElement> ArrayBuffer<T> {
+    /// Create an empty buffer.
+    pub fn new(session: &Session) -> ArrayBuffer<T> {
+        ArrayBuffer::with_capacity(session, 0)
+    }
+
+    /// Create an empty buffer with room for `capacity` elements.
+    ///
+    /// # Panics
+    /// if `capacity` does not fit in an array
+    pub fn with_capacity(session: &Session, capacity: usize) -> ArrayBuffer<T> {
+        let nelements = array_capacity(capacity);
+        let array = unsafe { Array::from_raw(AiArrayAllocate(session, nelements, 1, T::TYPE)) }
+            .expect("AiArrayAllocate failed");
+        ArrayBuffer { array: array, len: 0 }
+    }
+
+    /// Number of elements in the buffer.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check if the buffer holds no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of elements the buffer can hold without growing.
+    pub fn capacity(&self) -> usize {
+        self.array.num_elements()
+    }
+
+    /// Make room for at least `additional` more elements.
+    ///
+    /// # Panics
+    /// if the new capacity does not fit in an array
+    pub fn reserve(&mut self, additional: usize) {
+        let capacity = self.capacity();
+        let required = self.len.checked_add(additional).expect("ArrayBuffer capacity overflow");
+        if required <= capacity {
+            return;
+        }
+        let new_capacity = required.max(capacity * 2).max(4);
+        self.array.unmap();
+        AiArrayResize(self.array.as_ptr(), array_capacity(new_capacity), 1);
+    }
+
+    /// Append an element.
+    pub fn push(&mut self, value: T) {
+        self.reserve(1);
+        unsafe { self.array.map_data().add(self.len).write(value) };
+        self.len += 1;
+    }
+
+    /// Append all elements of a slice.
+    pub fn extend_from_slice(&mut self, values: &[T]) {
+        if values.is_empty() {
+            return;
+        }
+        self.reserve(values.len());
+        unsafe { ptr::copy_nonoverlapping(values.as_ptr(), self.array.map_data().add(self.len), values.len()) };
+        self.len += values.len();
+    }
+
+    /// Remove all elements, keeping the capacity.
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Hand the storage over as an [`Array`](../ai_array/struct.Array.html) of [`len`](#method.len) elements.
+    ///
+    /// A full buffer is handed over as is. Otherwise the unused capacity is dropped with AiArrayResize(), which may copy the elements into a smaller block.
+    pub fn into_array(self) -> Array<T> {
+        if self.len < self.capacity() {
+            self.array.unmap();
+            AiArrayResize(self.array.as_ptr(), self.len as u32, 1);
+        }
+        self.array
+    }
+}
+
+impl<T: ArrayElement> Deref for ArrayBuffer<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        if self.len == 0 {
+            return &[];
+        }
+        unsafe { slice::from_raw_parts(self.array.map_data(), self.len) }
+    }
+}
+
+impl<T: ArrayElement> DerefMut for ArrayBuffer<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        if self.len == 0 {
+            return &mut [];
+        }
+        unsafe { slice::from_raw_parts_mut(self.array.map_data(), self.len) }
+    }
+}
+
+impl<T: ArrayElement> Extend<T> for ArrayBuffer<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+fn array_capacity(capacity: usize) -> u32 {
+    assert!(capacity <= u32::MAX as usize, "ArrayBuffer capacity overflow");
+    capacity as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_msg::AI_LOG_NONE;
+    use ai_render::AI_SESSION_BATCH;
+    #[test]
+    fn arnold_allocator() {
+        let allocator = ArnoldAllocator;
+        for &align in &[1, 8, 64] {
+            let layout = Layout::from_size_align(100, align).unwrap();
+            unsafe {
+                let ptr = allocator.alloc(layout);
+                assert!(!ptr.is_null());
+                assert_eq!(ptr as usize % align, 0);
+                ptr.write_bytes(7, 100);
+                let ptr = allocator.realloc(ptr, layout, 200);
+                assert_eq!(ptr as usize % align, 0);
+                assert_eq!(*ptr.add(99), 7);
+                allocator.dealloc(ptr, Layout::from_size_align(200, align).unwrap());
+            }
+        }
+    }
+    #[test]
+    fn array_buffer() {
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let mut buffer = ArrayBuffer::new(&session);
+        buffer.extend((0..1000).map(|i| i as f32));
+        buffer.push(1000.0);
+        buffer.extend_from_slice(&[1001.0, 1002.0]);
+        assert_eq!(buffer.len(), 1003);
+        assert!(buffer.capacity() >= 1003);
+        buffer[0] = -1.0;
+        let array = buffer.into_array();
+        assert_eq!((array.num_elements(), array.num_keys()), (1003, 1));
+        assert_eq!(array.get(0), Some(-1.0));
+        assert_eq!(array.get(1002), Some(1002.0));
+    }
+    #[test]
+    fn array_buffer_outliving_session() {
+        let buffer = {
+            let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+            let mut buffer = ArrayBuffer::with_capacity(&session, 4);
+            buffer.push(1u32);
+            buffer
+        };
+        // AiEnd() released the storage, so dropping the buffer must not call into Arnold.
+        drop(buffer);
+    }
+}
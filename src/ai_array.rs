@@ -8,53 +8,92 @@
 use ai_bindings;
 use ai_bindings::{AtArray, AtMatrix, AtRGB, AtRGBA};
 use ai_vector::{AtVector, AtVector2};
+use ai_render::{active_session, Session};
 use ai_string::AtString;
+use error::ArnoldError;
 
-use std::ffi::CString;
-
-pub use ai_bindings::AI_TYPE_VECTOR2;
-pub use ai_bindings::AI_TYPE_ARRAY;
-pub use ai_bindings::AI_TYPE_BOOLEAN;
-pub use ai_bindings::AI_TYPE_BYTE;
-pub use ai_bindings::AI_TYPE_CLOSURE;
-pub use ai_bindings::AI_TYPE_ENUM;
-pub use ai_bindings::AI_TYPE_FLOAT;
-pub use ai_bindings::AI_TYPE_HALF;
-pub use ai_bindings::AI_TYPE_INT;
-pub use ai_bindings::AI_TYPE_MATRIX;
-pub use ai_bindings::AI_TYPE_NODE;
-pub use ai_bindings::AI_TYPE_NONE;
-pub use ai_bindings::AI_TYPE_POINTER;
-pub use ai_bindings::AI_TYPE_RGB;
-pub use ai_bindings::AI_TYPE_RGBA;
-pub use ai_bindings::AI_TYPE_STRING;
-pub use ai_bindings::AI_TYPE_UINT;
-pub use ai_bindings::AI_TYPE_UNDEFINED;
-pub use ai_bindings::AI_TYPE_USHORT;
-pub use ai_bindings::AI_TYPE_VECTOR;
-
-extern "C" {
-    /// Create an array and initialize it with supplied data.
-    ///
-    /// This function should always be given nelements * nkeys number of data elements. To create an array without supplying any initial data see [AiArrayAllocate()] instead. To create an array out of an existing memory buffer, use [AiArrayConvert()].
-    ///
-    /// # Safety
-    /// As this function use `varargs` we expose the unsafe binding.
-    /// # Example:
-    /// ```rust
-    /// let AtArray a = unsafe{ AiArray(4, 1, AI_TYPE_FLOAT, 10.f, 11.f, 12.f, 13.f) };
-    /// assert_eq!(AiArrayGetFlt(a,3), 13.0);
-    /// ```
-    /// Parameters
-    /// nelements	number of elements per motion key in the new array
-    /// nkeys	number of motion keys in the new array
-    /// type	array type ([AI_TYPE_BYTE], etc.)
-    /// ...	array data
-    /// # Returns
-    /// pointer to the new array filled with data, or NULL if the array couldn't be allocated
-    /// # See also
-    /// [AiArrayAllocate]
-    pub fn AiArray(nelements: u32, nkeys: u8, type_: ::std::os::raw::c_int, ...) -> *mut AtArray;
+use std::cell::Cell;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::os::raw::{c_char, c_void};
+use std::ptr;
+use std::slice;
+
+pub use ai_params::ParamType;
+pub use ai_params::{
+    AI_TYPE_ARRAY, AI_TYPE_BOOLEAN, AI_TYPE_BYTE, AI_TYPE_CLOSURE, AI_TYPE_ENUM, AI_TYPE_FLOAT,
+    AI_TYPE_HALF, AI_TYPE_INT, AI_TYPE_MATRIX, AI_TYPE_NODE, AI_TYPE_NONE, AI_TYPE_POINTER,
+    AI_TYPE_RGB, AI_TYPE_RGBA, AI_TYPE_STRING, AI_TYPE_UINT, AI_TYPE_UNDEFINED, AI_TYPE_USHORT,
+    AI_TYPE_VECTOR, AI_TYPE_VECTOR2,
+};
+
+/// Create an [`Array`](ai_array/struct.Array.html) and initialize it with supplied data.
+///
+/// This replaces the variadic AiArray() constructor. The data is gathered into a buffer and passed to AiArrayConvert(), so values keep their exact type instead of going through C varargs.
+///
+/// The element type is inferred from the values, or given as an `AI_TYPE_*` name before a `;`, in which case every value is converted with `Into`, or with `AtString::try_from` for AI_TYPE_STRING. Motion keys are listed with `keys:`, and must all hold the same number of elements.
+/// ```
+/// let a = arnold_array![AI_TYPE_FLOAT; 10.0, 11.0, 12.0, 13.0];
+/// assert_eq!(a.get(3), Some(13.0));
+/// let names = arnold_array![AI_TYPE_STRING; "left", "right"];
+/// let points = arnold_array![keys: [
+///     [AtVector { x: 0.0, y: 0.0, z: 0.0 }],
+///     [AtVector { x: 0.0, y: 1.0, z: 0.0 }],
+/// ]];
+/// let weights = arnold_array![AI_TYPE_FLOAT; keys: [[1.0, 1.0, 1.0], [2.0, 2.0, 2.0]]];
+/// ```
+/// # Panics
+/// if no session is active, the motion keys differ in length, there are more than 255 keys or a string contains a NUL byte
+#[macro_export]
+macro_rules! arnold_array {
+    ($array_type:ident; keys: [$([$($value:expr),* $(,)*]),* $(,)*]) => {
+        $crate::ai_array::Array::<arnold_array_element!($array_type)>::from(
+            vec![$(vec![$(arnold_array_value!($array_type, $value)),*]),*]
+        )
+    };
+    ($array_type:ident; $($value:expr),* $(,)*) => {
+        $crate::ai_array::Array::<arnold_array_element!($array_type)>::from(
+            vec![$(arnold_array_value!($array_type, $value)),*]
+        )
+    };
+    (keys: [$([$($value:expr),* $(,)*]),* $(,)*]) => {
+        $crate::ai_array::Array::from(vec![$(vec![$($value),*]),*])
+    };
+    ($($value:expr),+ $(,)*) => {
+        $crate::ai_array::Array::from(vec![$($value),+])
+    };
+}
+
+/// The Rust element type stored for an `AI_TYPE_*` name, used by [`arnold_array!`](macro.arnold_array.html).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! arnold_array_element {
+    (AI_TYPE_BYTE) => { u8 };
+    (AI_TYPE_INT) => { i32 };
+    (AI_TYPE_UINT) => { u32 };
+    (AI_TYPE_BOOLEAN) => { bool };
+    (AI_TYPE_FLOAT) => { f32 };
+    (AI_TYPE_RGB) => { $crate::ai_bindings::AtRGB };
+    (AI_TYPE_RGBA) => { $crate::ai_bindings::AtRGBA };
+    (AI_TYPE_VECTOR) => { $crate::ai_vector::AtVector };
+    (AI_TYPE_VECTOR2) => { $crate::ai_vector::AtVector2 };
+    (AI_TYPE_MATRIX) => { $crate::ai_bindings::AtMatrix };
+    (AI_TYPE_STRING) => { $crate::ai_string::AtString };
+}
+
+/// Convert a value to the element type of an `AI_TYPE_*` name, used by [`arnold_array!`](macro.arnold_array.html).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! arnold_array_value {
+    (AI_TYPE_STRING, $value:expr) => {
+        <$crate::ai_string::AtString as ::std::convert::TryFrom<_>>::try_from($value)
+            .expect("array strings cannot contain NUL bytes")
+    };
+    ($array_type:ident, $value:expr) => {
+        ::std::convert::Into::<arnold_array_element!($array_type)>::into($value)
+    };
 }
 
 /// Create an empty (uninitialized) array of the specified type.
@@ -62,15 +101,16 @@ extern "C" {
 /// The returned array has data fully allocated (but not initialized) and its elements can be set with the ArraySet*() functions/macros.
 ///
 /// # Parameters
+/// * `_session` - the active session
 /// * `nelements` - number of elements per motion key in the new array
 /// * `nkeys` - number of motion keys in the new array
 /// * `type` - type of the elements in the new array (AI_TYPE_BYTE, etc.)
 /// # Returns
 /// pointer to an allocated array of nelements * nkeys elements, or NULL if the array couldn't be allocated
 /// # See also
-/// [AiArray]
-pub fn AiArrayAllocate(nelements: u32, nkeys: u8, type_: u8) -> *mut AtArray {
-    unsafe { ai_bindings::AiArrayAllocate(nelements, nkeys, type_) }
+/// [`arnold_array!`](../macro.arnold_array.html)
+pub fn AiArrayAllocate(_session: &Session, nelements: u32, nkeys: u8, type_: ParamType) -> *mut AtArray {
+    unsafe { ai_bindings::AiArrayAllocate(nelements, nkeys, type_.code()) }
 }
 
 /// Deallocate an array object.
@@ -83,7 +123,7 @@ pub fn AiArrayDestroy(array: *mut AtArray) {
 
 /// Create an array and initialize it from an existing data buffer.
 ///
-/// This is the recommended constructor when programmatically creating arrays of arbitrary size with existing data. For on-the-fly creation of small arrays of known values, the AiArray() constructor can also be used.
+/// This is the recommended constructor when programmatically creating arrays of arbitrary size with existing data. For on-the-fly creation of small arrays of known values, the [`arnold_array!`](../macro.arnold_array.html) macro can also be used.
 ///
 /// Usage example:
 /// ```
@@ -96,6 +136,7 @@ pub fn AiArrayDestroy(array: *mut AtArray) {
 /// assert(AiArrayGetFlt(a,3) == 13.f);
 /// ```
 /// # Parameters
+/// * `_session` - the active session
 /// * `nelements` - number of elements per motion key
 /// * `nkeys` - number of motion keys
 /// * `type` - element type
@@ -103,12 +144,13 @@ pub fn AiArrayDestroy(array: *mut AtArray) {
 /// # Returns
 /// pointer to the new array filled with data, or NULL if the array couldn't be allocated
 pub fn AiArrayConvert(
+    _session: &Session,
     nelements: u32,
     nkeys: u8,
-    type_: u8,
+    type_: ParamType,
     data: *const ::std::os::raw::c_void,
 ) -> *mut AtArray {
-    unsafe { ai_bindings::AiArrayConvert(nelements, nkeys, type_, data) }
+    unsafe { ai_bindings::AiArrayConvert(nelements, nkeys, type_.code(), data) }
 }
 
 /// Resize an existing array contents in place.
@@ -140,7 +182,8 @@ pub fn AiArrayCopy(array: *const AtArray) -> *mut AtArray {
 /// Usage example:
 /// ```
 /// // create an array of two motion keys, three elements per key
-/// AtArray* array = AiArray(3, 2, AI_TYPE_FLOAT, 1.0f, 1.0f, 1.0f, 2.0f, 2.0f, 2.0f);
+/// float olddata[6] = { 1.0f, 1.0f, 1.0f, 2.0f, 2.0f, 2.0f };
+/// AtArray* array = AiArrayConvert(3, 2, AI_TYPE_FLOAT, olddata);
 /// // at this point, the array contains:   1, 1, 1, 2, 2, 2
 ///
 /// float newdata[3] = { 9.0f, 9.0f, 9.0f };
@@ -226,8 +269,8 @@ pub fn AiArrayGetNumKeys(array: *const AtArray) -> u8 {
 /// * `array` - source array
 /// # Returns
 /// type of elements in this array
-pub fn AiArrayGetType(array: *const AtArray) -> u8 {
-    unsafe { ai_bindings::AiArrayGetType(array) }
+pub fn AiArrayGetType(array: *const AtArray) -> ParamType {
+    ParamType::from_code(unsafe { ai_bindings::AiArrayGetType(array) })
 }
 
 /// Get the total size in bytes of the data buffer for this array.
@@ -315,7 +358,7 @@ pub fn AiArrayGetBool(a: *const AtArray, i: u32) -> bool {
         ai_bindings::AiArrayGetBoolFunc(
             a,
             i,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -326,7 +369,7 @@ pub fn AiArrayGetByte(a: *const AtArray, i: u32) -> u8 {
         ai_bindings::AiArrayGetByteFunc(
             a,
             i,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -337,7 +380,7 @@ pub fn AiArrayGetInt(a: *const AtArray, i: u32) -> ::std::os::raw::c_int {
         ai_bindings::AiArrayGetIntFunc(
             a,
             i,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -348,7 +391,7 @@ pub fn AiArrayGetUInt(a: *const AtArray, i: u32) -> u32 {
         ai_bindings::AiArrayGetUIntFunc(
             a,
             i,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -359,7 +402,7 @@ pub fn AiArrayGetFlt(a: *const AtArray, i: u32) -> f32 {
         ai_bindings::AiArrayGetFltFunc(
             a,
             i,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -370,7 +413,7 @@ pub fn AiArrayGetRGB(a: *const AtArray, i: u32) -> AtRGB {
         ai_bindings::AiArrayGetRGBFunc(
             a,
             i,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -381,7 +424,7 @@ pub fn AiArrayGetRGBA(a: *const AtArray, i: u32) -> AtRGBA {
         ai_bindings::AiArrayGetRGBAFunc(
             a,
             i,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -392,7 +435,7 @@ pub fn AiArrayGetVec2(a: *const AtArray, i: u32) -> AtVector2 {
         ai_bindings::AiArrayGetVec2Func(
             a,
             i,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -403,7 +446,7 @@ pub fn AiArrayGetVec(a: *const AtArray, i: u32) -> AtVector {
         ai_bindings::AiArrayGetVecFunc(
             a,
             i,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -414,7 +457,7 @@ pub fn AiArrayGetMtx(a: *const AtArray, i: u32) -> AtMatrix {
         ai_bindings::AiArrayGetMtxFunc(
             a,
             i,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -425,7 +468,7 @@ pub fn AiArrayGetStr(a: *const AtArray, i: u32) -> AtString {
         ai_bindings::AiArrayGetStrFunc(
             a,
             i,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -436,7 +479,7 @@ pub fn AiArrayGetPtr(a: *const AtArray, i: u32) -> *mut ::std::os::raw::c_void {
         ai_bindings::AiArrayGetPtrFunc(
             a,
             i,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -447,7 +490,7 @@ pub fn AiArrayGetArray(a: *const AtArray, i: u32) -> *mut AtArray {
         ai_bindings::AiArrayGetArrayFunc(
             a,
             i,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -459,7 +502,7 @@ pub fn AiArraySetBool(a: *mut AtArray, i: u32, val: bool) -> bool {
             a,
             i,
             val,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -471,7 +514,7 @@ pub fn AiArraySetByte(a: *mut AtArray, i: u32, val: u8) -> bool {
             a,
             i,
             val,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -483,7 +526,7 @@ pub fn AiArraySetInt(a: *mut AtArray, i: u32, val: ::std::os::raw::c_int) -> boo
             a,
             i,
             val,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -495,7 +538,7 @@ pub fn AiArraySetUInt(a: *mut AtArray, i: u32, val: u32) -> bool {
             a,
             i,
             val,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -507,7 +550,7 @@ pub fn AiArraySetFlt(a: *mut AtArray, i: u32, val: f32) -> bool {
             a,
             i,
             val,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -519,7 +562,7 @@ pub fn AiArraySetRGB(a: *mut AtArray, i: u32, val: AtRGB) -> bool {
             a,
             i,
             val,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -531,7 +574,7 @@ pub fn AiArraySetRGBA(a: *mut AtArray, i: u32, val: AtRGBA) -> bool {
             a,
             i,
             val,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -543,7 +586,7 @@ pub fn AiArraySetVec2(a: *mut AtArray, i: u32, val: AtVector2) -> bool {
             a,
             i,
             val,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -555,7 +598,7 @@ pub fn AiArraySetVec(a: *mut AtArray, i: u32, val: AtVector) -> bool {
             a,
             i,
             val,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -567,7 +610,7 @@ pub fn AiArraySetMtx(a: *mut AtArray, i: u32, val: AtMatrix) -> bool {
             a,
             i,
             val,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -579,7 +622,7 @@ pub fn AiArraySetStr(a: *mut AtArray, i: u32, val: AtString) -> bool {
             a,
             i,
             val,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -591,7 +634,7 @@ pub fn AiArraySetPtr(a: *mut AtArray, i: u32, val: *mut ::std::os::raw::c_void)
             a,
             i,
             val,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
@@ -603,8 +646,532 @@ pub fn AiArraySetArray(a: *mut AtArray, i: u32, val: *mut AtArray) -> bool {
             a,
             i,
             val,
-            CString::new(file!()).unwrap().as_ptr(),
+            concat!(file!(), "\0").as_ptr() as *const c_char,
             line!() as i32,
         )
     }
 }
+
+/// Rust types that can be stored in an [`Array`](struct.Array.html).
+///
+/// # Safety
+/// The type must have the same size and layout as Arnold's storage for `TYPE`.
+pub unsafe trait ArrayElement: Copy {
+    /// The Arnold type of the array elements.
+    const TYPE: ParamType;
+}
+
+unsafe impl ArrayElement for u8 {
+    const TYPE: ParamType = AI_TYPE_BYTE;
+}
+
+unsafe impl ArrayElement for i32 {
+    const TYPE: ParamType = AI_TYPE_INT;
+}
+
+unsafe impl ArrayElement for u32 {
+    const TYPE: ParamType = AI_TYPE_UINT;
+}
+
+unsafe impl ArrayElement for bool {
+    const TYPE: ParamType = AI_TYPE_BOOLEAN;
+}
+
+unsafe impl ArrayElement for f32 {
+    const TYPE: ParamType = AI_TYPE_FLOAT;
+}
+
+unsafe impl ArrayElement for AtRGB {
+    const TYPE: ParamType = AI_TYPE_RGB;
+}
+
+unsafe impl ArrayElement for AtRGBA {
+    const TYPE: ParamType = AI_TYPE_RGBA;
+}
+
+unsafe impl ArrayElement for AtVector {
+    const TYPE: ParamType = AI_TYPE_VECTOR;
+}
+
+unsafe impl ArrayElement for AtVector2 {
+    const TYPE: ParamType = AI_TYPE_VECTOR2;
+}
+
+unsafe impl ArrayElement for AtMatrix {
+    const TYPE: ParamType = AI_TYPE_MATRIX;
+}
+
+unsafe impl ArrayElement for AtString {
+    const TYPE: ParamType = AI_TYPE_STRING;
+}
+
+/// An owned AtArray of `T` elements, grouped by motion keys.
+///
+/// The array is destroyed when dropped, unless it has been handed to a node with [`AiNodeSetArray`](../ai_nodes/fn.AiNodeSetArray.html), which takes ownership.
+/// Arrays that are still alive when the session ends are released by AiEnd(), and dropping them afterwards does nothing.
+///
+/// ```
+/// let mut points = Array::from(vec![AtVector { x: 0.0, y: 0.0, z: 0.0 }, AtVector { x: 1.0, y: 0.0, z: 0.0 }]);
+/// points.push_key(&[AtVector { x: 0.0, y: 1.0, z: 0.0 }, AtVector { x: 1.0, y: 1.0, z: 0.0 }])?;
+/// AiNodeSetArray(&mesh, AtString::try_from("vlist")?, points);
+/// ```
+pub struct Array<T: ArrayElement> {
+    array: *mut AtArray,
+    session: Option<usize>,
+    mapping: Mapping,
+    _type: PhantomData<T>,
+}
+
+impl<T: ArrayElement> Array<T> {
+    /// Create an array of `nkeys` motion keys, each holding `nelements` copies of `value`.
+    ///
+    /// # Parameters
+    /// * `session` - the active session
+    /// * `nelements` - number of elements per motion key
+    /// * `nkeys` - number of motion keys
+    /// * `value` - initial value of every element
+    pub fn new(session: &Session, nelements: u32, nkeys: u8, value: T) -> Array<T> {
+        let mut array = unsafe { Array::from_raw(AiArrayAllocate(session, nelements, nkeys, T::TYPE)) }
+            .expect("AiArrayAllocate failed");
+        for element in array.data_mut() {
+            *element = value;
+        }
+        array
+    }
+
+    /// Take ownership of a raw array.
+    ///
+    /// # Safety
+    /// `array` must be NULL or an array of `T::TYPE` elements that nothing else destroys, created in the active session.
+    /// # Returns
+    /// the owned array, or `None` for NULL or outside of a session
+    pub unsafe fn from_raw(array: *mut AtArray) -> Option<Array<T>> {
+        let session = active_session();
+        if array.is_null() || session.is_none() {
+            return None;
+        }
+        Some(Array {
+            array: array,
+            session: session,
+            mapping: Mapping {
+                data: Cell::new(ptr::null_mut()),
+            },
+            _type: PhantomData,
+        })
+    }
+
+    /// The raw array pointer, for use with the C API.
+    pub fn as_ptr(&self) -> *mut AtArray {
+        self.array
+    }
+
+    /// Give up ownership of the array, which is no longer destroyed on drop.
+    pub fn into_raw(self) -> *mut AtArray {
+        self.unmap();
+        let array = self.array;
+        mem::forget(self);
+        array
+    }
+
+    /// Number of elements per motion key.
+    pub fn num_elements(&self) -> usize {
+        AiArrayGetNumElements(self.array) as usize
+    }
+
+    /// Number of motion keys.
+    pub fn num_keys(&self) -> usize {
+        AiArrayGetNumKeys(self.array) as usize
+    }
+
+    /// Iterate over the motion keys, as slices of [`num_elements`](#method.num_elements) elements.
+    pub fn keys(&self) -> Keys<'_, T> {
+        Keys {
+            array: self,
+            key: 0,
+        }
+    }
+
+    /// The elements of motion key `key`.
+    ///
+    /// # Panics
+    /// if `key` is not less than [`num_keys`](#method.num_keys)
+    pub fn key(&self, key: usize) -> &[T] {
+        assert!(key < self.num_keys(), "motion key {} out of range", key);
+        let nelements = self.num_elements();
+        &self.data()[key * nelements..(key + 1) * nelements]
+    }
+
+    /// Append a motion key, which must hold [`num_elements`](#method.num_elements) elements.
+    pub fn push_key(&mut self, key: &[T]) -> Result<(), ArnoldError> {
+        let nelements = self.num_elements();
+        let nkeys = self.num_keys();
+        if key.len() != nelements {
+            return Err(ArnoldError::KeyLength {
+                expected: nelements,
+                found: key.len(),
+            });
+        }
+        if nkeys >= u8::MAX as usize {
+            return Err(ArnoldError::TooManyKeys { nkeys: nkeys + 1 });
+        }
+        self.unmap();
+        // Keys are stored one after the other, so growing the key count keeps the existing keys in place.
+        AiArrayResize(self.array, nelements as u32, nkeys as u8 + 1);
+        AiArraySetKey(self.array, nkeys as u8, key.as_ptr() as *const c_void);
+        Ok(())
+    }
+
+    /// Change the number of elements per motion key, truncating every key or padding it with `value`.
+    pub fn resize(&mut self, nelements: u32, value: T) {
+        let old_nelements = self.num_elements();
+        let nkeys = self.num_keys();
+        let mut data = Vec::with_capacity(nelements as usize * nkeys);
+        for key in self.keys() {
+            data.extend(key.iter().take(nelements as usize));
+            data.extend((old_nelements..nelements as usize).map(|_| value));
+        }
+        self.unmap();
+        let array = AiArrayConvertData(nelements, nkeys as u8, &data);
+        AiArrayDestroy(mem::replace(&mut self.array, array));
+    }
+
+    /// Return the element at `index`, counting across all motion keys, or `None` if out of bounds.
+    pub fn get(&self, index: usize) -> Option<T> {
+        self.data().get(index).cloned()
+    }
+
+    /// Set the element at `index`, counting across all motion keys.
+    pub fn set(&mut self, index: usize, value: T) -> Result<(), ArnoldError> {
+        let data = self.data_mut();
+        let len = data.len();
+        match data.get_mut(index) {
+            Some(element) => {
+                *element = value;
+                Ok(())
+            }
+            None => Err(ArnoldError::IndexOutOfBounds { index: index, len: len }),
+        }
+    }
+
+    /// All elements, with the motion keys one after the other.
+    fn data(&self) -> &[T] {
+        let len = self.num_elements() * self.num_keys();
+        if len == 0 {
+            return &[];
+        }
+        unsafe { slice::from_raw_parts(self.map_data(), len) }
+    }
+
+    fn data_mut(&mut self) -> &mut [T] {
+        let len = self.num_elements() * self.num_keys();
+        if len == 0 {
+            return &mut [];
+        }
+        unsafe { slice::from_raw_parts_mut(self.map_data(), len) }
+    }
+
+    /// Map the array once, it stays mapped until it is resized, handed over or destroyed.
+    pub(crate) fn map_data(&self) -> *mut T {
+        if self.mapping.data.get().is_null() {
+            self.mapping.data.set(AiArrayMap(self.array));
+        }
+        self.mapping.data.get() as *mut T
+    }
+
+    /// Undo the mapping made by [`map_data`](#method.map_data), if any.
+    pub(crate) fn unmap(&self) {
+        if !self.mapping.data.replace(ptr::null_mut()).is_null() {
+            AiArrayUnmap(self.array);
+        }
+    }
+
+    /// Map all motion keys for reading.
+    ///
+    /// ```
+    /// let weights = array.map::<f32>()?;
+    /// let total: f32 = weights.iter().sum();
+    /// ```
+    /// # Returns
+    /// a guard that dereferences to the elements, or an error if the array does not hold `U` elements
+    pub fn map<U: ArrayElement>(&self) -> Result<ArrayMap<'_, U>, ArnoldError> {
+        let len = self.num_elements() * self.num_keys();
+        let data = self.map_guarded::<U>(None)?;
+        Ok(ArrayMap {
+            data: data,
+            len: len,
+            _array: PhantomData,
+        })
+    }
+
+    /// Map all motion keys for writing.
+    ///
+    /// ```
+    /// let mut points = array.map_mut::<AtVector>()?;
+    /// for (point, position) in points.iter_mut().zip(positions) {
+    ///     *point = position;
+    /// }
+    /// ```
+    /// # Returns
+    /// a guard that dereferences to the elements, or an error if the array does not hold `U` elements
+    pub fn map_mut<U: ArrayElement>(&mut self) -> Result<ArrayMapMut<'_, U>, ArnoldError> {
+        let len = self.num_elements() * self.num_keys();
+        let data = self.map_guarded::<U>(None)?;
+        Ok(ArrayMapMut {
+            data: data,
+            len: len,
+            _array: PhantomData,
+        })
+    }
+
+    /// Map a single motion key for reading, see [`map`](#method.map).
+    pub fn map_key<U: ArrayElement>(&self, key: usize) -> Result<ArrayMap<'_, U>, ArnoldError> {
+        let len = self.num_elements();
+        let data = self.map_guarded::<U>(Some(key))?;
+        Ok(ArrayMap {
+            data: data,
+            len: len,
+            _array: PhantomData,
+        })
+    }
+
+    /// Map a single motion key for writing, see [`map_mut`](#method.map_mut).
+    pub fn map_key_mut<U: ArrayElement>(&mut self, key: usize) -> Result<ArrayMapMut<'_, U>, ArnoldError> {
+        let len = self.num_elements();
+        let data = self.map_guarded::<U>(Some(key))?;
+        Ok(ArrayMapMut {
+            data: data,
+            len: len,
+            _array: PhantomData,
+        })
+    }
+
+    /// Check the element type and key, then point into the mapping shared with [`data`](#method.data), at the start of the array or of a single key.
+    fn map_guarded<U: ArrayElement>(&self, key: Option<usize>) -> Result<*mut U, ArnoldError> {
+        let array_type = AiArrayGetType(self.array);
+        if array_type != U::TYPE {
+            return Err(ArnoldError::ArrayType {
+                array_type: array_type,
+                requested: U::TYPE,
+            });
+        }
+        let offset = match key {
+            None => 0,
+            Some(key) if key < self.num_keys() => key * self.num_elements(),
+            Some(key) => {
+                return Err(ArnoldError::IndexOutOfBounds {
+                    index: key,
+                    len: self.num_keys(),
+                })
+            }
+        };
+        if self.num_elements() * self.num_keys() == 0 {
+            return Ok(ptr::null_mut());
+        }
+        // U has the same element type as T, so the mapping holds U elements.
+        Ok(unsafe { (self.map_data() as *mut U).add(offset) })
+    }
+}
+
+/// Mapping state of an [`Array`](struct.Array.html).
+///
+/// Slices returned by [`Array::key`](struct.Array.html#method.key) and the [`ArrayMap`](struct.ArrayMap.html) guards share a single AiArrayMap() call, which is undone when the array is resized, handed over or destroyed.
+/// Those methods take `&mut self`, so no slice or guard can outlive the mapping.
+#[derive(Debug)]
+struct Mapping {
+    data: Cell<*mut c_void>,
+}
+
+/// Read access to mapped array data, see [`Array::map`](struct.Array.html#method.map).
+///
+/// The guard borrows the mapping of the array, which stays mapped after the guard is dropped.
+pub struct ArrayMap<'a, U: ArrayElement> {
+    data: *mut U,
+    len: usize,
+    _array: PhantomData<&'a [U]>,
+}
+
+impl<'a, U: ArrayElement> Deref for ArrayMap<'a, U> {
+    type Target = [U];
+
+    fn deref(&self) -> &[U] {
+        if self.len == 0 {
+            return &[];
+        }
+        unsafe { slice::from_raw_parts(self.data, self.len) }
+    }
+}
+
+/// Write access to mapped array data, see [`Array::map_mut`](struct.Array.html#method.map_mut).
+///
+/// The guard borrows the mapping of the array, which stays mapped after the guard is dropped.
+pub struct ArrayMapMut<'a, U: ArrayElement> {
+    data: *mut U,
+    len: usize,
+    _array: PhantomData<&'a mut [U]>,
+}
+
+impl<'a, U: ArrayElement> Deref for ArrayMapMut<'a, U> {
+    type Target = [U];
+
+    fn deref(&self) -> &[U] {
+        if self.len == 0 {
+            return &[];
+        }
+        unsafe { slice::from_raw_parts(self.data, self.len) }
+    }
+}
+
+impl<'a, U: ArrayElement> DerefMut for ArrayMapMut<'a, U> {
+    fn deref_mut(&mut self) -> &mut [U] {
+        if self.len == 0 {
+            return &mut [];
+        }
+        unsafe { slice::from_raw_parts_mut(self.data, self.len) }
+    }
+}
+
+impl<T: ArrayElement> Drop for Array<T> {
+    fn drop(&mut self) {
+        if self.session.is_some() && self.session == active_session() {
+            self.unmap();
+            AiArrayDestroy(self.array);
+        }
+    }
+}
+
+impl<T: ArrayElement + fmt::Debug> fmt::Debug for Array<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_list().entries(self.keys()).finish()
+    }
+}
+
+/// Build an array from a buffer of `nelements * nkeys` elements.
+fn AiArrayConvertData<T: ArrayElement>(nelements: u32, nkeys: u8, data: &[T]) -> *mut AtArray {
+    assert!(active_session().is_some(), "arrays can only be created during a session");
+    let array = unsafe { ai_bindings::AiArrayConvert(nelements, nkeys, T::TYPE.code(), data.as_ptr() as *const c_void) };
+    assert!(!array.is_null(), "AiArrayConvert failed");
+    array
+}
+
+impl<T: ArrayElement> From<Vec<T>> for Array<T> {
+    /// Create an array with a single motion key.
+    ///
+    /// # Panics
+    /// if no session is active
+    fn from(data: Vec<T>) -> Array<T> {
+        let array = AiArrayConvertData(data.len() as u32, 1, &data);
+        unsafe { Array::from_raw(array) }.unwrap()
+    }
+}
+
+impl<T: ArrayElement> From<Vec<Vec<T>>> for Array<T> {
+    /// Create an array with one motion key per inner vector.
+    ///
+    /// # Panics
+    /// if no session is active, the keys differ in length or there are more than 255 keys
+    fn from(keys: Vec<Vec<T>>) -> Array<T> {
+        assert!(keys.len() <= u8::MAX as usize, "arrays hold at most 255 motion keys");
+        let nelements = keys.first().map_or(0, Vec::len);
+        assert!(keys.iter().all(|key| key.len() == nelements), "motion keys differ in length");
+        let nkeys = keys.len() as u8;
+        let data: Vec<T> = keys.into_iter().flat_map(Vec::into_iter).collect();
+        let array = AiArrayConvertData(nelements as u32, nkeys, &data);
+        unsafe { Array::from_raw(array) }.unwrap()
+    }
+}
+
+/// Iterator over the motion keys of an array, see [`Array::keys`](struct.Array.html#method.keys).
+#[derive(Debug)]
+pub struct Keys<'a, T: ArrayElement + 'a> {
+    array: &'a Array<T>,
+    key: usize,
+}
+
+impl<'a, T: ArrayElement> Iterator for Keys<'a, T> {
+    type Item = &'a [T];
+
+    fn next(&mut self) -> Option<&'a [T]> {
+        if self.key >= self.array.num_keys() {
+            return None;
+        }
+        self.key += 1;
+        Some(self.array.key(self.key - 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_msg::AI_LOG_NONE;
+    use ai_render::AI_SESSION_BATCH;
+    #[test]
+    fn owned_arrays() {
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let mut array = Array::new(&session, 3, 1, 0.5f32);
+        assert_eq!(array.key(0), &[0.5, 0.5, 0.5]);
+        array.set(1, 2.0).unwrap();
+        assert_eq!(array.get(1), Some(2.0));
+        assert_eq!(array.get(3), None);
+        assert_eq!(array.set(3, 1.0), Err(ArnoldError::IndexOutOfBounds { index: 3, len: 3 }));
+        array.push_key(&[1.0, 2.0, 3.0]).unwrap();
+        assert_eq!(array.num_keys(), 2);
+        assert_eq!(array.key(1), &[1.0, 2.0, 3.0]);
+        assert_eq!(array.push_key(&[1.0]), Err(ArnoldError::KeyLength { expected: 3, found: 1 }));
+        array.resize(4, 9.0);
+        assert_eq!(array.keys().collect::<Vec<_>>(), vec![&[0.5, 2.0, 0.5, 9.0][..], &[1.0, 2.0, 3.0, 9.0][..]]);
+    }
+    #[test]
+    fn array_conversions() {
+        let _session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let array = Array::from(vec![1, 2, 3]);
+        assert_eq!(AiArrayGetType(array.as_ptr()), AI_TYPE_INT);
+        assert_eq!((array.num_elements(), array.num_keys()), (3, 1));
+        let keys = Array::from(vec![vec![1u8, 2], vec![3, 4], vec![5, 6]]);
+        assert_eq!((keys.num_elements(), keys.num_keys()), (2, 3));
+        assert_eq!(keys.key(2), &[5, 6]);
+    }
+    #[test]
+    fn array_macro() {
+        let _session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let floats = arnold_array![AI_TYPE_FLOAT; 10.0, 11.0, 12.0, 13.0];
+        assert_eq!(AiArrayGetType(floats.as_ptr()), AI_TYPE_FLOAT);
+        assert_eq!(floats.get(3), Some(13.0));
+        let bytes = arnold_array![AI_TYPE_BYTE; 1, 2];
+        assert_eq!(AiArrayGetType(bytes.as_ptr()), AI_TYPE_BYTE);
+        let names = arnold_array![AI_TYPE_STRING; "left", "right"];
+        assert_eq!(names.get(1).map(|name| name.to_str().unwrap().to_owned()), Some("right".to_owned()));
+        let inferred = arnold_array![1u32, 2, 3];
+        assert_eq!(AiArrayGetType(inferred.as_ptr()), AI_TYPE_UINT);
+        let keys = arnold_array![AI_TYPE_FLOAT; keys: [[1.0, 1.0, 1.0], [2.0, 2.0, 2.0]]];
+        assert_eq!((keys.num_elements(), keys.num_keys()), (3, 2));
+        assert_eq!(keys.key(1), &[2.0, 2.0, 2.0]);
+        let flags = arnold_array![keys: [[true], [false]]];
+        assert_eq!(flags.key(1), &[false]);
+    }
+    #[test]
+    fn mapped_arrays() {
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let mut array = Array::new(&session, 2, 2, AtVector { x: 0.0, y: 0.0, z: 0.0 });
+        {
+            let mut points = array.map_key_mut::<AtVector>(1).unwrap();
+            assert_eq!(points.len(), 2);
+            points[1] = AtVector { x: 1.0, y: 2.0, z: 3.0 };
+        }
+        assert_eq!(array.map::<AtVector>().unwrap()[3], AtVector { x: 1.0, y: 2.0, z: 3.0 });
+        assert_eq!(
+            array.map::<f32>().err(),
+            Some(ArnoldError::ArrayType {
+                array_type: AI_TYPE_VECTOR,
+                requested: AI_TYPE_FLOAT
+            })
+        );
+        assert!(array.map_key::<AtVector>(2).is_err());
+        // Slices and guards share one mapping, which push_key() releases before resizing.
+        let first = array.key(0);
+        drop(array.map_key::<AtVector>(0).unwrap());
+        assert_eq!(first, &[AtVector { x: 0.0, y: 0.0, z: 0.0 }; 2]);
+        assert_eq!(array.key(1), array.key(1));
+        array.push_key(&[AtVector { x: 4.0, y: 5.0, z: 6.0 }; 2]).unwrap();
+        assert_eq!(array.map_key::<AtVector>(2).unwrap()[0], AtVector { x: 4.0, y: 5.0, z: 6.0 });
+    }
+}
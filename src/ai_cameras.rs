@@ -0,0 +1,208 @@
+//! Camera API
+//!
+//! A camera projection can be implemented in Rust with the [`Camera`](trait.Camera.html) trait and installed with [`install_camera`](fn.install_camera.html). Rays are created in camera space, Arnold then moves them to world space with the camera matrix:
+//! ```
+//! struct Spherical;
+//!
+//! impl Camera for Spherical {
+//!     fn initialize(_node: &Node) -> Spherical { Spherical }
+//!     fn create_ray(&self, screen_pos: AtVector2, _lens_sample: AtVector2) -> (AtVector, AtVector, AtRGB) {
+//!         let (phi, theta) = (screen_pos.x * AI_PI, screen_pos.y * AI_PIOVER2);
+//!         let dir = AtVector { x: theta.cos() * phi.sin(), y: theta.sin(), z: -theta.cos() * phi.cos() };
+//!         (AtVector { x: 0.0, y: 0.0, z: 0.0 }, dir, AtRGB { r: 1.0, g: 1.0, b: 1.0 })
+//!     }
+//! }
+//!
+//! install_camera::<Spherical>(&session, "spherical_camera")?;
+//! ```
+
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+
+use ai_bindings;
+use ai_bindings::{AtCameraInput, AtCameraNodeMethods, AtCameraOutput, AtCommonMethods, AtList, AtNode, AtNodeEntry, AtRGB};
+use ai_node_entry::{drop_instance, guard_method, install_methods, instance, instance_mut, report, set_instance, NodeEntry, NodeType};
+use ai_nodes::Node;
+use ai_params::{ParamList, ParamType};
+use ai_render::Session;
+use ai_vector::{AtVector, AtVector2};
+use error::ArnoldError;
+
+use std::os::raw::c_void;
+
+/// A camera implemented in Rust.
+///
+/// Cameras follow the [lifecycle of node types implemented in Rust](../ai_node_entry/index.html#node-types-implemented-in-rust).
+pub trait Camera: Send + Sync + Sized + 'static {
+    /// Declare the camera parameters, in addition to the ones shared by all cameras such as "fov" or "matrix".
+    fn parameters(_params: &mut ParamList) -> Result<(), ArnoldError> {
+        Ok(())
+    }
+
+    /// Create the camera for a new camera node.
+    fn initialize(node: &Node) -> Self;
+
+    /// Read the camera parameters again, such as the ones animated between frames.
+    fn update(&mut self, _node: &Node) {}
+
+    /// Create a camera ray.
+    ///
+    /// The ray derivatives are left at zero, so texture lookups are not filtered by the pixel footprint.
+    /// # Parameters
+    /// * `screen_pos` - position on the screen window, in [-1, 1] along the larger image side
+    /// * `lens_sample` - lens sampling position, in [0, 1)
+    /// # Returns
+    /// the ray origin and direction in camera space, and the ray weight
+    fn create_ray(&self, screen_pos: AtVector2, lens_sample: AtVector2) -> (AtVector, AtVector, AtRGB);
+
+    /// Project a camera-space point back onto the screen, the reverse of [`create_ray`](#tymethod.create_ray).
+    ///
+    /// # Parameters
+    /// * `point` - point in camera space
+    /// * `distance` - distance from the camera to the point
+    /// # Returns
+    /// the screen position, or `None` if the point is not visible or the camera cannot project points, which is the default
+    fn reverse_ray(&self, _point: AtVector, _distance: f32) -> Option<AtVector2> {
+        None
+    }
+}
+
+/// Install a node type for a Rust [`Camera`](trait.Camera.html).
+///
+/// # Parameters
+/// * `session` - the active session
+/// * `name` - name of the new camera node type
+/// # Returns
+/// the installed node entry, or an error, see [node types implemented in Rust](../ai_node_entry/index.html#node-types-implemented-in-rust)
+pub fn install_camera<'s, C: Camera>(session: &'s Session, name: &str) -> Result<NodeEntry<'s>, ArnoldError> {
+    let cmethods = AtCommonMethods {
+        PluginInitialize: None,
+        PluginCleanup: None,
+        Parameters: Some(camera_parameters::<C>),
+        Initialize: Some(camera_initialize::<C>),
+        Update: Some(camera_update::<C>),
+        Finish: Some(camera_finish::<C>),
+    };
+    let dmethods: &'static AtCameraNodeMethods = Box::leak(Box::new(AtCameraNodeMethods {
+        CreateRay: Some(camera_create_ray::<C>),
+        ReverseRay: Some(camera_reverse_ray::<C>),
+    }));
+    install_methods(session, NodeType::CAMERA, ParamType::Undefined, name, cmethods, dmethods as *const AtCameraNodeMethods as *const c_void)
+}
+
+unsafe extern "C" fn camera_parameters<C: Camera>(params: *mut AtList, _nentry: *mut AtNodeEntry) {
+    guard_method("camera parameters", None, || report("camera parameters", C::parameters(&mut ParamList::from_raw(params))));
+}
+
+unsafe extern "C" fn camera_initialize<C: Camera>(node: *mut AtNode) {
+    ai_bindings::AiCameraInitialize(node);
+    let camera = guard_method("camera initialize", None, || Node::from_raw(node).map(|node| C::initialize(&node)));
+    if let Some(camera) = camera {
+        set_instance(node, camera);
+    }
+}
+
+unsafe extern "C" fn camera_update<C: Camera>(node: *mut AtNode) {
+    ai_bindings::AiCameraUpdate(node, false);
+    if let (Some(camera), Some(node)) = (instance_mut::<C>(node), Node::from_raw(node)) {
+        guard_method("camera update", (), || camera.update(&node))
+    }
+}
+
+unsafe extern "C" fn camera_finish<C: Camera>(node: *mut AtNode) {
+    guard_method("camera finish", (), || drop_instance::<C>(node));
+}
+
+unsafe extern "C" fn camera_create_ray<C: Camera>(node: *const AtNode, input: *const AtCameraInput, output: *mut AtCameraOutput, _tid: u16) {
+    let camera = match instance::<C>(node) {
+        Some(camera) => camera,
+        None => return,
+    };
+    let input = &*input;
+    let screen_pos = AtVector2 { x: input.sx, y: input.sy };
+    let lens_sample = AtVector2 { x: input.lensx, y: input.lensy };
+    if let Some((origin, dir, weight)) = guard_method("camera create_ray", None, || Some(camera.create_ray(screen_pos, lens_sample))) {
+        let output = &mut *output;
+        output.origin = origin;
+        output.dir = dir;
+        output.weight = weight;
+    }
+}
+
+unsafe extern "C" fn camera_reverse_ray<C: Camera>(node: *const AtNode, point: *const AtVector, distance: f32, screen_pos: *mut AtVector2) -> bool {
+    let camera = match instance::<C>(node) {
+        Some(camera) => camera,
+        None => return false,
+    };
+    match guard_method("camera reverse_ray", None, || camera.reverse_ray(*point, distance)) {
+        Some(pos) => {
+            *screen_pos = pos;
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_drivers::TestScene;
+    use ai_msg::AI_LOG_NONE;
+    use ai_nodes::AiNode;
+    use ai_render::AI_SESSION_BATCH;
+    use ai_universe::Universe;
+
+    /// Orthographic camera looking down -Z, with a screen window scaled by "scale".
+    struct Ortho {
+        scale: f32,
+    }
+
+    impl Camera for Ortho {
+        fn parameters(params: &mut ParamList) -> Result<(), ArnoldError> {
+            params.float("scale", 1.0)?;
+            Ok(())
+        }
+        fn initialize(node: &Node) -> Ortho {
+            Ortho { scale: node.get("scale").unwrap() }
+        }
+        fn update(&mut self, node: &Node) {
+            self.scale = node.get("scale").unwrap();
+        }
+        fn create_ray(&self, screen_pos: AtVector2, _lens_sample: AtVector2) -> (AtVector, AtVector, AtRGB) {
+            let origin = AtVector { x: screen_pos.x * self.scale, y: screen_pos.y * self.scale, z: 0.0 };
+            let dir = AtVector { x: 0.0, y: 0.0, z: -1.0 };
+            (origin, dir, AtRGB { r: 1.0, g: 1.0, b: 1.0 })
+        }
+        fn reverse_ray(&self, point: AtVector, _distance: f32) -> Option<AtVector2> {
+            Some(AtVector2 { x: point.x / self.scale, y: point.y / self.scale })
+        }
+    }
+    #[test]
+    fn rust_camera() {
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let entry = install_camera::<Ortho>(&session, "rust_ortho").unwrap();
+        assert_eq!(entry.node_type(), NodeType::CAMERA);
+        assert!(entry.param("matrix").is_some());
+        assert!(entry.param("scale").is_some());
+
+        let universe = Universe::default(&session);
+        let camera = AiNode(&universe, "rust_ortho", Some("camera"), None).unwrap();
+        camera.set("scale", 2.0).unwrap();
+        let scene = TestScene::new(&universe, &camera);
+        scene.sphere("sphere", AtVector { x: 0.0, y: 0.0, z: -5.0 }, 1.0);
+
+        // The screen window spans [-2, 2], so the sphere of radius 1 covers the pixels within 4 pixels of the center.
+        let framebuffer = scene.render();
+        assert_eq!(framebuffer.pixel(8, 8).a, 1.0);
+        assert_eq!(framebuffer.pixel(10, 6).a, 1.0);
+        assert_eq!(framebuffer.pixel(8, 1).a, 0.0);
+        assert_eq!(framebuffer.pixel(0, 0).a, 0.0);
+
+        // Doubling the scale through update() halves the sphere on screen, to 2 pixels around the center.
+        camera.set("scale", 4.0).unwrap();
+        let framebuffer = scene.render();
+        assert_eq!(framebuffer.pixel(8, 8).a, 1.0);
+        assert_eq!(framebuffer.pixel(10, 6).a, 0.0);
+    }
+}
@@ -14,18 +14,21 @@
 
 use ai_bindings;
 use ai_bindings::AtMetadataStore;
+use error::ArnoldError;
+use ai_node_entry::NodeType;
+use ai_universe::Universe;
 
 use std::ffi::CString;
 /// Write all nodes in the scene to an .ass file.
 /// 
 /// This function can selectively write all nodes of a given type to an .ass file. For example, to write light nodes and camera nodes only, use:
 /// ```
-/// AiASSWrite("lightsncams.ass", AI_NODE_LIGHT + AI_NODE_CAMERA, false);
+/// AiASSWrite(&universe, "lightsncams.ass", AI_NODE_LIGHT | AI_NODE_CAMERA, false, false);
 /// ```
 /// 
 /// To write all nodes of all types, use:
 /// ```
-/// AiASSWrite("everything.ass", AI_NODE_ALL, false);
+/// AiASSWrite(&universe, "everything.ass", AI_NODE_ALL, false, false);
 /// ```
 /// 
 /// Just like AiASSLoad(), this function has built-in gzip compression. If filename ends in ".gz", the generated file will be automatically compressed.
@@ -33,17 +36,19 @@ use std::ffi::CString;
 /// # Warning
 /// Because nodes are partially-released (their array-parameters are released), we can NO LONGER RENDER after a call to AiASSWrite().
 /// # Parameters
+/// * `universe` - the universe whose nodes are written
 /// * `filename` - output filename
 /// * `mask` - only write the desired types of nodes
 /// * `open_procs` - if set, then all procedurals will be recursively expanded
 /// * `binary` - allow binary encoding in .ass files
 /// # Returns
-/// 0 if the file was written succesfully, -1 if error
-pub fn AiASSWrite(filename: &str, mask: i32, open_procs: bool, binary: bool) -> i32{
-    let filename = CString::new(filename).unwrap();
-    unsafe {
-        ai_bindings::AiASSWrite(filename.as_ptr(), mask, open_procs, binary)
-    }
+/// `Ok` if the file was written succesfully, [`ArnoldError::AssWrite`](../error/enum.ArnoldError.html) if error
+pub fn AiASSWrite(universe: &Universe, filename: &str, mask: NodeType, open_procs: bool, binary: bool) -> Result<(), ArnoldError> {
+    let c_filename = CString::new(filename)?;
+    let result = unsafe {
+        ai_bindings::AiASSWrite(universe.as_ptr(), c_filename.as_ptr(), mask.bits() as i32, open_procs, binary)
+    };
+    write_result(result, filename)
 }
 
 /// Write all nodes in the scene to an .ass file, including metadata.
@@ -51,17 +56,27 @@ pub fn AiASSWrite(filename: &str, mask: i32, open_procs: bool, binary: bool) ->
 /// See AiASSWrite
 /// 
 /// # Parameters
+/// * `universe` - the universe whose nodes are written
 /// * `filename` - output filename
 /// * `mask` - only write the desired types of nodes
 /// * `open_procs` - if set, then all procedurals will be recursively expanded
 /// * `binary` - allow binary encoding in .ass files
 /// * `mds` - optional metadata store for writing metadata into the file
 /// # Returns
-/// 0 if the file was written succesfully, -1 if error
-pub fn AiASSWriteWithMetadata(filename: &str, mask: i32, open_procs: bool, binary: bool, mds: *const AtMetadataStore) -> i32{
-    let filename = CString::new(filename).unwrap();
-    unsafe {
-        ai_bindings::AiASSWriteWithMetadata(filename.as_ptr(), mask, open_procs, binary, mds)
+/// `Ok` if the file was written succesfully, [`ArnoldError::AssWrite`](../error/enum.ArnoldError.html) if error
+pub fn AiASSWriteWithMetadata(universe: &Universe, filename: &str, mask: NodeType, open_procs: bool, binary: bool, mds: *const AtMetadataStore) -> Result<(), ArnoldError> {
+    let c_filename = CString::new(filename)?;
+    let result = unsafe {
+        ai_bindings::AiASSWriteWithMetadata(universe.as_ptr(), c_filename.as_ptr(), mask.bits() as i32, open_procs, binary, mds)
+    };
+    write_result(result, filename)
+}
+
+fn write_result(result: i32, filename: &str) -> Result<(), ArnoldError> {
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(ArnoldError::AssWrite { path: filename.to_owned() })
     }
 }
 
@@ -74,14 +89,40 @@ pub fn AiASSWriteWithMetadata(filename: &str, mask: i32, open_procs: bool, binar
 /// Any forward references due to linked nodes will be automatically resolved at the end of the file. The order in which nodes appear in the file is irrelevant.
 /// 
 /// # Parameters
+/// * `universe` - the universe the nodes are loaded into
 /// * `filename` - input filename
 /// * `mask` - only read nodes with types included in the mask (default is AI_NODE_ALL)
 /// # Returns
-/// 0 if the file was read successfully, -1 if error
-pub fn AiASSLoad(filename: &str, mask: i32) -> i32{
-    let filename = CString::new(filename).unwrap();
-    unsafe {
-        ai_bindings::AiASSLoad(filename.as_ptr(), mask)
+/// `Ok` if the file was read successfully, [`ArnoldError::AssLoad`](../error/enum.ArnoldError.html) if error
+pub fn AiASSLoad(universe: &Universe, filename: &str, mask: NodeType) -> Result<(), ArnoldError> {
+    let c_filename = CString::new(filename)?;
+    let result = unsafe {
+        ai_bindings::AiASSLoad(universe.as_ptr(), c_filename.as_ptr(), mask.bits() as i32)
+    };
+    if result == 0 {
+        Ok(())
+    } else {
+        Err(ArnoldError::AssLoad { path: filename.to_owned() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_msg::AI_LOG_NONE;
+    use ai_node_entry::AI_NODE_ALL;
+    use ai_render::{Session, AI_SESSION_BATCH};
+    #[test]
+    fn ass_errors() {
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let universe = Universe::default(&session);
+        assert_eq!(
+            AiASSLoad(&universe, "no_such_scene.ass", AI_NODE_ALL),
+            Err(ArnoldError::AssLoad { path: "no_such_scene.ass".to_owned() })
+        );
+        match AiASSLoad(&universe, "bad\0name.ass", AI_NODE_ALL) {
+            Err(ArnoldError::InvalidString { position, .. }) => assert_eq!(position, 3),
+            other => panic!("expected an invalid string error, got {:?}", other),
+        }
     }
 }
-    
@@ -1,64 +1,776 @@
+//! Output Driver API
+//!
+//! Drivers receive the rendered buckets and write them out, usually to an image file. A driver can be implemented in Rust with the [`Driver`](trait.Driver.html) trait and installed with [`install_driver`](fn.install_driver.html):
+//! ```
+//! struct Preview { pixels: Vec<AtRGBA> }
+//!
+//! impl Driver for Preview {
+//!     fn initialize(_node: &Node) -> Preview { Preview { pixels: Vec::new() } }
+//!     fn open(&mut self, _outputs: &[DriverOutput], _display: AtBBox2, _data: AtBBox2, _bucket_size: i32) {}
+//!     fn write_bucket(&mut self, _bucket: Bucket, aovs: &[AovBucket]) {
+//!         if let AovPixels::RGBA(pixels) = aovs[0].pixels {
+//!             self.pixels.extend_from_slice(pixels);
+//!         }
+//!     }
+//!     fn close(&mut self) { send_to_compositor(&self.pixels) }
+//! }
+//!
+//! install_driver::<Preview>(&session, "driver_preview")?;
+//! ```
+//!
+//! Every session also installs `driver_memory`, which keeps the rendered pixels in a [`Framebuffer`](struct.Framebuffer.html) instead of writing a file:
+//! ```
+//! let driver = AiNode(&universe, DRIVER_MEMORY, Some("memory"), None)?;
+//! AiNodeSetArray(&options, AtString::try_from("outputs")?, arnold_array![AI_TYPE_STRING; "RGBA RGBA filter memory"]);
+//! AiRender(&universe, AI_RENDER_MODE_CAMERA)?;
+//! let framebuffer = framebuffer(&driver).unwrap();
+//! let center = framebuffer.index(framebuffer.resolution().0 / 2, framebuffer.resolution().1 / 2).unwrap();
+//! println!("center pixel: {:?}", framebuffer.rgba("RGBA").unwrap()[center]);
+//! ```
+
 #![allow(non_upper_case_globals)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 
+use ai_bbox::AtBBox2;
 use ai_bindings;
+use ai_bindings::{AtAOVSampleIterator, AtCommonMethods, AtDriverNodeMethods, AtList, AtNode, AtNodeEntry, AtOutputIterator};
+use ai_color::{AtRGB, AtRGBA};
+use error::ArnoldError;
+use ai_node_entry::{drop_instance, guard_method, install_methods, instance, set_instance, NodeEntry, NodeType};
+use ai_nodes::Node;
+use ai_params::ParamType;
+use ai_render::Session;
+use ai_string::from_c_str;
+use ai_vector::{AtVector, AtVector2};
+#[cfg(test)]
+use ai_nodes::{AiNode, AiNodeSetArray};
+#[cfg(test)]
+use ai_render::{AiRender, AI_RENDER_MODE_CAMERA};
+#[cfg(test)]
+use ai_string::AtString;
+#[cfg(test)]
+use ai_universe::Universe;
 
 use std::ffi::{CStr, CString};
-use std::ptr;
+use std::os::raw::{c_char, c_int, c_void};
+use std::sync::{Mutex, PoisonError};
+use std::{ptr, slice};
+#[cfg(test)]
+use std::convert::TryFrom;
 
 /// Driver's driver_extension method declaration.
 ///
 /// # Returns
-/// a NULL-terminated array of filename extensions which this driver is capable of writing. For example, a 'jpeg' driver might return the following array: { "jpeg", "jpg", NULL }
-pub fn AiDriverExtension(node_entry: *const ai_bindings::AtNodeEntry) -> Vec<&'static str> {
-    let char_ptr = unsafe { ai_bindings::AiDriverExtension(node_entry) };
-    let mut p = char_ptr;
+/// the filename extensions which this driver is capable of writing. For example, a 'jpeg' driver might return `["jpeg", "jpg"]`. Nodes that are not drivers return no extensions. Returns an error if an extension is not valid UTF-8.
+pub fn AiDriverExtension<'s>(node_entry: &NodeEntry<'s>) -> Result<Vec<&'s str>, ArnoldError> {
+    let mut p = unsafe { ai_bindings::AiDriverExtension(node_entry.as_ptr()) };
     let mut vec = Vec::new();
+    if p.is_null() {
+        return Ok(vec);
+    }
     unsafe {
-        while *p != ptr::null() {
-            let s = CStr::from_ptr(*p);
-            vec.push(s.to_str().unwrap());
+        while let Some(extension) = from_c_str(*p)? {
+            vec.push(extension);
             p = p.offset(1);
         }
     }
-    vec
+    Ok(vec)
 }
 
 /// Get correct driver node type from an extension.
 ///
 /// # Parameters
+/// * `_session` - the active session
 /// * `extension` - a string containing the file extension, e.g. "tiff"
 /// # Returns
-/// the [`AtNodeEntry`](ai_bindings::AtNodeEntry) corresponding to extension
-pub fn AiFindDriverType(extension: &str) -> *const ai_bindings::AtNodeEntry {
-    unsafe { ai_bindings::AiFindDriverType(CString::new(extension).unwrap().as_ptr()) }
+/// the driver node entry corresponding to extension, or [`ArnoldError::DriverNotFound`](../error/enum.ArnoldError.html) if no driver writes such files
+pub fn AiFindDriverType<'s>(_session: &'s Session, extension: &str) -> Result<NodeEntry<'s>, ArnoldError> {
+    let c_extension = CString::new(extension)?;
+    unsafe { NodeEntry::from_raw(ai_bindings::AiFindDriverType(c_extension.as_ptr())) }.ok_or_else(|| {
+        ArnoldError::DriverNotFound {
+            extension: extension.to_owned(),
+        }
+    })
+}
+
+/// An output handled by a driver, as declared in the options "outputs" parameter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DriverOutput<'a> {
+    name: &'a CStr,
+    /// Pixel type of the AOV
+    pub pixel_type: ParamType,
+}
+
+impl<'a> DriverOutput<'a> {
+    /// AOV name, such as "RGBA", or an error if it is not valid UTF-8.
+    pub fn name(&self) -> Result<&'a str, ArnoldError> {
+        Ok(self.name.to_str()?)
+    }
+}
+
+/// Region of the image covered by a bucket, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bucket {
+    /// X coordinate of the top-left pixel
+    pub x: i32,
+    /// Y coordinate of the top-left pixel
+    pub y: i32,
+    /// Width in pixels
+    pub width: i32,
+    /// Height in pixels
+    pub height: i32,
+}
+
+impl Bucket {
+    /// Number of pixels in the bucket.
+    pub fn len(&self) -> usize {
+        self.width.max(0) as usize * self.height.max(0) as usize
+    }
+
+    /// Check if the bucket holds no pixels.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Pixels of one AOV in a bucket, row by row from the top-left pixel.
+#[derive(Debug, Clone, Copy)]
+pub enum AovPixels<'a> {
+    RGBA(&'a [AtRGBA]),
+    RGB(&'a [AtRGB]),
+    Float(&'a [f32]),
+    Vector(&'a [AtVector]),
+    Vector2(&'a [AtVector2]),
+    Int(&'a [i32]),
+    UInt(&'a [u32]),
+    /// A pixel type without a typed view, such as AI_TYPE_POINTER
+    Unsupported(ParamType),
+}
+
+impl<'a> AovPixels<'a> {
+    /// Wrap raw bucket data of the given pixel type.
+    ///
+    /// # Safety
+    /// `data` must be NULL or point to `len` pixels of `pixel_type` that stay valid for `'a`.
+    unsafe fn from_raw(pixel_type: ParamType, data: *const c_void, len: usize) -> AovPixels<'a> {
+        unsafe fn pixels<'a, T>(data: *const c_void, len: usize) -> &'a [T] {
+            if data.is_null() {
+                &[]
+            } else {
+                slice::from_raw_parts(data as *const T, len)
+            }
+        }
+        match pixel_type {
+            ParamType::RGBA => AovPixels::RGBA(pixels(data, len)),
+            ParamType::RGB => AovPixels::RGB(pixels(data, len)),
+            ParamType::Float => AovPixels::Float(pixels(data, len)),
+            ParamType::Vector => AovPixels::Vector(pixels(data, len)),
+            ParamType::Vector2 => AovPixels::Vector2(pixels(data, len)),
+            ParamType::Int => AovPixels::Int(pixels(data, len)),
+            ParamType::UInt => AovPixels::UInt(pixels(data, len)),
+            other => AovPixels::Unsupported(other),
+        }
+    }
+
+    /// Pixel type of the AOV.
+    pub fn pixel_type(&self) -> ParamType {
+        match *self {
+            AovPixels::RGBA(_) => ParamType::RGBA,
+            AovPixels::RGB(_) => ParamType::RGB,
+            AovPixels::Float(_) => ParamType::Float,
+            AovPixels::Vector(_) => ParamType::Vector,
+            AovPixels::Vector2(_) => ParamType::Vector2,
+            AovPixels::Int(_) => ParamType::Int,
+            AovPixels::UInt(_) => ParamType::UInt,
+            AovPixels::Unsupported(pixel_type) => pixel_type,
+        }
+    }
+}
+
+/// The pixels of one output in a bucket, see [`Driver::write_bucket`](trait.Driver.html#tymethod.write_bucket).
+#[derive(Debug, Clone, Copy)]
+pub struct AovBucket<'a> {
+    name: &'a CStr,
+    /// Bucket pixels
+    pub pixels: AovPixels<'a>,
+}
+
+impl<'a> AovBucket<'a> {
+    /// AOV name, such as "RGBA", or an error if it is not valid UTF-8.
+    pub fn name(&self) -> Result<&'a str, ArnoldError> {
+        Ok(self.name.to_str()?)
+    }
+}
+
+/// An output driver implemented in Rust.
+///
+/// Drivers follow the [lifecycle of node types implemented in Rust](../ai_node_entry/index.html#node-types-implemented-in-rust), except that every method takes `&mut self` and is called with the driver locked, since Arnold may call [`prepare_bucket`](#method.prepare_bucket) from several render threads.
+pub trait Driver: Send + Sized + 'static {
+    /// Whether a single driver node can write several outputs.
+    const MULTIPLE_OUTPUTS: bool = true;
+
+    /// Create the driver for a new driver node.
+    fn initialize(node: &Node) -> Self;
+
+    /// Check if the driver can write AOVs of this pixel type, by default the types with an [`AovPixels`](enum.AovPixels.html) view.
+    fn supports_pixel_type(pixel_type: ParamType) -> bool {
+        matches!(
+            pixel_type,
+            ParamType::RGBA | ParamType::RGB | ParamType::Float | ParamType::Vector | ParamType::Vector2 | ParamType::Int | ParamType::UInt
+        )
+    }
+
+    /// Called when the render starts.
+    ///
+    /// # Parameters
+    /// * `outputs` - the outputs written by this driver
+    /// * `display_window` - full image resolution
+    /// * `data_window` - region of the image that is rendered, which differs from the display window for crop or overscan renders
+    /// * `bucket_size` - size in pixels of the buckets
+    fn open(&mut self, outputs: &[DriverOutput], display_window: AtBBox2, data_window: AtBBox2, bucket_size: i32);
+
+    /// Called before a bucket is rendered.
+    fn prepare_bucket(&mut self, _bucket: Bucket) {}
+
+    /// Called with the pixels of each rendered bucket, one [`AovBucket`](struct.AovBucket.html) per output.
+    fn write_bucket(&mut self, bucket: Bucket, aovs: &[AovBucket]);
+
+    /// Called when the render is done.
+    fn close(&mut self);
+}
+
+/// Install a node type for a Rust [`Driver`](trait.Driver.html).
+///
+/// Nodes of the new type can then be created with AiNode() and referenced from the options "outputs" parameter.
+/// # Parameters
+/// * `session` - the active session
+/// * `name` - name of the new driver node type, such as "driver_preview"
+/// # Returns
+/// the installed node entry, or an error, see [node types implemented in Rust](../ai_node_entry/index.html#node-types-implemented-in-rust)
+pub fn install_driver<'s, D: Driver>(session: &'s Session, name: &str) -> Result<NodeEntry<'s>, ArnoldError> {
+    let cmethods = AtCommonMethods {
+        PluginInitialize: None,
+        PluginCleanup: None,
+        Parameters: Some(driver_parameters),
+        Initialize: Some(driver_initialize::<D>),
+        Update: Some(driver_update),
+        Finish: Some(driver_finish::<D>),
+    };
+    let dmethods: &'static AtDriverNodeMethods = Box::leak(Box::new(AtDriverNodeMethods {
+        DriverExtension: Some(driver_extension),
+        DriverSupportsPixelType: Some(driver_supports_pixel_type::<D>),
+        DriverOpen: Some(driver_open::<D>),
+        DriverNeedsBucket: Some(driver_needs_bucket),
+        DriverPrepareBucket: Some(driver_prepare_bucket::<D>),
+        DriverProcessBucket: Some(driver_process_bucket),
+        DriverWriteBucket: Some(driver_write_bucket::<D>),
+        DriverClose: Some(driver_close::<D>),
+    }));
+    install_methods(session, NodeType::DRIVER, ParamType::Undefined, name, cmethods, dmethods as *const AtDriverNodeMethods as *const c_void)
+}
+
+/// NULL-terminated list of file extensions, Rust drivers do not write files.
+static NO_EXTENSIONS: [usize; 1] = [0];
+
+/// Lock the driver stored in the node's local data, and run `f` with it inside [`guard_method`](../ai_node_entry/fn.guard_method.html).
+///
+/// `f` also builds the arguments of the trait method, so nothing that can panic runs outside the guard.
+unsafe fn with_driver<D: Driver, R, F: FnOnce(&mut D) -> R>(node: *const AtNode, method: &str, default: R, f: F) -> R {
+    match instance::<Mutex<D>>(node) {
+        Some(driver) => guard_method(method, default, || f(&mut driver.lock().unwrap_or_else(PoisonError::into_inner))),
+        None => default,
+    }
+}
+
+/// Collect the outputs of a driver, with their bucket data.
+unsafe fn outputs<'a>(iterator: *mut AtOutputIterator) -> Vec<(DriverOutput<'a>, *const c_void)> {
+    let mut outputs = Vec::new();
+    ai_bindings::AiOutputIteratorReset(iterator);
+    let mut name: *const c_char = ptr::null();
+    let mut pixel_type: c_int = 0;
+    let mut data: *const c_void = ptr::null();
+    while ai_bindings::AiOutputIteratorGetNext(iterator, &mut name, &mut pixel_type, &mut data) {
+        let output = DriverOutput {
+            name: if name.is_null() { Default::default() } else { CStr::from_ptr(name) },
+            pixel_type: ParamType::from_code(pixel_type as u8),
+        };
+        outputs.push((output, data));
+    }
+    outputs
+}
+
+unsafe extern "C" fn driver_parameters(_params: *mut AtList, _nentry: *mut AtNodeEntry) {}
+
+unsafe extern "C" fn driver_initialize<D: Driver>(node: *mut AtNode) {
+    ai_bindings::AiDriverInitialize(node, D::MULTIPLE_OUTPUTS);
+    let driver = guard_method("driver initialize", None, || Node::from_raw(node).map(|node| D::initialize(&node)));
+    if let Some(driver) = driver {
+        set_instance(node, Mutex::new(driver));
+    }
+}
+
+unsafe extern "C" fn driver_update(_node: *mut AtNode) {}
+
+unsafe extern "C" fn driver_finish<D: Driver>(node: *mut AtNode) {
+    guard_method("driver finish", (), || drop_instance::<Mutex<D>>(node));
+}
+
+unsafe extern "C" fn driver_extension() -> *mut *const c_char {
+    NO_EXTENSIONS.as_ptr() as *mut *const c_char
+}
+
+unsafe extern "C" fn driver_supports_pixel_type<D: Driver>(_node: *const AtNode, pixel_type: u8) -> bool {
+    guard_method("driver supports_pixel_type", false, || D::supports_pixel_type(ParamType::from_code(pixel_type)))
+}
+
+unsafe extern "C" fn driver_open<D: Driver>(node: *mut AtNode, iterator: *mut AtOutputIterator, display_window: AtBBox2, data_window: AtBBox2, bucket_size: c_int) {
+    with_driver(node, "driver open", (), |driver: &mut D| {
+        let outputs: Vec<DriverOutput> = outputs(iterator).into_iter().map(|(output, _)| output).collect();
+        driver.open(&outputs, display_window, data_window, bucket_size)
+    })
+}
+
+unsafe extern "C" fn driver_needs_bucket(_node: *mut AtNode, _bucket_xo: c_int, _bucket_yo: c_int, _bucket_size_x: c_int, _bucket_size_y: c_int, _tid: u16) -> bool {
+    true
+}
+
+unsafe extern "C" fn driver_prepare_bucket<D: Driver>(node: *mut AtNode, bucket_xo: c_int, bucket_yo: c_int, bucket_size_x: c_int, bucket_size_y: c_int, _tid: u16) {
+    let bucket = Bucket {
+        x: bucket_xo,
+        y: bucket_yo,
+        width: bucket_size_x,
+        height: bucket_size_y,
+    };
+    with_driver(node, "driver prepare_bucket", (), |driver: &mut D| driver.prepare_bucket(bucket))
+}
+
+unsafe extern "C" fn driver_process_bucket(_node: *mut AtNode, _iterator: *mut AtOutputIterator, _sample_iterator: *mut AtAOVSampleIterator, _bucket_xo: c_int, _bucket_yo: c_int, _bucket_size_x: c_int, _bucket_size_y: c_int, _tid: u16) {}
+
+unsafe extern "C" fn driver_write_bucket<D: Driver>(node: *mut AtNode, iterator: *mut AtOutputIterator, _sample_iterator: *mut AtAOVSampleIterator, bucket_xo: c_int, bucket_yo: c_int, bucket_size_x: c_int, bucket_size_y: c_int) {
+    let bucket = Bucket {
+        x: bucket_xo,
+        y: bucket_yo,
+        width: bucket_size_x,
+        height: bucket_size_y,
+    };
+    with_driver(node, "driver write_bucket", (), |driver: &mut D| {
+        let aovs: Vec<AovBucket> = outputs(iterator)
+            .into_iter()
+            .map(|(output, data)| AovBucket {
+                name: output.name,
+                pixels: AovPixels::from_raw(output.pixel_type, data, bucket.len()),
+            })
+            .collect();
+        driver.write_bucket(bucket, &aovs)
+    })
+}
+
+unsafe extern "C" fn driver_close<D: Driver>(node: *mut AtNode, _iterator: *mut AtOutputIterator) {
+    with_driver(node, "driver close", (), |driver: &mut D| driver.close())
+}
+
+/// Name of the built-in memory driver, installed when a [`Session`](../ai_render/struct.Session.html) starts.
+pub const DRIVER_MEMORY: &str = "driver_memory";
+
+/// Pixels of one AOV, owned by a [`Framebuffer`](struct.Framebuffer.html).
+#[derive(Debug, Clone)]
+pub enum AovData {
+    RGBA(Vec<AtRGBA>),
+    RGB(Vec<AtRGB>),
+    Float(Vec<f32>),
+    Vector(Vec<AtVector>),
+    Vector2(Vec<AtVector2>),
+    Int(Vec<i32>),
+    UInt(Vec<u32>),
+    /// A pixel type without a typed view, no pixels are stored
+    Unsupported(ParamType),
+}
+
+impl AovData {
+    /// Create `len` zeroed pixels of the given type.
+    fn new(pixel_type: ParamType, len: usize) -> AovData {
+        match pixel_type {
+            ParamType::RGBA => AovData::RGBA(vec![AtRGBA { r: 0.0, g: 0.0, b: 0.0, a: 0.0 }; len]),
+            ParamType::RGB => AovData::RGB(vec![AtRGB { r: 0.0, g: 0.0, b: 0.0 }; len]),
+            ParamType::Float => AovData::Float(vec![0.0; len]),
+            ParamType::Vector => AovData::Vector(vec![AtVector { x: 0.0, y: 0.0, z: 0.0 }; len]),
+            ParamType::Vector2 => AovData::Vector2(vec![AtVector2 { x: 0.0, y: 0.0 }; len]),
+            ParamType::Int => AovData::Int(vec![0; len]),
+            ParamType::UInt => AovData::UInt(vec![0; len]),
+            other => AovData::Unsupported(other),
+        }
+    }
+
+    /// Pixel type of the AOV.
+    pub fn pixel_type(&self) -> ParamType {
+        match *self {
+            AovData::RGBA(_) => ParamType::RGBA,
+            AovData::RGB(_) => ParamType::RGB,
+            AovData::Float(_) => ParamType::Float,
+            AovData::Vector(_) => ParamType::Vector,
+            AovData::Vector2(_) => ParamType::Vector2,
+            AovData::Int(_) => ParamType::Int,
+            AovData::UInt(_) => ParamType::UInt,
+            AovData::Unsupported(pixel_type) => pixel_type,
+        }
+    }
+}
+
+/// An AOV stored by a [`Framebuffer`](struct.Framebuffer.html).
+#[derive(Debug, Clone)]
+pub struct AovBuffer {
+    /// AOV name, such as "RGBA"
+    pub name: String,
+    /// Pixels of the data window, row by row from the top-left pixel
+    pub data: AovData,
+}
+
+/// Pixels rendered into a `driver_memory` node, see [`framebuffer`](fn.framebuffer.html).
+///
+/// Each AOV holds the pixels of the data window, row by row, so pixel `(x, y)` in image coordinates is found with [`index`](#method.index).
+#[derive(Debug, Clone)]
+pub struct Framebuffer {
+    display_window: AtBBox2,
+    data_window: AtBBox2,
+    aovs: Vec<AovBuffer>,
+}
+
+impl Framebuffer {
+    fn new(outputs: &[DriverOutput], display_window: AtBBox2, data_window: AtBBox2) -> Framebuffer {
+        let len = window_width(&data_window) * window_height(&data_window);
+        Framebuffer {
+            display_window: display_window,
+            data_window: data_window,
+            aovs: outputs
+                .iter()
+                .map(|output| AovBuffer {
+                    name: output.name.to_string_lossy().into_owned(),
+                    data: AovData::new(output.pixel_type, len),
+                })
+                .collect(),
+        }
+    }
+
+    /// Full image resolution.
+    pub fn display_window(&self) -> AtBBox2 {
+        self.display_window
+    }
+
+    /// Region of the image stored in the buffers.
+    pub fn data_window(&self) -> AtBBox2 {
+        self.data_window
+    }
+
+    /// Width and height of the display window, i.e. the options xres and yres.
+    pub fn resolution(&self) -> (i32, i32) {
+        (window_width(&self.display_window) as i32, window_height(&self.display_window) as i32)
+    }
+
+    /// All AOVs, in the order of the outputs.
+    pub fn aovs(&self) -> &[AovBuffer] {
+        &self.aovs
+    }
+
+    /// Pixels of the first AOV with this name.
+    pub fn aov(&self, name: &str) -> Option<&AovData> {
+        self.aovs.iter().find(|aov| aov.name == name).map(|aov| &aov.data)
+    }
+
+    /// Pixels of an RGBA AOV.
+    pub fn rgba(&self, name: &str) -> Option<&[AtRGBA]> {
+        match self.aov(name) {
+            Some(AovData::RGBA(pixels)) => Some(pixels),
+            _ => None,
+        }
+    }
+
+    /// Pixels of a float AOV, such as "Z".
+    pub fn float(&self, name: &str) -> Option<&[f32]> {
+        match self.aov(name) {
+            Some(AovData::Float(pixels)) => Some(pixels),
+            _ => None,
+        }
+    }
+
+    /// Pixels of a vector AOV, such as "N" or "P".
+    pub fn vector(&self, name: &str) -> Option<&[AtVector]> {
+        match self.aov(name) {
+            Some(AovData::Vector(pixels)) => Some(pixels),
+            _ => None,
+        }
+    }
+
+    /// Index in the AOV pixels of pixel `(x, y)`, in image coordinates.
+    ///
+    /// # Returns
+    /// the pixel index, or `None` if the pixel is outside the data window
+    pub fn index(&self, x: i32, y: i32) -> Option<usize> {
+        let window = &self.data_window;
+        if x < window.minx || x > window.maxx || y < window.miny || y > window.maxy {
+            return None;
+        }
+        Some((y - window.miny) as usize * window_width(window) + (x - window.minx) as usize)
+    }
+
+    /// Copy the pixels of a bucket into the buffers.
+    fn write_bucket(&mut self, bucket: Bucket, aovs: &[AovBucket]) {
+        let data_window = self.data_window;
+        for (buffer, aov) in self.aovs.iter_mut().zip(aovs) {
+            match (&mut buffer.data, aov.pixels) {
+                (AovData::RGBA(dst), AovPixels::RGBA(src)) => copy_bucket(dst, src, bucket, &data_window),
+                (AovData::RGB(dst), AovPixels::RGB(src)) => copy_bucket(dst, src, bucket, &data_window),
+                (AovData::Float(dst), AovPixels::Float(src)) => copy_bucket(dst, src, bucket, &data_window),
+                (AovData::Vector(dst), AovPixels::Vector(src)) => copy_bucket(dst, src, bucket, &data_window),
+                (AovData::Vector2(dst), AovPixels::Vector2(src)) => copy_bucket(dst, src, bucket, &data_window),
+                (AovData::Int(dst), AovPixels::Int(src)) => copy_bucket(dst, src, bucket, &data_window),
+                (AovData::UInt(dst), AovPixels::UInt(src)) => copy_bucket(dst, src, bucket, &data_window),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn window_width(window: &AtBBox2) -> usize {
+    (window.maxx - window.minx + 1).max(0) as usize
+}
+
+fn window_height(window: &AtBBox2) -> usize {
+    (window.maxy - window.miny + 1).max(0) as usize
+}
+
+/// Copy bucket rows into a buffer covering `window`, skipping the pixels outside of it.
+fn copy_bucket<T: Copy>(dst: &mut [T], src: &[T], bucket: Bucket, window: &AtBBox2) {
+    let width = bucket.width.max(0) as usize;
+    let x0 = bucket.x.max(window.minx);
+    let x1 = (bucket.x + bucket.width - 1).min(window.maxx);
+    if x0 > x1 {
+        return;
+    }
+    for (row, pixels) in src.chunks(width.max(1)).enumerate() {
+        let y = bucket.y + row as i32;
+        if y < window.miny || y > window.maxy {
+            continue;
+        }
+        let offset = (y - window.miny) as usize * window_width(window);
+        let src_row = &pixels[(x0 - bucket.x) as usize..=(x1 - bucket.x) as usize];
+        dst[offset + (x0 - window.minx) as usize..=offset + (x1 - window.minx) as usize].copy_from_slice(src_row);
+    }
+}
+
+/// The built-in `driver_memory`.
+struct MemoryDriver {
+    framebuffer: Option<Framebuffer>,
+}
+
+impl Driver for MemoryDriver {
+    fn initialize(_node: &Node) -> MemoryDriver {
+        MemoryDriver { framebuffer: None }
+    }
+
+    fn open(&mut self, outputs: &[DriverOutput], display_window: AtBBox2, data_window: AtBBox2, _bucket_size: i32) {
+        self.framebuffer = Some(Framebuffer::new(outputs, display_window, data_window));
+    }
+
+    fn write_bucket(&mut self, bucket: Bucket, aovs: &[AovBucket]) {
+        if let Some(ref mut framebuffer) = self.framebuffer {
+            framebuffer.write_bucket(bucket, aovs);
+        }
+    }
+
+    fn close(&mut self) {}
+}
+
+/// Install the drivers implemented by this crate, called when a session starts.
+pub(crate) fn install_builtin_drivers(session: &Session) -> Result<(), ArnoldError> {
+    install_driver::<MemoryDriver>(session, DRIVER_MEMORY).map(|_| ())
+}
+
+/// Return a copy of the pixels rendered into a `driver_memory` node.
+///
+/// # Parameters
+/// * `driver` - a node created from [`DRIVER_MEMORY`](constant.DRIVER_MEMORY.html)
+/// # Returns
+/// the framebuffer of the last render, or `None` if `driver` is not a memory driver or has not been rendered into
+pub fn framebuffer(driver: &Node) -> Option<Framebuffer> {
+    if !driver.is(DRIVER_MEMORY) {
+        return None;
+    }
+    let memory = unsafe { instance::<Mutex<MemoryDriver>>(driver.as_ptr())? };
+    let memory = memory.lock().unwrap_or_else(PoisonError::into_inner);
+    memory.framebuffer.clone()
+}
+
+/// Set up the options of `universe` to render through `camera` into a new `driver_memory` node.
+#[cfg(test)]
+pub(crate) fn memory_output<'u>(universe: &'u Universe, camera: &Node<'u>, xres: i32, yres: i32) -> Node<'u> {
+    let options = universe.options();
+    options.set("xres", xres).unwrap();
+    options.set("yres", yres).unwrap();
+    options.set("AA_samples", 1).unwrap();
+    options.set("camera", camera).unwrap();
+    AiNode(universe, "box_filter", Some("memory_filter"), None).unwrap();
+    let driver = AiNode(universe, DRIVER_MEMORY, Some("memory_driver"), None).unwrap();
+    AiNodeSetArray(
+        &options,
+        AtString::try_from("outputs").unwrap(),
+        arnold_array![AI_TYPE_STRING; "RGBA RGBA memory_filter memory_driver"],
+    );
+    driver
+}
+
+/// The scene shared by the render tests: a camera rendering into a 16x16 `driver_memory` node.
+#[cfg(test)]
+pub(crate) struct TestScene<'u> {
+    universe: &'u Universe<'u>,
+    driver: Node<'u>,
+}
+
+#[cfg(test)]
+impl<'u> TestScene<'u> {
+    /// Set up the options of `universe` to render through `camera`, see [`memory_output`](fn.memory_output.html).
+    pub(crate) fn new(universe: &'u Universe<'u>, camera: &Node<'u>) -> TestScene<'u> {
+        TestScene {
+            universe: universe,
+            driver: memory_output(universe, camera, 16, 16),
+        }
+    }
+
+    /// Add a sphere, giving the radius explicitly since Arnold defaults it to 0.5.
+    pub(crate) fn sphere(&self, name: &str, center: AtVector, radius: f32) -> Node<'u> {
+        let sphere = AiNode(self.universe, "sphere", Some(name), None).unwrap();
+        sphere.set("center", center).unwrap();
+        sphere.set("radius", radius).unwrap();
+        sphere
+    }
+
+    /// Render the scene and return the pixels of the memory driver.
+    pub(crate) fn render(&self) -> Framebuffer {
+        AiRender(self.universe, AI_RENDER_MODE_CAMERA).unwrap();
+        self.framebuffer()
+    }
+
+    /// The pixels of the last render of the scene.
+    pub(crate) fn framebuffer(&self) -> Framebuffer {
+        framebuffer(&self.driver).unwrap()
+    }
+}
+
+#[cfg(test)]
+impl Framebuffer {
+    /// The RGBA pixel at `(x, y)`, for the scenes rendered by [`TestScene`](struct.TestScene.html).
+    pub(crate) fn pixel(&self, x: i32, y: i32) -> AtRGBA {
+        self.rgba("RGBA").unwrap()[self.index(x, y).unwrap()]
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ai_msg::{AiMsgSetConsoleFlags, AI_LOG_NONE};
-    use ai_render::{AiBegin, AiEnd, AI_SESSION_BATCH};
+    use ai_msg::AI_LOG_NONE;
+    use ai_render::AI_SESSION_BATCH;
     use ai_node_entry::{AiNodeEntryLookUp, AiNodeEntryGetNameAtString};
-    use ai_string::AtString;
+
+    /// Pixels written by the last `CountingDriver`, and the data window it was opened with.
+    static WRITTEN: Mutex<Option<(usize, AtBBox2)>> = Mutex::new(None);
+
+    struct CountingDriver {
+        pixels: usize,
+        data_window: Option<AtBBox2>,
+    }
+
+    impl Driver for CountingDriver {
+        fn initialize(_node: &Node) -> CountingDriver {
+            CountingDriver { pixels: 0, data_window: None }
+        }
+        fn open(&mut self, outputs: &[DriverOutput], _display_window: AtBBox2, data_window: AtBBox2, _bucket_size: i32) {
+            assert_eq!(outputs.len(), 1);
+            assert_eq!(outputs[0].name(), Ok("RGBA"));
+            assert_eq!(outputs[0].pixel_type, ParamType::RGBA);
+            self.data_window = Some(data_window);
+        }
+        fn write_bucket(&mut self, bucket: Bucket, aovs: &[AovBucket]) {
+            if let AovPixels::RGBA(pixels) = aovs[0].pixels {
+                assert_eq!(pixels.len(), bucket.len());
+                self.pixels += pixels.len();
+            }
+        }
+        fn close(&mut self) {
+            *WRITTEN.lock().unwrap() = self.data_window.map(|window| (self.pixels, window));
+        }
+    }
+    #[test]
+    fn rust_driver() {
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let entry = install_driver::<CountingDriver>(&session, "driver_counting").unwrap();
+        assert_eq!(entry.node_type(), NodeType::DRIVER);
+        assert!(install_driver::<CountingDriver>(&session, "bad\0name").is_err());
+        let universe = Universe::default(&session);
+        let options = universe.options();
+        options.set("xres", 8).unwrap();
+        options.set("yres", 4).unwrap();
+        let camera = AiNode(&universe, "persp_camera", Some("camera"), None).unwrap();
+        options.set("camera", &camera).unwrap();
+        AiNode(&universe, "box_filter", Some("filter"), None).unwrap();
+        AiNode(&universe, "driver_counting", Some("driver"), None).unwrap();
+        AiNodeSetArray(&options, AtString::try_from("outputs").unwrap(), arnold_array![AI_TYPE_STRING; "RGBA RGBA filter driver"]);
+        AiRender(&universe, AI_RENDER_MODE_CAMERA).unwrap();
+        let (pixels, data_window) = WRITTEN.lock().unwrap().unwrap();
+        assert_eq!(pixels, 8 * 4);
+        assert_eq!(data_window, AtBBox2 { minx: 0, miny: 0, maxx: 7, maxy: 3 });
+    }
+    #[test]
+    fn memory_driver() {
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let universe = Universe::default(&session);
+        let camera = AiNode(&universe, "persp_camera", Some("camera"), None).unwrap();
+        let driver = memory_output(&universe, &camera, 6, 3);
+        assert!(framebuffer(&driver).is_none());
+        assert!(framebuffer(&camera).is_none());
+        AiRender(&universe, AI_RENDER_MODE_CAMERA).unwrap();
+        let framebuffer = framebuffer(&driver).unwrap();
+        assert_eq!(framebuffer.resolution(), (6, 3));
+        assert_eq!(framebuffer.data_window(), AtBBox2 { minx: 0, miny: 0, maxx: 5, maxy: 2 });
+        assert_eq!(framebuffer.aovs().len(), 1);
+        let pixels = framebuffer.rgba("RGBA").unwrap();
+        assert_eq!(pixels.len(), 6 * 3);
+        // An empty scene renders a transparent black image.
+        assert!(pixels.iter().all(|p| p.a == 0.0 && p.r == 0.0));
+        assert_eq!(framebuffer.index(5, 2), Some(17));
+        assert_eq!(framebuffer.index(6, 0), None);
+        assert!(framebuffer.float("RGBA").is_none());
+    }
+    #[test]
+    fn copy_buckets() {
+        let window = AtBBox2 { minx: -1, miny: -1, maxx: 2, maxy: 2 };
+        let mut dst = vec![0; 16];
+        let bucket = Bucket { x: 1, y: 1, width: 2, height: 2 };
+        copy_bucket(&mut dst, &[1, 2, 3, 4], bucket, &window);
+        let bucket = Bucket { x: -2, y: -2, width: 2, height: 2 };
+        copy_bucket(&mut dst, &[5, 6, 7, 8], bucket, &window);
+        assert_eq!(dst, vec![8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 2, 0, 0, 3, 4]);
+    }
     #[test]
     fn driver_extensions() {
-        AiBegin(AI_SESSION_BATCH);
-        AiMsgSetConsoleFlags(AI_LOG_NONE);
-        let node_entry = AiNodeEntryLookUp(AtString::from("driver_exr"));
-        let ext = AiDriverExtension(node_entry);
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let node_entry = AiNodeEntryLookUp(&session, AtString::try_from("driver_exr").unwrap()).unwrap();
+        let ext = AiDriverExtension(&node_entry).unwrap();
         assert_eq!(&["exr"], &ext[..]);
+        let sphere = AiNodeEntryLookUp(&session, AtString::try_from("sphere").unwrap()).unwrap();
+        assert!(AiDriverExtension(&sphere).unwrap().is_empty());
         println!("extension: {:?}", ext);
-        AiEnd();
     }
     #[test]
     fn find_driver() {
-        AiBegin(AI_SESSION_BATCH);
-        AiMsgSetConsoleFlags(AI_LOG_NONE);
-        let node_entry = AiFindDriverType("exr");
-        assert_eq!("driver_exr", AiNodeEntryGetNameAtString(node_entry).to_str());
-        println!("extension: {}", AiNodeEntryGetNameAtString(node_entry));
-        AiEnd();
-    }
-}
\ No newline at end of file
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let node_entry = AiFindDriverType(&session, "exr").unwrap();
+        assert_eq!(Ok("driver_exr"), AiNodeEntryGetNameAtString(&node_entry).to_str());
+        println!("extension: {}", AiNodeEntryGetNameAtString(&node_entry));
+        assert_eq!(
+            AiFindDriverType(&session, "no_such_extension"),
+            Err(ArnoldError::DriverNotFound {
+                extension: "no_such_extension".to_owned()
+            })
+        );
+    }
+}
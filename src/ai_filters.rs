@@ -0,0 +1,307 @@
+//! Filter API
+//!
+//! A pixel reconstruction filter can be implemented in Rust with the [`Filter`](trait.Filter.html) trait and installed with [`install_filter`](fn.install_filter.html):
+//! ```
+//! struct Triangle { width: f32 }
+//!
+//! impl Filter for Triangle {
+//!     fn output_type() -> ParamType { AI_TYPE_RGBA }
+//!     fn parameters(params: &mut ParamList) -> Result<(), ArnoldError> { params.float("width", 2.0).map(|_| ()) }
+//!     fn initialize(node: &Node) -> Triangle { Triangle { width: node.get("width").unwrap() } }
+//!     fn width(&self) -> f32 { self.width }
+//!     fn filter(&self, samples: &[FilterSample]) -> ParamValue {
+//!         let (mut sum, mut total) = (AtRGBA { r: 0.0, g: 0.0, b: 0.0, a: 0.0 }, 0.0);
+//!         for sample in samples {
+//!             let radius = (sample.offset.x * sample.offset.x + sample.offset.y * sample.offset.y).sqrt();
+//!             let weight = (1.0 - 2.0 * radius / self.width).max(0.0) * sample.inv_density;
+//!             let value = sample.get::<AtRGBA>().unwrap();
+//!             sum = AtRGBA { r: sum.r + value.r * weight, g: sum.g + value.g * weight, b: sum.b + value.b * weight, a: sum.a + value.a * weight };
+//!             total += weight;
+//!         }
+//!         ParamValue::RGBA(AtRGBA { r: sum.r / total, g: sum.g / total, b: sum.b / total, a: sum.a / total })
+//!     }
+//! }
+//!
+//! install_filter::<Triangle>(&session, "triangle_filter")?;
+//! ```
+
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+
+use ai_bindings;
+use ai_bindings::{AtAOVSampleIterator, AtCommonMethods, AtFilterNodeMethods, AtList, AtNode, AtNodeEntry, AtRGB, AtRGBA};
+use ai_node_entry::{drop_instance, guard_method, install_methods, instance, instance_mut, report, set_instance, NodeEntry, NodeType};
+use ai_nodes::Node;
+use ai_params::{ParamData, ParamList, ParamType, ParamValue};
+use ai_render::Session;
+use ai_vector::{AtVector, AtVector2};
+use error::ArnoldError;
+
+use std::cell::RefCell;
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+
+/// An AOV sample contributing to a pixel, see [`Filter::filter`](trait.Filter.html#tymethod.filter).
+#[derive(Debug, Clone)]
+pub struct FilterSample {
+    /// Offset of the sample from the pixel center, in pixels
+    pub offset: AtVector2,
+    /// Inverse of the sample density, to weight samples that are not evenly distributed
+    pub inv_density: f32,
+    /// The AOV value of the sample
+    pub value: ParamValue,
+}
+
+impl FilterSample {
+    /// The sample value as a Rust type, or `None` if the AOV holds another type.
+    pub fn get<T: ParamData>(&self) -> Option<T> {
+        T::from_value(self.value.clone())
+    }
+}
+
+/// A pixel filter implemented in Rust.
+///
+/// Filters follow the [lifecycle of node types implemented in Rust](../ai_node_entry/index.html#node-types-implemented-in-rust).
+pub trait Filter: Send + Sync + Sized + 'static {
+    /// Type of the filtered pixels, reported by AiNodeEntryGetOutputType().
+    fn output_type() -> ParamType;
+
+    /// Check if the filter can filter AOVs of this type, by default only AOVs of the [`output_type`](#tymethod.output_type).
+    fn accepts(input_type: ParamType) -> bool {
+        input_type == Self::output_type()
+    }
+
+    /// Declare the filter parameters.
+    fn parameters(_params: &mut ParamList) -> Result<(), ArnoldError> {
+        Ok(())
+    }
+
+    /// Create the filter for a new filter node.
+    fn initialize(node: &Node) -> Self;
+
+    /// Read the filter parameters again, [`width`](#tymethod.width) is queried right after.
+    fn update(&mut self, _node: &Node) {}
+
+    /// Filter width in pixels, which sets how far from the pixel center samples are gathered.
+    fn width(&self) -> f32;
+
+    /// Compute a pixel value from the samples around it.
+    ///
+    /// # Returns
+    /// the pixel value, which must be of the [`output_type`](#tymethod.output_type); values of another type are written as zero
+    fn filter(&self, samples: &[FilterSample]) -> ParamValue;
+}
+
+/// Install a node type for a Rust [`Filter`](trait.Filter.html).
+///
+/// The node entry output type is `F::output_type()`.
+/// # Parameters
+/// * `session` - the active session
+/// * `name` - name of the new filter node type
+/// # Returns
+/// the installed node entry, or an error, see [node types implemented in Rust](../ai_node_entry/index.html#node-types-implemented-in-rust)
+pub fn install_filter<'s, F: Filter>(session: &'s Session, name: &str) -> Result<NodeEntry<'s>, ArnoldError> {
+    let cmethods = AtCommonMethods {
+        PluginInitialize: None,
+        PluginCleanup: None,
+        Parameters: Some(filter_parameters::<F>),
+        Initialize: Some(filter_initialize::<F>),
+        Update: Some(filter_update::<F>),
+        Finish: Some(filter_finish::<F>),
+    };
+    let dmethods: &'static AtFilterNodeMethods = Box::leak(Box::new(AtFilterNodeMethods {
+        FilterOutputType: Some(filter_output_type::<F>),
+        FilterPixel: Some(filter_pixel::<F>),
+    }));
+    install_methods(session, NodeType::FILTER, F::output_type(), name, cmethods, dmethods as *const AtFilterNodeMethods as *const c_void)
+}
+
+thread_local! {
+    /// Sample buffer reused across pixels, so filtering does not allocate per pixel.
+    static SAMPLES: RefCell<Vec<FilterSample>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Read the current sample of an AOV sample iterator.
+unsafe fn sample_value(iterator: *const AtAOVSampleIterator, aov_type: ParamType) -> Option<ParamValue> {
+    Some(match aov_type {
+        ParamType::Boolean => ParamValue::Bool(ai_bindings::AiAOVSampleIteratorGetBool(iterator)),
+        ParamType::Int => ParamValue::Int(ai_bindings::AiAOVSampleIteratorGetInt(iterator)),
+        ParamType::UInt => ParamValue::UInt(ai_bindings::AiAOVSampleIteratorGetUInt(iterator)),
+        ParamType::Float => ParamValue::Float(ai_bindings::AiAOVSampleIteratorGetFlt(iterator)),
+        ParamType::RGB => ParamValue::RGB(ai_bindings::AiAOVSampleIteratorGetRGB(iterator)),
+        ParamType::RGBA => ParamValue::RGBA(ai_bindings::AiAOVSampleIteratorGetRGBA(iterator)),
+        ParamType::Vector => ParamValue::Vector(ai_bindings::AiAOVSampleIteratorGetVec(iterator)),
+        ParamType::Vector2 => ParamValue::Vector2(ai_bindings::AiAOVSampleIteratorGetVec2(iterator)),
+        _ => return None,
+    })
+}
+
+/// Write a filtered pixel, or zero if `value` is not of `data_type`.
+unsafe fn write_pixel(data_out: *mut c_void, data_type: ParamType, value: ParamValue) {
+    unsafe fn write<T>(data_out: *mut c_void, value: T) {
+        (data_out as *mut T).write_unaligned(value)
+    }
+    match value {
+        ParamValue::Byte(v) if data_type == ParamType::Byte => write(data_out, v),
+        ParamValue::Bool(v) if data_type == ParamType::Boolean => write(data_out, v),
+        ParamValue::Int(v) if data_type == ParamType::Int => write(data_out, v),
+        ParamValue::UInt(v) if data_type == ParamType::UInt => write(data_out, v),
+        ParamValue::Float(v) if data_type == ParamType::Float => write::<f32>(data_out, v),
+        ParamValue::RGB(v) if data_type == ParamType::RGB => write::<AtRGB>(data_out, v),
+        ParamValue::RGBA(v) if data_type == ParamType::RGBA => write::<AtRGBA>(data_out, v),
+        ParamValue::Vector(v) if data_type == ParamType::Vector => write::<AtVector>(data_out, v),
+        ParamValue::Vector2(v) if data_type == ParamType::Vector2 => write::<AtVector2>(data_out, v),
+        _ => ptr::write_bytes(data_out as *mut u8, 0, data_type.size()),
+    }
+}
+
+unsafe extern "C" fn filter_parameters<F: Filter>(params: *mut AtList, _nentry: *mut AtNodeEntry) {
+    guard_method("filter parameters", None, || report("filter parameters", F::parameters(&mut ParamList::from_raw(params))));
+}
+
+unsafe extern "C" fn filter_initialize<F: Filter>(node: *mut AtNode) {
+    ai_bindings::AiFilterInitialize(node, false, ptr::null_mut());
+    let filter = guard_method("filter initialize", None, || Node::from_raw(node).map(|node| F::initialize(&node)));
+    if let Some(filter) = filter {
+        set_instance(node, filter);
+    }
+}
+
+unsafe extern "C" fn filter_update<F: Filter>(node: *mut AtNode) {
+    if let (Some(filter), Some(wrapped)) = (instance_mut::<F>(node), Node::from_raw(node)) {
+        if let Some(width) = guard_method("filter update", None, || {
+            filter.update(&wrapped);
+            Some(filter.width())
+        }) {
+            ai_bindings::AiFilterUpdate(node, width);
+        }
+    }
+}
+
+unsafe extern "C" fn filter_finish<F: Filter>(node: *mut AtNode) {
+    guard_method("filter finish", (), || drop_instance::<F>(node));
+}
+
+unsafe extern "C" fn filter_output_type<F: Filter>(_node: *const AtNode, input_type: c_int) -> c_int {
+    let output_type = guard_method("filter output_type", ParamType::Undefined, || {
+        if F::accepts(ParamType::from_code(input_type as u8)) {
+            F::output_type()
+        } else {
+            ParamType::Undefined
+        }
+    });
+    output_type.code() as c_int
+}
+
+unsafe extern "C" fn filter_pixel<F: Filter>(node: *mut AtNode, iterator: *mut AtAOVSampleIterator, data_out: *mut c_void, data_type: u8) {
+    let data_type = ParamType::from_code(data_type);
+    let filter = match instance::<F>(node) {
+        Some(filter) => filter,
+        None => return write_pixel(data_out, data_type, ParamValue::Int(0)),
+    };
+    let aov_type = ParamType::from_code(ai_bindings::AiAOVSampleIteratorGetAOVType(iterator));
+    let value = SAMPLES.with(|samples| {
+        let mut samples = samples.borrow_mut();
+        samples.clear();
+        while ai_bindings::AiAOVSampleIteratorGetNext(iterator) {
+            if let Some(value) = sample_value(iterator, aov_type) {
+                samples.push(FilterSample {
+                    offset: ai_bindings::AiAOVSampleIteratorGetOffset(iterator),
+                    inv_density: ai_bindings::AiAOVSampleIteratorGetInvDensity(iterator),
+                    value: value,
+                });
+            }
+        }
+        guard_method("filter pixel", None, || Some(filter.filter(&samples)))
+    });
+    write_pixel(data_out, data_type, value.unwrap_or(ParamValue::Int(0)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_drivers::TestScene;
+    use ai_msg::AI_LOG_NONE;
+    use ai_node_entry::AiNodeEntryGetOutputType;
+    use ai_nodes::{AiNode, AiNodeSetArray};
+    use ai_render::AI_SESSION_BATCH;
+    use ai_string::AtString;
+    use ai_universe::Universe;
+    use std::convert::TryFrom;
+
+    /// Box filter of "width" pixels, scaling the pixel average by "gain".
+    struct Gain {
+        gain: f32,
+        width: f32,
+    }
+
+    impl Filter for Gain {
+        fn output_type() -> ParamType {
+            ParamType::RGBA
+        }
+        fn parameters(params: &mut ParamList) -> Result<(), ArnoldError> {
+            params.float("gain", 1.0)?;
+            params.float("width", 1.0)?;
+            Ok(())
+        }
+        fn initialize(node: &Node) -> Gain {
+            Gain { gain: node.get("gain").unwrap(), width: node.get("width").unwrap() }
+        }
+        fn update(&mut self, node: &Node) {
+            self.gain = node.get("gain").unwrap();
+            self.width = node.get("width").unwrap();
+        }
+        fn width(&self) -> f32 {
+            self.width
+        }
+        fn filter(&self, samples: &[FilterSample]) -> ParamValue {
+            let mut sum = AtRGBA { r: 0.0, g: 0.0, b: 0.0, a: 0.0 };
+            for value in samples.iter().filter_map(|sample| sample.get::<AtRGBA>()) {
+                sum = AtRGBA { r: sum.r + value.r, g: sum.g + value.g, b: sum.b + value.b, a: sum.a + value.a };
+            }
+            let scale = self.gain / samples.len().max(1) as f32;
+            ParamValue::RGBA(AtRGBA { r: sum.r * scale, g: sum.g * scale, b: sum.b * scale, a: sum.a * scale })
+        }
+    }
+    #[test]
+    fn rust_filter() {
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let entry = install_filter::<Gain>(&session, "gain_filter").unwrap();
+        assert_eq!(AiNodeEntryGetOutputType(&entry), ParamType::RGBA);
+        assert_eq!(entry.node_type(), NodeType::FILTER);
+
+        let universe = Universe::default(&session);
+        let camera = AiNode(&universe, "persp_camera", Some("camera"), None).unwrap();
+        let scene = TestScene::new(&universe, &camera);
+        scene.sphere("sphere", AtVector { x: 0.0, y: 0.0, z: -5.0 }, 1.0);
+        let filter = AiNode(&universe, "gain_filter", Some("gain"), None).unwrap();
+        filter.set("gain", 0.5).unwrap();
+        AiNodeSetArray(
+            &universe.options(),
+            AtString::try_from("outputs").unwrap(),
+            arnold_array![AI_TYPE_STRING; "RGBA RGBA gain memory_driver"],
+        );
+
+        // A one pixel filter only scales the coverage of each pixel by the gain.
+        let framebuffer = scene.render();
+        assert_eq!(framebuffer.pixel(8, 8).a, 0.5);
+        assert_eq!(framebuffer.pixel(0, 0).a, 0.0);
+
+        // A filter as wide as the image blurs the sphere, which reaches the corner and fades at the center.
+        filter.set("width", 16.0).unwrap();
+        let framebuffer = scene.render();
+        assert!(framebuffer.pixel(8, 8).a < 0.5);
+        assert!(framebuffer.pixel(0, 0).a > 0.0);
+    }
+    #[test]
+    fn filter_pixels() {
+        let mut pixel = [1.0f32; 4];
+        unsafe {
+            write_pixel(pixel.as_mut_ptr() as *mut c_void, ParamType::Float, ParamValue::Float(0.25));
+            assert_eq!(pixel, [0.25, 1.0, 1.0, 1.0]);
+            write_pixel(pixel.as_mut_ptr() as *mut c_void, ParamType::RGBA, ParamValue::Float(0.25));
+        }
+        assert_eq!(pixel, [0.0; 4]);
+    }
+}
@@ -3,6 +3,7 @@
 #![allow(non_snake_case)]
 
 use ai_bindings;
+use error::ArnoldError;
 
 use std::ffi::{CString};
 
@@ -11,54 +12,112 @@ pub const AI_SEVERITY_WARNING: u32 = 1;
 pub const AI_SEVERITY_ERROR: u32 = 2;
 pub const AI_SEVERITY_FATAL: u32 = 3;
 
-pub const AI_LOG_NONE: i32 = 0;
-pub const AI_LOG_INFO: i32 = 1;
-pub const AI_LOG_WARNINGS: i32 = 2;
-pub const AI_LOG_ERRORS: i32 = 4;
-pub const AI_LOG_DEBUG: i32 = 8;
-pub const AI_LOG_STATS: i32 = 16;
-pub const AI_LOG_ASS_PARSE: i32 = 32;
-pub const AI_LOG_PLUGINS: i32 = 64;
-pub const AI_LOG_PROGRESS: i32 = 128;
-pub const AI_LOG_NAN: i32 = 256;
-pub const AI_LOG_TIMESTAMP: i32 = 512;
-pub const AI_LOG_BACKTRACE: i32 = 1024;
-pub const AI_LOG_MEMORY: i32 = 2048;
-pub const AI_LOG_COLOR: i32 = 4096;
-pub const AI_LOG_ALL: i32 = 8191;
+ai_bitflags! {
+    /// Console log flags, such as `LogFlags::WARNINGS | LogFlags::ERRORS`.
+    ///
+    /// Prints and parses as the flag names, e.g. `"WARNINGS | ERRORS"`.
+    pub struct LogFlags: i32 {
+        flags {
+            /// Regular information messages
+            INFO = 1,
+            /// Warning messages
+            WARNINGS = 2,
+            /// Error messages
+            ERRORS = 4,
+            /// Debug messages
+            DEBUG = 8,
+            /// Detailed render statistics
+            STATS = 16,
+            /// .ass parsing messages
+            ASS_PARSE = 32,
+            /// Details about plugins loaded
+            PLUGINS = 64,
+            /// Progress messages at 5% increments while rendering
+            PROGRESS = 128,
+            /// Warnings for pixels with NaN's
+            NAN = 256,
+            /// Prefix messages with a timestamp (elapsed time and memory usage)
+            TIMESTAMP = 512,
+            /// Add a stack trace to fatal errors
+            BACKTRACE = 1024,
+            /// Prefix messages with current memory usage
+            MEMORY = 2048,
+            /// Add colors to log messages based on severity
+            COLOR = 4096,
+        }
+        composites {
+            /// All log flags
+            ALL = 8191,
+        }
+    }
+}
+
+pub const AI_LOG_NONE: LogFlags = LogFlags::NONE;
+pub const AI_LOG_INFO: LogFlags = LogFlags::INFO;
+pub const AI_LOG_WARNINGS: LogFlags = LogFlags::WARNINGS;
+pub const AI_LOG_ERRORS: LogFlags = LogFlags::ERRORS;
+pub const AI_LOG_DEBUG: LogFlags = LogFlags::DEBUG;
+pub const AI_LOG_STATS: LogFlags = LogFlags::STATS;
+pub const AI_LOG_ASS_PARSE: LogFlags = LogFlags::ASS_PARSE;
+pub const AI_LOG_PLUGINS: LogFlags = LogFlags::PLUGINS;
+pub const AI_LOG_PROGRESS: LogFlags = LogFlags::PROGRESS;
+pub const AI_LOG_NAN: LogFlags = LogFlags::NAN;
+pub const AI_LOG_TIMESTAMP: LogFlags = LogFlags::TIMESTAMP;
+pub const AI_LOG_BACKTRACE: LogFlags = LogFlags::BACKTRACE;
+pub const AI_LOG_MEMORY: LogFlags = LogFlags::MEMORY;
+pub const AI_LOG_COLOR: LogFlags = LogFlags::COLOR;
+pub const AI_LOG_ALL: LogFlags = LogFlags::ALL;
 
-pub fn AiMsgInfo(msg: &str){
+pub fn AiMsgInfo(msg: &str) -> Result<(), ArnoldError> {
+    let msg = CString::new(msg)?;
     unsafe {
-        ai_bindings::AiMsgInfo(CString::new(msg).unwrap().as_ptr());
+        ai_bindings::AiMsgInfo(msg.as_ptr());
     }
+    Ok(())
 }
 
-pub fn AiMsgDebug(msg: &str){
+pub fn AiMsgDebug(msg: &str) -> Result<(), ArnoldError> {
+    let msg = CString::new(msg)?;
     unsafe {
-        ai_bindings::AiMsgDebug(CString::new(msg).unwrap().as_ptr());
+        ai_bindings::AiMsgDebug(msg.as_ptr());
     }
+    Ok(())
 }
 
-pub fn AiMsgWarning(msg: &str){
+pub fn AiMsgWarning(msg: &str) -> Result<(), ArnoldError> {
+    let msg = CString::new(msg)?;
     unsafe {
-        ai_bindings::AiMsgWarning(CString::new(msg).unwrap().as_ptr());
+        ai_bindings::AiMsgWarning(msg.as_ptr());
     }
+    Ok(())
 }
 
-pub fn AiMsgError(msg: &str){
+pub fn AiMsgError(msg: &str) -> Result<(), ArnoldError> {
+    let msg = CString::new(msg)?;
     unsafe {
-        ai_bindings::AiMsgError(CString::new(msg).unwrap().as_ptr());
+        ai_bindings::AiMsgError(msg.as_ptr());
     }
+    Ok(())
 }
 
-pub fn AiMsgFatal(msg: &str){
+pub fn AiMsgFatal(msg: &str) -> Result<(), ArnoldError> {
+    let msg = CString::new(msg)?;
     unsafe {
-        ai_bindings::AiMsgFatal(CString::new(msg).unwrap().as_ptr());
+        ai_bindings::AiMsgFatal(msg.as_ptr());
     }
+    Ok(())
+}
+
+/// Log a message built by the crate, such as an error or panic message from Rust code, with one of the AiMsg* functions.
+///
+/// The message is passed to Arnold as a printf format, so `%` is escaped, and NUL bytes are removed instead of failing.
+pub(crate) fn log_literal(log: fn(&str) -> Result<(), ArnoldError>, msg: &str) {
+    // Without NUL bytes the message always converts to a CString.
+    let _ = log(&msg.replace('%', "%%").replace('\0', ""));
 }
 
-pub fn AiMsgSetConsoleFlags(flags: i32){
+pub fn AiMsgSetConsoleFlags(flags: LogFlags){
     unsafe{
-        ai_bindings::AiMsgSetConsoleFlags(flags)
+        ai_bindings::AiMsgSetConsoleFlags(flags.bits())
     }
 }
\ No newline at end of file
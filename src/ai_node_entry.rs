@@ -1,6 +1,16 @@
 //! AtParamEntry API
 //! 
 //! For a discussion of Arnold's object-oriented system of pluggable nodes, please refer to [AtNode API](../ai_nodes/index.html).
+//!
+//! # Node types implemented in Rust
+//!
+//! [`install_driver`](../ai_drivers/fn.install_driver.html), [`install_shader`](../ai_shaders/fn.install_shader.html), [`install_camera`](../ai_cameras/fn.install_camera.html), [`install_filter`](../ai_filters/fn.install_filter.html) and [`install_procedural`](../ai_procedural/fn.install_procedural.html) install node types whose methods are implemented by a Rust trait. They all work the same way:
+//! * one value of the implementing type is created per node when the node is initialized, kept as the node's local data, and dropped when the node is destroyed
+//! * `update` is called before a render when the node parameters may have changed, never while the render is running
+//! * the methods called during the render take `&self`, as Arnold calls them from all render threads at once
+//! * a panic in any method is reported with AiMsgError() instead of unwinding into Arnold
+//!
+//! The install functions return the new node entry, or an error if the name contains a NUL byte or Arnold rejected the node.
 #![allow(non_upper_case_globals)]
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
@@ -14,52 +24,242 @@ pub use ai_bindings::{
     AtNodeMethods,
     AtMetaDataEntry
 };
-use ai_bindings::AtString;
+use ai_bindings::{AtCommonMethods, AtNode, AtString};
+use ai_msg::{log_literal, AiMsgError};
+use ai_params::{read_value, ParamEntry, ParamType, ParamValue};
+use ai_render::Session;
+use ai_string::from_c_str;
+use error::ArnoldError;
 
-use std::ffi::{CStr, CString};
-use std::str;
+use std::any::Any;
+use std::convert::{TryFrom, TryInto};
+use std::ffi::CString;
+use std::marker::PhantomData;
+use std::os::raw::c_void;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::str::Utf8Error;
+
+ai_bitflags! {
+    /// Node type, or a mask of node types such as `NodeType::LIGHT | NodeType::CAMERA`.
+    ///
+    /// Prints and parses as the flag names, e.g. `"LIGHT | CAMERA"`.
+    pub struct NodeType: u32 {
+        flags {
+            /// Options node (following the "singleton" pattern, there is only one options node)
+            OPTIONS = 1,
+            /// Camera nodes (persp_camera, fisheye_camera, etc)
+            CAMERA = 2,
+            /// Light source nodes (spot_light, etc)
+            LIGHT = 4,
+            /// Geometry nodes (sphere, polymesh, etc)
+            SHAPE = 8,
+            /// Shader nodes (lambert, etc)
+            SHADER = 16,
+            /// EXPERIMENTAL: override nodes support "delayed parameter overrides" for procedural nodes.
+            OVERRIDE = 32,
+            /// Output driver nodes (driver_tiff, etc)
+            DRIVER = 64,
+            /// Pixel sample filter nodes (box_filter, etc)
+            FILTER = 128,
+            /// Procedural shapes, reported by AiNodeEntryGetDerivedType()
+            SHAPE_PROCEDURAL = 256,
+            /// Volume shapes, reported by AiNodeEntryGetDerivedType()
+            SHAPE_VOLUME = 512,
+            /// Implicit surface shapes, reported by AiNodeEntryGetDerivedType()
+            SHAPE_IMPLICIT = 1024,
+            /// Color manager nodes (Syncolor, OCIO, etc)
+            COLOR_MANAGER = 2048,
+            /// Operator plug-in nodes.
+            OPERATOR = 4096,
+        }
+        composites {
+            /// Bitmask including all node types, used by [AiASSWrite()](../ai_dotass/fn.AiASSWrite.html)
+            ALL = 65535,
+        }
+    }
+}
 
 // Nodes
-/// Undefined type. 
-pub const AI_NODE_UNDEFINED: u32 = 0;
-/// Options node (following the "singleton" pattern, there is only one options node) 
-pub const AI_NODE_OPTIONS: u32 = 1;
-/// Camera nodes (persp_camera, fisheye_camera, etc) 
-pub const AI_NODE_CAMERA: u32 = 2;
-/// Light source nodes (spot_light, etc) 
-pub const AI_NODE_LIGHT: u32 = 4;
-/// Geometry nodes (sphere, polymesh, etc) 
-pub const AI_NODE_SHAPE: u32 = 8;
-/// Shader nodes (lambert, etc) 
-pub const AI_NODE_SHADER: u32 = 16;
-/// EXPERIMENTAL: override nodes support "delayed parameter overrides" for procedural nodes. 
-pub const AI_NODE_OVERRIDE: u32 = 32;
-/// Output driver nodes (driver_tiff, etc) 
-pub const AI_NODE_DRIVER: u32 = 64;
-/// Pixel sample filter nodes (box_filter, etc. 
-pub const AI_NODE_FILTER: u32 = 128;
-/// Color manager nodes (Syncolor, OCIO, etc) 
-pub const AI_NODE_COLOR_MANAGER: u32 = 2048;
-/// Operator plug-in nodes. 
-pub const AI_NODE_OPERATOR: u32 = 4096;
+/// Undefined type.
+pub const AI_NODE_UNDEFINED: NodeType = NodeType::NONE;
+/// Options node (following the "singleton" pattern, there is only one options node)
+pub const AI_NODE_OPTIONS: NodeType = NodeType::OPTIONS;
+/// Camera nodes (persp_camera, fisheye_camera, etc)
+pub const AI_NODE_CAMERA: NodeType = NodeType::CAMERA;
+/// Light source nodes (spot_light, etc)
+pub const AI_NODE_LIGHT: NodeType = NodeType::LIGHT;
+/// Geometry nodes (sphere, polymesh, etc)
+pub const AI_NODE_SHAPE: NodeType = NodeType::SHAPE;
+/// Shader nodes (lambert, etc)
+pub const AI_NODE_SHADER: NodeType = NodeType::SHADER;
+/// EXPERIMENTAL: override nodes support "delayed parameter overrides" for procedural nodes.
+pub const AI_NODE_OVERRIDE: NodeType = NodeType::OVERRIDE;
+/// Output driver nodes (driver_tiff, etc)
+pub const AI_NODE_DRIVER: NodeType = NodeType::DRIVER;
+/// Pixel sample filter nodes (box_filter, etc.
+pub const AI_NODE_FILTER: NodeType = NodeType::FILTER;
+/// Color manager nodes (Syncolor, OCIO, etc)
+pub const AI_NODE_COLOR_MANAGER: NodeType = NodeType::COLOR_MANAGER;
+/// Operator plug-in nodes.
+pub const AI_NODE_OPERATOR: NodeType = NodeType::OPERATOR;
 /// Bitmask including all node types, used by [AiASSWrite()](../ai_dotass/fn.AiASSWrite.html)
-pub const AI_NODE_ALL: u32 = 65535;
+pub const AI_NODE_ALL: NodeType = NodeType::ALL;
 
 // Shapes
-pub const AI_NODE_SHAPE_PROCEDURAL: u32 = 256;
-pub const AI_NODE_SHAPE_VOLUME: u32 = 512;
-pub const AI_NODE_SHAPE_IMPLICIT: u32 = 1024;
+pub const AI_NODE_SHAPE_PROCEDURAL: NodeType = NodeType::SHAPE_PROCEDURAL;
+pub const AI_NODE_SHAPE_VOLUME: NodeType = NodeType::SHAPE_VOLUME;
+pub const AI_NODE_SHAPE_IMPLICIT: NodeType = NodeType::SHAPE_IMPLICIT;
+
+/// Handle to an installed node entry, valid for the lifetime of the [`Session`](../ai_render/struct.Session.html).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NodeEntry<'s> {
+    nentry: *const AtNodeEntry,
+    _session: PhantomData<&'s Session>,
+}
+
+impl<'s> NodeEntry<'s> {
+    /// Wrap a raw node entry pointer, returning `None` for NULL.
+    ///
+    /// # Safety
+    /// `nentry` must be NULL or point to a node entry that stays installed for `'s`.
+    pub unsafe fn from_raw(nentry: *const AtNodeEntry) -> Option<NodeEntry<'s>> {
+        if nentry.is_null() {
+            None
+        } else {
+            Some(NodeEntry {
+                nentry: nentry,
+                _session: PhantomData,
+            })
+        }
+    }
+
+    /// The raw node entry pointer, for use with the C API.
+    pub fn as_ptr(&self) -> *const AtNodeEntry {
+        self.nentry
+    }
+
+    /// The name of the node entry, such as "lambert".
+    pub fn name(&self) -> AtString {
+        AiNodeEntryGetNameAtString(self)
+    }
+
+    /// The type of the node entry (AI_NODE_SHADER, etc).
+    pub fn node_type(&self) -> NodeType {
+        AiNodeEntryGetType(self)
+    }
+
+    /// The number of parameters declared by the node entry.
+    pub fn num_params(&self) -> i32 {
+        AiNodeEntryGetNumParams(self)
+    }
+
+    /// The `i`-th parameter of the node entry, or `None` if `i` is out of range.
+    pub fn param_at(&self, i: i32) -> Option<ParamEntry<'s>> {
+        AiNodeEntryGetParameter(self, i)
+    }
+
+    /// Look up a parameter by name.
+    pub fn param(&self, name: &str) -> Option<ParamEntry<'s>> {
+        AiNodeEntryLookUpParameter(self, name)
+    }
+
+    /// Iterate over all built-in parameters of the node entry.
+    ///
+    /// ```
+    /// for param in entry.params() {
+    ///     println!("{}: {}", param.name(), param.type_name());
+    /// }
+    /// ```
+    pub fn params(&self) -> ParamIter<'s> {
+        ParamIter {
+            iter: AiNodeEntryGetParamIterator(self),
+            _session: PhantomData,
+        }
+    }
+
+    /// Iterate over the metadata of parameter `param`, or of the node entry itself for `None`.
+    ///
+    /// # Returns
+    /// the iterator, or an error if `param` contains a NUL byte
+    pub fn metadata(&self, param: Option<&str>) -> Result<MetaDataIter<'s>, ArnoldError> {
+        Ok(MetaDataIter {
+            iter: AiNodeEntryGetMetaDataIterator(self, param)?,
+            _session: PhantomData,
+        })
+    }
+}
+
+/// Iterator over the built-in parameters of a node entry, see [`NodeEntry::params`](struct.NodeEntry.html#method.params).
+///
+/// The underlying AtParamIterator is destroyed when the iterator is dropped.
+#[derive(Debug)]
+pub struct ParamIter<'s> {
+    iter: *mut AtParamIterator,
+    _session: PhantomData<&'s Session>,
+}
+
+impl<'s> Iterator for ParamIter<'s> {
+    type Item = ParamEntry<'s>;
+
+    fn next(&mut self) -> Option<ParamEntry<'s>> {
+        if AiParamIteratorFinished(self.iter) {
+            return None;
+        }
+        unsafe { ParamEntry::from_raw(AiParamIteratorGetNext(self.iter)) }
+    }
+}
+
+impl<'s> Drop for ParamIter<'s> {
+    fn drop(&mut self) {
+        AiParamIteratorDestroy(self.iter);
+    }
+}
+
+/// Iterator over the metadata of a node entry or one of its parameters, see [`NodeEntry::metadata`](struct.NodeEntry.html#method.metadata).
+///
+/// The underlying AtMetaDataIterator is destroyed when the iterator is dropped.
+#[derive(Debug)]
+pub struct MetaDataIter<'s> {
+    iter: *mut AtMetaDataIterator,
+    _session: PhantomData<&'s Session>,
+}
+
+impl<'s> Iterator for MetaDataIter<'s> {
+    type Item = &'s AtMetaDataEntry;
+
+    fn next(&mut self) -> Option<&'s AtMetaDataEntry> {
+        if AiMetaDataIteratorFinished(self.iter) {
+            return None;
+        }
+        unsafe { AiMetaDataIteratorGetNext(self.iter).as_ref() }
+    }
+}
+
+impl<'s> Drop for MetaDataIter<'s> {
+    fn drop(&mut self) {
+        AiMetaDataIteratorDestroy(self.iter);
+    }
+}
+
+impl AtMetaDataEntry {
+    /// The metadata value, or `None` if its type cannot be held by a [`ParamValue`](../ai_params/enum.ParamValue.html).
+    pub fn value(&self) -> Option<ParamValue> {
+        unsafe { read_value(&self.value, ParamType::from_code(self.type_)) }
+    }
+}
 
 /// Look up a node entry from a name string.
 /// 
 /// Search all installed node entries for a given node name. If found, return a pointer to the node entry.
 /// 
 /// # Parameters
+/// * `_session` - the active session
 /// * `name` - input node name (such as "lambert")
 /// # Returns
-/// pointer to the AtNodeEntry that matches the input name
-pub fn AiNodeEntryLookUp(name: AtString) -> *const AtNodeEntry{
-    unsafe { ai_bindings::AiNodeEntryLookUp(name) }
+/// the node entry that matches the input name, or `None` if there is no such node entry
+pub fn AiNodeEntryLookUp<'s>(_session: &'s Session, name: AtString) -> Option<NodeEntry<'s>> {
+    unsafe { NodeEntry::from_raw(ai_bindings::AiNodeEntryLookUp(name)) }
 }
 
 /// Return the name of the given AtNodeEntry as an AtString.
@@ -67,9 +267,9 @@ pub fn AiNodeEntryLookUp(name: AtString) -> *const AtNodeEntry{
 /// # Parameters
 /// * `nentry` - input node entry
 /// # Returns
-/// AtString name string, or NULL AtString if nentry is NULL
-pub fn AiNodeEntryGetNameAtString(nentry: *const AtNodeEntry) -> AtString {
-    unsafe {ai_bindings::AiNodeEntryGetNameAtString(nentry)}
+/// AtString name string
+pub fn AiNodeEntryGetNameAtString(nentry: &NodeEntry) -> AtString {
+    unsafe {ai_bindings::AiNodeEntryGetNameAtString(nentry.as_ptr())}
 }
 
 /// Return the type of the given AtNodeEntry.
@@ -77,9 +277,9 @@ pub fn AiNodeEntryGetNameAtString(nentry: *const AtNodeEntry) -> AtString {
 /// # Parameters
 /// * `nentry` - input node entry
 /// # Returns
-/// type of node (AI_NODE_SHADER, etc), or 0 if nentry is NULL
-pub fn AiNodeEntryGetType(nentry: *const AtNodeEntry) -> i32 {
-    unsafe {ai_bindings::AiNodeEntryGetType(nentry)}
+/// type of node (AI_NODE_SHADER, etc)
+pub fn AiNodeEntryGetType(nentry: &NodeEntry) -> NodeType {
+    NodeType::from_bits(unsafe {ai_bindings::AiNodeEntryGetType(nentry.as_ptr())} as u32)
 }
 
 /// Return the type of the given AtNodeEntry as a string.
@@ -89,13 +289,9 @@ pub fn AiNodeEntryGetType(nentry: *const AtNodeEntry) -> i32 {
 /// # Parameters
 /// * `nentry` - input node entry
 /// # Returns
-/// type string, or NULL if nentry is NULL
-pub fn AiNodeEntryGetTypeName(nentry: *const AtNodeEntry) -> &'static str {
-    let slice = unsafe {
-        let data = ai_bindings::AiNodeEntryGetTypeName(nentry);
-        CStr::from_ptr(data)
-    };
-    str::from_utf8(slice.to_bytes()).unwrap()
+/// type string, `None` if Arnold returns NULL, or an error if it is not valid UTF-8
+pub fn AiNodeEntryGetTypeName<'s>(nentry: &NodeEntry<'s>) -> Result<Option<&'s str>, Utf8Error> {
+    unsafe { from_c_str(ai_bindings::AiNodeEntryGetTypeName(nentry.as_ptr())) }
 }
 
 /// Return the derived type of the given AtNodeEntry.
@@ -103,9 +299,9 @@ pub fn AiNodeEntryGetTypeName(nentry: *const AtNodeEntry) -> &'static str {
 /// # Parameters
 /// * `nentry` - input node entry
 /// # Returns
-/// derived type of node (AI_NODE_SHAPE_VOLUME, etc), or 0 if nentry is either NULL or a non-derived type
-pub fn AiNodeEntryGetDerivedType(nentry: *const AtNodeEntry) -> i32 {
-    unsafe {ai_bindings::AiNodeEntryGetDerivedType(nentry)}
+/// derived type of node (AI_NODE_SHAPE_VOLUME, etc), or AI_NODE_UNDEFINED if nentry is a non-derived type
+pub fn AiNodeEntryGetDerivedType(nentry: &NodeEntry) -> NodeType {
+    NodeType::from_bits(unsafe {ai_bindings::AiNodeEntryGetDerivedType(nentry.as_ptr())} as u32)
 }
 
 /// Return the derived type of the given AtNodeEntry as a string, if applicable.
@@ -115,13 +311,9 @@ pub fn AiNodeEntryGetDerivedType(nentry: *const AtNodeEntry) -> i32 {
 /// # Parameters
 /// * `nentry` - input node entry
 /// # Returns
-/// type string, or NULL if nentry is NULL or not a derived type
-pub fn AiNodeEntryGetDerivedTypeName(nentry: *const AtNodeEntry) -> &'static str {
-    let slice = unsafe {
-        let data = ai_bindings::AiNodeEntryGetDerivedTypeName(nentry);
-        CStr::from_ptr(data)
-    };
-    str::from_utf8(slice.to_bytes()).unwrap()
+/// type string, `None` if nentry is not a derived type, or an error if the string is not valid UTF-8
+pub fn AiNodeEntryGetDerivedTypeName<'s>(nentry: &NodeEntry<'s>) -> Result<Option<&'s str>, Utf8Error> {
+    unsafe { from_c_str(ai_bindings::AiNodeEntryGetDerivedTypeName(nentry.as_ptr())) }
 }
 
 /// Return the output type of the given AtNodeEntry.
@@ -130,8 +322,8 @@ pub fn AiNodeEntryGetDerivedTypeName(nentry: *const AtNodeEntry) -> &'static str
 /// * `nentry` - input node entry
 /// # Returns
 /// output type (AI_TYPE_RGB, etc)
-pub fn AiNodeEntryGetOutputType(nentry: *const AtNodeEntry) -> i32 {
-    unsafe {ai_bindings::AiNodeEntryGetOutputType(nentry)}
+pub fn AiNodeEntryGetOutputType(nentry: &NodeEntry) -> ParamType {
+    ParamType::from_code(unsafe {ai_bindings::AiNodeEntryGetOutputType(nentry.as_ptr())} as u8)
 }
 
 /// Return the filename (so/dll path) of an AtNodeEntry.
@@ -139,13 +331,9 @@ pub fn AiNodeEntryGetOutputType(nentry: *const AtNodeEntry) -> i32 {
 /// # Parameters
 /// * `nentry` - input node entry
 /// # Returns
-/// string with the path ot the dynamic library where this node was installed from, NULL if this is a built-in node
-pub fn AiNodeEntryGetFilename(nentry: *const AtNodeEntry) -> &'static str {
-    let slice = unsafe {
-        let data = ai_bindings::AiNodeEntryGetFilename(nentry);
-        CStr::from_ptr(data)
-    };
-    str::from_utf8(slice.to_bytes()).unwrap()
+/// string with the path ot the dynamic library where this node was installed from, `None` if this is a built-in node, or an error if the path is not valid UTF-8
+pub fn AiNodeEntryGetFilename<'s>(nentry: &NodeEntry<'s>) -> Result<Option<&'s str>, Utf8Error> {
+    unsafe { from_c_str(ai_bindings::AiNodeEntryGetFilename(nentry.as_ptr())) }
 }
 
 /// Return the version that this node was linked with.
@@ -155,13 +343,9 @@ pub fn AiNodeEntryGetFilename(nentry: *const AtNodeEntry) -> &'static str {
 /// # Parameters
 /// * `nentry` - input node entry
 /// # Returns
-/// string containing the Arnold version that this node was linked with.
-pub fn AiNodeEntryGetVersion(nentry: *const AtNodeEntry) -> &'static str {
-    let slice = unsafe {
-        let data = ai_bindings::AiNodeEntryGetVersion(nentry);
-        CStr::from_ptr(data)
-    };
-    str::from_utf8(slice.to_bytes()).unwrap()
+/// string containing the Arnold version that this node was linked with, `None` if Arnold returns NULL, or an error if it is not valid UTF-8
+pub fn AiNodeEntryGetVersion<'s>(nentry: &NodeEntry<'s>) -> Result<Option<&'s str>, Utf8Error> {
+    unsafe { from_c_str(ai_bindings::AiNodeEntryGetVersion(nentry.as_ptr())) }
 }
 
 /// Return the number of instances of a particular node.
@@ -169,9 +353,9 @@ pub fn AiNodeEntryGetVersion(nentry: *const AtNodeEntry) -> &'static str {
 /// # Parameters
 /// * `nentry` - input node entry
 /// # Returns
-/// number of nodes of this type that have been created so far (for example, how many "sphere" nodes exist in memory), or 0 if nentry is NULL
-pub fn AiNodeEntryGetCount(nentry: *const AtNodeEntry) -> i32 {
-    unsafe {ai_bindings::AiNodeEntryGetCount(nentry)}
+/// number of nodes of this type that have been created so far (for example, how many "sphere" nodes exist in memory)
+pub fn AiNodeEntryGetCount(nentry: &NodeEntry) -> i32 {
+    unsafe {ai_bindings::AiNodeEntryGetCount(nentry.as_ptr())}
 }
 
 /// Return the number of parameters of a given AtNodeEntry.
@@ -179,9 +363,9 @@ pub fn AiNodeEntryGetCount(nentry: *const AtNodeEntry) -> i32 {
 /// # Parameters
 /// * `nentry` - input node entry
 /// # Returns
-/// number of parameters in the node, or 0 if nentry is NULL
-pub fn AiNodeEntryGetNumParams(nentry: *const AtNodeEntry) -> i32 {
-    unsafe {ai_bindings::AiNodeEntryGetNumParams(nentry)}
+/// number of parameters in the node
+pub fn AiNodeEntryGetNumParams(nentry: &NodeEntry) -> i32 {
+    unsafe {ai_bindings::AiNodeEntryGetNumParams(nentry.as_ptr())}
 }
 
 /// Return the i'th parameter in an AtNodeEntry.
@@ -190,9 +374,12 @@ pub fn AiNodeEntryGetNumParams(nentry: *const AtNodeEntry) -> i32 {
 /// * `nentry` - input node entry
 /// * `i` - parameter index, in 0..num_params-1
 /// # Returns
-/// the i'th parameter entry in this node
-pub fn AiNodeEntryGetParameter(nentry: *const AtNodeEntry, i: i32) -> *const AtParamEntry {
-    unsafe {ai_bindings::AiNodeEntryGetParameter(nentry, i)}
+/// the i'th parameter entry in this node, or `None` if `i` is out of range
+pub fn AiNodeEntryGetParameter<'s>(nentry: &NodeEntry<'s>, i: i32) -> Option<ParamEntry<'s>> {
+    if i < 0 || i >= AiNodeEntryGetNumParams(nentry) {
+        return None;
+    }
+    unsafe { ParamEntry::from_raw(ai_bindings::AiNodeEntryGetParameter(nentry.as_ptr(), i)) }
 }
 
 /// Look up a parameter in a node entry from a name string.
@@ -203,9 +390,10 @@ pub fn AiNodeEntryGetParameter(nentry: *const AtNodeEntry, i: i32) -> *const AtP
 /// * `nentry` - input node entry
 /// * `name` - parameter name that we are looking for (such as "samples")
 /// # Returns
-/// pointer to the AtParamEntry that matches the parameter name, or NULL if either nentry is NULL or the parameter is not found
-pub fn AiNodeEntryLookUpParameter<T: Into<AtString>>(nentry: *const AtNodeEntry, name: T) -> *const AtParamEntry {
-    unsafe {ai_bindings::AiNodeEntryLookUpParameter(nentry, name.into())}
+/// the parameter entry that matches the parameter name, or `None` if the parameter is not found or `name` is not a valid AtString
+pub fn AiNodeEntryLookUpParameter<'s, T: TryInto<AtString>>(nentry: &NodeEntry<'s>, name: T) -> Option<ParamEntry<'s>> {
+    let name = name.try_into().ok()?;
+    unsafe { ParamEntry::from_raw(ai_bindings::AiNodeEntryLookUpParameter(nentry.as_ptr(), name)) }
 }
 
 /// Creates and returns a new AtParamIterator for this node entry.
@@ -214,20 +402,24 @@ pub fn AiNodeEntryLookUpParameter<T: Into<AtString>>(nentry: *const AtNodeEntry,
 /// * `nentry`- node entry whose parameters will be iterated over
 /// # Returns
 /// an iterator over all built-in parameters on this node entry
-pub fn AiNodeEntryGetParamIterator(nentry: *const AtNodeEntry) -> *const AtParamIterator {
-    unsafe {ai_bindings::AiNodeEntryGetParamIterator(nentry)}
+pub fn AiNodeEntryGetParamIterator(nentry: &NodeEntry) -> *mut AtParamIterator {
+    unsafe {ai_bindings::AiNodeEntryGetParamIterator(nentry.as_ptr())}
 }
 
 /// Creates and returns a new AtMetaDataIterator for this node entry.
 /// 
 /// # Parameters
 /// * `nentry` - node entry whose metadata will be iterated over
-/// * `param`- selects a specific parameter to get its metadata or NULL for node metadata
+/// * `param`- selects a specific parameter to get its metadata or `None` for node metadata
 /// # Returns
-/// an iterator over metadata on this node entry
-pub fn AiNodeEntryGetMetaDataIterator(nentry: *const AtNodeEntry, param: &str) -> *const AtMetaDataIterator {
-    let param = CString::new(param).unwrap();
-    unsafe {ai_bindings::AiNodeEntryGetMetaDataIterator(nentry, param.as_ptr())}
+/// an iterator over metadata on this node entry, or an error if `param` contains a NUL byte
+pub fn AiNodeEntryGetMetaDataIterator(nentry: &NodeEntry, param: Option<&str>) -> Result<*mut AtMetaDataIterator, ArnoldError> {
+    let param = match param {
+        Some(param) => Some(CString::new(param)?),
+        None => None,
+    };
+    let param_ptr = param.as_ref().map_or(ptr::null(), |param| param.as_ptr());
+    Ok(unsafe {ai_bindings::AiNodeEntryGetMetaDataIterator(nentry.as_ptr(), param_ptr)})
 }
 
 /// Install a new node in the system.
@@ -236,21 +428,120 @@ pub fn AiNodeEntryGetMetaDataIterator(nentry: *const AtNodeEntry, param: &str) -
 /// 
 /// # Examples:
 /// ```
-///     AiNodeEntryInstall(AI_NODE_SHAPE, AI_TYPE_UNDEFINED, "sphere", "<built-in>", SphMethods, AI_VERSION);
-///     AiNodeEntryInstall(AI_NODE_SHADER, AI_TYPE_FLOAT, "fBm_noise", "./shaders/fBm_noise.so", fBmMethods, AI_VERSION);
+///     AiNodeEntryInstall(&session, AI_NODE_SHAPE, AI_TYPE_UNDEFINED, "sphere", "<built-in>", SphMethods, AI_VERSION);
+///     AiNodeEntryInstall(&session, AI_NODE_SHADER, AI_TYPE_FLOAT, "fBm_noise", "./shaders/fBm_noise.so", fBmMethods, AI_VERSION);
 /// ```
 /// # Parameters
+/// * `_session` - the active session
 /// * `type` - type of node (AI_NODE_CAMERA, AI_NODE_SHADER, etc)
 /// * `output_type` - for shader nodes, which can link their output to the input of another shader, this is the output type (AI_TYPE_RGB, etc)
 /// * `name` - name string of the newly created node type
 /// * `filename` - string identifier that hints at how this node was created, usually the path of the dynamic library where it was loaded from but, in general, it should be set to whatever makes sense for the app that's installing the node
 /// * `methods` - pointer to the node methods
 /// * `version` - Arnold version string that this node is linked with
-pub fn AiNodeEntryInstall(entry_type: i32, output_type: u8, name: &str, filename: &str, methods: *const AtNodeMethods, version: &str){
-    let name = CString::new(name).unwrap();
-    let filename = CString::new(filename).unwrap();
-    let version = CString::new(version).unwrap();
-    unsafe {ai_bindings::AiNodeEntryInstall(entry_type, output_type, name.as_ptr(), filename.as_ptr(), methods, version.as_ptr())}
+/// # Returns
+/// an error if `name`, `filename` or `version` contains a NUL byte
+pub fn AiNodeEntryInstall(_session: &Session, entry_type: NodeType, output_type: ParamType, name: &str, filename: &str, methods: *const AtNodeMethods, version: &str) -> Result<(), ArnoldError> {
+    let name = CString::new(name)?;
+    let filename = CString::new(filename)?;
+    let version = CString::new(version)?;
+    unsafe {ai_bindings::AiNodeEntryInstall(entry_type.bits() as i32, output_type.code(), name.as_ptr(), filename.as_ptr(), methods, version.as_ptr())}
+    Ok(())
+}
+
+/// Filename reported by AiNodeEntryGetFilename() for node entries implemented in Rust.
+const RUST_FILENAME: &[u8] = b"<rust>\0";
+
+/// Install a node entry whose methods are implemented in Rust, see [node types implemented in Rust](index.html#node-types-implemented-in-rust).
+///
+/// The method tables are leaked, as Arnold keeps pointing to them for as long as the process runs.
+/// # Parameters
+/// * `session` - the active session
+/// * `entry_type` - type of node
+/// * `output_type` - output type of the node
+/// * `name` - name of the new node type
+/// * `cmethods` - common node methods
+/// * `dmethods` - methods of the derived node type, such as AtDriverNodeMethods, leaked by the caller
+/// # Returns
+/// the installed node entry, or an error if `name` contains a NUL byte or Arnold rejected the node
+pub(crate) fn install_methods<'s>(session: &'s Session, entry_type: NodeType, output_type: ParamType, name: &str, cmethods: AtCommonMethods, dmethods: *const c_void) -> Result<NodeEntry<'s>, ArnoldError> {
+    let c_name = CString::new(name)?;
+    let methods: &'static AtNodeMethods = Box::leak(Box::new(AtNodeMethods {
+        cmethods: Box::leak(Box::new(cmethods)),
+        dmethods: dmethods,
+    }));
+    unsafe {
+        let version = ai_bindings::AiGetVersion(ptr::null_mut(), ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+        ai_bindings::AiNodeEntryInstall(entry_type.bits() as i32, output_type.code(), c_name.as_ptr(), RUST_FILENAME.as_ptr() as *const _, methods, version)
+    }
+    AiNodeEntryLookUp(session, AtString::try_from(name)?).ok_or_else(|| ArnoldError::Install {
+        name: name.to_owned(),
+    })
+}
+
+/// Store a Rust node instance as the local data of `node`.
+///
+/// # Safety
+/// `node` must be a valid node whose local data is not in use.
+pub(crate) unsafe fn set_instance<T>(node: *mut AtNode, instance: T) {
+    ai_bindings::AiNodeSetLocalData(node, Box::into_raw(Box::new(instance)) as *mut c_void)
+}
+
+/// Return the Rust node instance stored by [`set_instance`](fn.set_instance.html), if any.
+///
+/// # Safety
+/// The local data of `node` must be NULL or have been stored by `set_instance::<T>`.
+pub(crate) unsafe fn instance<'a, T>(node: *const AtNode) -> Option<&'a T> {
+    (ai_bindings::AiNodeGetLocalData(node) as *const T).as_ref()
+}
+
+/// Return the Rust node instance stored by [`set_instance`](fn.set_instance.html) for modification, if any.
+///
+/// # Safety
+/// The local data of `node` must be NULL or have been stored by `set_instance::<T>`, and no other reference to the instance may be alive, as is the case in node_update and node_finish.
+pub(crate) unsafe fn instance_mut<'a, T>(node: *mut AtNode) -> Option<&'a mut T> {
+    (ai_bindings::AiNodeGetLocalData(node) as *mut T).as_mut()
+}
+
+/// Drop the Rust node instance stored by [`set_instance`](fn.set_instance.html), if any.
+///
+/// # Safety
+/// The local data of `node` must be NULL or have been stored by `set_instance::<T>`.
+pub(crate) unsafe fn drop_instance<T>(node: *mut AtNode) {
+    let data = ai_bindings::AiNodeGetLocalData(node) as *mut T;
+    if !data.is_null() {
+        ai_bindings::AiNodeSetLocalData(node, ptr::null_mut());
+        drop(Box::from_raw(data));
+    }
+}
+
+/// Run a node method implemented in Rust, so that a panic is reported with AiMsgError() instead of unwinding into Arnold.
+///
+/// # Returns
+/// the result of `f`, or `default` if it panicked
+pub(crate) fn guard_method<R, F: FnOnce() -> R>(method: &str, default: R, f: F) -> R {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        log_literal(AiMsgError, &format!("{} panicked: {}", method, panic_message(&*payload)));
+        default
+    })
+}
+
+/// Log an error returned by a node method implemented in Rust with AiMsgError().
+///
+/// # Returns
+/// the value of `result`, or `None` if it was an error
+pub(crate) fn report<T>(method: &str, result: Result<T, ArnoldError>) -> Option<T> {
+    result.map_err(|err| log_literal(AiMsgError, &format!("{} failed: {}", method, err))).ok()
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "Box<dyn Any>"
+    }
 }
 
 /// Uninstall a node from the system.
@@ -258,10 +549,14 @@ pub fn AiNodeEntryInstall(entry_type: i32, output_type: u8, name: &str, filename
 /// Remove the node with the given name from the system. Successive calls to AiNode() using this node name will fail.
 /// 
 /// # Parameters
+/// * `_session` - the active session
 /// * `name` - name of the node to be removed from the system
-pub fn AiNodeEntryUninstall(name: &str){
-    let name = CString::new(name).unwrap();
+/// # Returns
+/// an error if `name` contains a NUL byte
+pub fn AiNodeEntryUninstall(_session: &Session, name: &str) -> Result<(), ArnoldError> {
+    let name = CString::new(name)?;
     unsafe {ai_bindings::AiNodeEntryUninstall(name.as_ptr()) }
+    Ok(())
 }
 
 /// Destroys a param iterator when it is no longer needed.
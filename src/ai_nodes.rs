@@ -17,14 +17,265 @@
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 
+use ai_array::{AiArrayCopy, AiArrayGetType, Array, ArrayElement};
 use ai_bindings;
+use error::ArnoldError;
+use ai_node_entry::{AiNodeEntryLookUpParameter, NodeEntry};
+use ai_params::{AiUserParamGetType, ParamData, ParamType, ParamValue, UserParamEntry};
+use ai_params::{
+    AI_TYPE_BOOLEAN, AI_TYPE_BYTE, AI_TYPE_ENUM, AI_TYPE_FLOAT, AI_TYPE_INT, AI_TYPE_MATRIX, AI_TYPE_NODE,
+    AI_TYPE_POINTER, AI_TYPE_RGB, AI_TYPE_RGBA, AI_TYPE_STRING, AI_TYPE_UINT, AI_TYPE_VECTOR, AI_TYPE_VECTOR2,
+};
+use ai_render::Session;
 use ai_string::AtString;
+use ai_universe::Universe;
 use ai_vector::{AtVector, AtVector2};
 
+use std::convert::{TryFrom, TryInto};
 use std::ffi::{CStr, CString};
+use std::marker::PhantomData;
 use std::os::raw::c_void;
 use std::{ptr, str};
 
+/// Handle to a node in a [`Universe`](../ai_universe/struct.Universe.html).
+///
+/// A `Node` borrows the universe it was created in, so it cannot outlive AiUniverseDestroy() or AiEnd().
+/// Handles are created by [`AiNode`](fn.AiNode.html), [`AiNodeLookUpByName`](fn.AiNodeLookUpByName.html) and the functions that return other nodes, such as [`parent`](#method.parent), so several handles can refer to the same node.
+/// Nodes are destroyed through [`Universe::destroy_node`](../ai_universe/struct.Universe.html#method.destroy_node), which borrows the universe mutably, so no handle can be used after its node is gone.
+#[derive(Debug, PartialEq)]
+pub struct Node<'s> {
+    node: *mut ai_bindings::AtNode,
+    _session: PhantomData<&'s Session>,
+}
+
+/// Identifies a node without borrowing its universe, see [`Node::id`](struct.Node.html#method.id).
+///
+/// An id does not keep its node alive. Functions taking an id check that the node still exists in the given universe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(*mut ai_bindings::AtNode);
+
+impl NodeId {
+    /// The raw node pointer, which may dangle if the node was destroyed.
+    pub fn as_ptr(&self) -> *mut ai_bindings::AtNode {
+        self.0
+    }
+}
+
+impl<'s> Node<'s> {
+    /// Wrap a raw node pointer, returning `None` for NULL.
+    ///
+    /// # Safety
+    /// `node` must be NULL or point to a node that stays alive for `'s`.
+    pub unsafe fn from_raw(node: *mut ai_bindings::AtNode) -> Option<Node<'s>> {
+        if node.is_null() {
+            None
+        } else {
+            Some(Node {
+                node: node,
+                _session: PhantomData,
+            })
+        }
+    }
+
+    /// The raw node pointer, for use with the C API.
+    pub fn as_ptr(&self) -> *mut ai_bindings::AtNode {
+        self.node
+    }
+
+    /// Return the node's name, or an error if it is not valid UTF-8.
+    pub fn name(&self) -> Result<&str, ArnoldError> {
+        AiNodeGetName(self)
+    }
+
+    /// Return the node entry for this node.
+    pub fn entry(&self) -> NodeEntry<'s> {
+        AiNodeGetNodeEntry(self)
+    }
+
+    /// Compare the node type against a node entry name, such as "lambert".
+    pub fn is<T: TryInto<AtString>>(&self, name: T) -> bool {
+        AiNodeIs(self, name)
+    }
+
+    /// Returns the procedural parent of this node, or `None` if it does not come from one.
+    pub fn parent(&self) -> Option<Node<'s>> {
+        AiNodeGetParent(self)
+    }
+
+    /// Return a deep copy of this node named `new_name`, see [`AiNodeClone`](fn.AiNodeClone.html).
+    pub fn clone_as(&self, new_name: &str, parent: Option<&Node<'s>>) -> Option<Node<'s>> {
+        AiNodeClone(self, new_name, parent)
+    }
+
+    /// An identifier for this node that does not borrow the universe, for [`Universe::destroy_node`](../ai_universe/struct.Universe.html#method.destroy_node).
+    pub fn id(&self) -> NodeId {
+        NodeId(self.node)
+    }
+
+    /// Disable or enable this node.
+    pub fn set_disabled(&self, disabled: bool) {
+        AiNodeSetDisabled(self, disabled)
+    }
+
+    /// Check if this node has been disabled.
+    pub fn is_disabled(&self) -> bool {
+        AiNodeIsDisabled(self)
+    }
+
+    /// Connect the whole output of this node to the `input` parameter of `target`, see [`AiNodeLink`](fn.AiNodeLink.html).
+    pub fn link(&self, input: &str, target: &Node<'s>) -> Result<(), ArnoldError> {
+        AiNodeLink(self, input, target)
+    }
+
+    /// Return the type (AI_TYPE_FLOAT, etc) of a built-in or user-defined parameter.
+    pub fn param_type(&self, param: &str) -> Result<ParamType, ArnoldError> {
+        if let Some(pentry) = AiNodeEntryLookUpParameter(&self.entry(), param) {
+            return Ok(pentry.param_type());
+        }
+        let upentry = AiNodeLookUpUserParameter(self, param);
+        if !upentry.is_null() {
+            return Ok(AiUserParamGetType(upentry));
+        }
+        Err(ArnoldError::UnknownParam {
+            node: self.display_name(),
+            param: param.to_owned(),
+        })
+    }
+
+    /// Set a parameter, checking the value against the parameter type first.
+    ///
+    /// ```
+    /// sphere.set("radius", 2.0)?;
+    /// sphere.set("matrix", AtMatrix::identity())?;
+    /// options.set("camera", &camera)?;
+    /// ```
+    pub fn set<T: Into<ParamValue>>(&self, param: &str, value: T) -> Result<(), ArnoldError> {
+        let value = value.into();
+        let param_type = self.param_type(param)?;
+        if !value.fits(param_type) {
+            return Err(self.type_mismatch(param, param_type, value.param_type()));
+        }
+        let name = AtString::try_from(param)?;
+        match value {
+            ParamValue::Byte(v) => AiNodeSetByte(self, name, v),
+            ParamValue::Int(v) | ParamValue::Enum(v) => AiNodeSetInt(self, name, v),
+            ParamValue::UInt(v) => AiNodeSetUInt(self, name, v),
+            ParamValue::Bool(v) => AiNodeSetBool(self, name, v),
+            ParamValue::Float(v) => AiNodeSetFlt(self, name, v),
+            ParamValue::RGB(v) => AiNodeSetRGB(self, name, v.r, v.g, v.b),
+            ParamValue::RGBA(v) => AiNodeSetRGBA(self, name, v.r, v.g, v.b, v.a),
+            ParamValue::Vector(v) => AiNodeSetVec(self, name, v.x, v.y, v.z),
+            ParamValue::Vector2(v) => AiNodeSetVec2(self, name, v.x, v.y),
+            ParamValue::String(v) => AiNodeSetStr(self, name, AtString::try_from(v)?),
+            ParamValue::Matrix(v) => AiNodeSetMatrix(self, name, v),
+            ParamValue::Node(v) => AiNodeSetPtr(self, name, v as *mut c_void),
+            ParamValue::Pointer(v) => AiNodeSetPtr(self, name, v),
+        }
+        Ok(())
+    }
+
+    /// Read a parameter as the Rust type matching its parameter type.
+    ///
+    /// ```
+    /// let radius = sphere.get::<f32>("radius")?;
+    /// ```
+    pub fn get<T: ParamData>(&self, param: &str) -> Result<T, ArnoldError> {
+        let value = self.get_value(param)?;
+        let param_type = value.param_type();
+        T::from_value(value).ok_or_else(|| self.type_mismatch(param, param_type, T::TYPE))
+    }
+
+    /// Read a parameter into a [`ParamValue`](../ai_params/enum.ParamValue.html) of its own type.
+    pub fn get_value(&self, param: &str) -> Result<ParamValue, ArnoldError> {
+        let param_type = self.param_type(param)?;
+        let name = AtString::try_from(param)?;
+        Ok(match param_type {
+            AI_TYPE_BYTE => ParamValue::Byte(AiNodeGetByte(self, name)),
+            AI_TYPE_INT => ParamValue::Int(AiNodeGetInt(self, name)),
+            AI_TYPE_UINT => ParamValue::UInt(AiNodeGetUInt(self, name)),
+            AI_TYPE_BOOLEAN => ParamValue::Bool(AiNodeGetBool(self, name)),
+            AI_TYPE_FLOAT => ParamValue::Float(AiNodeGetFlt(self, name)),
+            AI_TYPE_RGB => ParamValue::RGB(AiNodeGetRGB(self, name)),
+            AI_TYPE_RGBA => ParamValue::RGBA(AiNodeGetRGBA(self, name)),
+            AI_TYPE_VECTOR => ParamValue::Vector(AiNodeGetVec(self, name)),
+            AI_TYPE_VECTOR2 => ParamValue::Vector2(AiNodeGetVec2(self, name)),
+            AI_TYPE_STRING => {
+                let value = AiNodeGetStr(self, name);
+                ParamValue::String(if value.empty() { String::new() } else { value.to_str()?.to_owned() })
+            }
+            AI_TYPE_MATRIX => ParamValue::Matrix(AiNodeGetMatrix(self, name)),
+            AI_TYPE_ENUM => ParamValue::Enum(AiNodeGetInt(self, name)),
+            AI_TYPE_NODE => ParamValue::Node(AiNodeGetPtr(self, name) as *mut ai_bindings::AtNode),
+            AI_TYPE_POINTER => ParamValue::Pointer(AiNodeGetPtr(self, name)),
+            _ => {
+                return Err(ArnoldError::UnsupportedType {
+                    node: self.display_name(),
+                    param: param.to_owned(),
+                    param_type: param_type,
+                })
+            }
+        })
+    }
+
+    /// Iterate over the user-defined parameters declared on this node.
+    ///
+    /// As with [`AiUserParamIteratorGetNext`](fn.AiUserParamIteratorGetNext.html), parameters declared on the reference node of an instance are not included.
+    pub fn user_params<'a>(&'a self) -> UserParamIter<'a> {
+        UserParamIter {
+            iter: AiNodeGetUserParamIterator(self),
+            _session: PhantomData,
+        }
+    }
+
+    /// The node name for error messages, with invalid UTF-8 replaced.
+    fn display_name(&self) -> String {
+        unsafe { CStr::from_ptr(ai_bindings::AiNodeGetName(self.node)) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    pub(crate) fn type_mismatch(&self, param: &str, expected: ParamType, found: ParamType) -> ArnoldError {
+        ArnoldError::TypeMismatch {
+            node: self.display_name(),
+            param: param.to_owned(),
+            expected: expected,
+            found: found,
+        }
+    }
+}
+
+/// Iterator over the user-defined parameters of a node, see [`Node::user_params`](struct.Node.html#method.user_params).
+///
+/// The underlying AtUserParamIterator is destroyed when the iterator is dropped.
+#[derive(Debug)]
+pub struct UserParamIter<'s> {
+    iter: *mut ai_bindings::AtUserParamIterator,
+    _session: PhantomData<&'s Session>,
+}
+
+impl<'s> Iterator for UserParamIter<'s> {
+    type Item = UserParamEntry<'s>;
+
+    fn next(&mut self) -> Option<UserParamEntry<'s>> {
+        if AiUserParamIteratorFinished(self.iter) {
+            return None;
+        }
+        unsafe { UserParamEntry::from_raw(AiUserParamIteratorGetNext(self.iter)) }
+    }
+}
+
+impl<'s> Drop for UserParamIter<'s> {
+    fn drop(&mut self) {
+        AiUserParamIteratorDestroy(self.iter);
+    }
+}
+
+impl<'a, 's> From<&'a Node<'s>> for ParamValue {
+    fn from(node: &'a Node<'s>) -> ParamValue {
+        ParamValue::Node(node.as_ptr())
+    }
+}
+
 /// Create a fresh instantiation of a node.
 ///
 /// Arnold-based apps would call this function to populate the scene with nodes. Node instantiations can be created out of built-in nodes (such as "sphere" or "lambert"), manually-installed nodes (using AiNodeEntryInstall()), or plug-in nodes contained in a .so/.dll dynamic library (using AiLoadPlugins()).
@@ -34,29 +285,41 @@ use std::{ptr, str};
 /// # Note
 /// When creating a node that will be contained in a procedural node, it is CRITICAL that the proper parent procedural pointer is given through the "parent" parameter. Failure to do so will result in some issues due to incomplete node initialization.
 /// # Parameters
+/// * `universe` - the universe the node is created in
 /// * `nentry_name` - name of the type of node to be created ("polymesh", etc)
 /// * `name` - name of the new node to be created
-/// * `parent` - parent of the new node, or NULL for none (global scope)
+/// * `parent` - parent of the new node, or `None` for none (global scope)
 /// # Returns
-/// pointer to a new [`AtString`](::ai_string::AtString) of the requested type, or NULL if the node could not be created (the node wasn't previously installed, or it was in the ignore list)
+/// a new [`Node`](struct.Node.html) of the requested type, or [`ArnoldError::NodeCreation`](../error/enum.ArnoldError.html) if the node could not be created (the node wasn't previously installed, or it was in the ignore list)
 /// # Rust
 /// To get default behaviour:
 /// ```
-/// AiNode("flat", "flatShader1", None)
+/// AiNode(&universe, "flat", Some("flatShader1"), None)
 /// ```
-pub fn AiNode<T: Into<AtString>>(
+pub fn AiNode<'u, T>(
+    universe: &'u Universe,
     nentry_name: T,
     name: Option<&str>,
-    parent: Option<*const ai_bindings::AtNode>,
-) -> *mut ai_bindings::AtNode {
-    unsafe {
-        let name = name.unwrap_or("");
-        return ai_bindings::AiNode(
-            nentry_name.into(),
-            name.into(),
-            parent.unwrap_or(ptr::null_mut()),
-        );
-    }
+    parent: Option<&Node<'u>>,
+) -> Result<Node<'u>, ArnoldError>
+where
+    T: TryInto<AtString>,
+    ArnoldError: From<T::Error>,
+{
+    let nentry_name = nentry_name.try_into()?;
+    let name = name.unwrap_or("");
+    let node = unsafe {
+        Node::from_raw(ai_bindings::AiNode(
+            universe.as_ptr(),
+            nentry_name,
+            AtString::try_from(name)?,
+            parent.map_or(ptr::null_mut(), |p| p.as_ptr()),
+        ))
+    };
+    node.ok_or_else(|| ArnoldError::NodeCreation {
+        node_type: nentry_name.to_string_lossy().into_owned(),
+        name: name.to_owned(),
+    })
 }
 
 /// Search for a specific node in the scene with the given name string  
@@ -72,20 +335,36 @@ pub fn AiNode<T: Into<AtString>>(
 /// AiNodeLookUpByName("^myproc^mymesh");
 /// ```
 /// # Parameters
+/// * `universe` - the universe to search in
 /// * `name`- node path to look up
 /// * `parent` - if not null, search will begin within the contents of this node, then it will continue recursively up towards the root of the scene. If null, search will be limited to nodes in the root level.
 /// # Returns
-/// pointer to a node whose "name" parameter matches the given name string, or NULL if there are no nodes with that name
+/// the node whose "name" parameter matches the given name string, or [`ArnoldError::NodeNotFound`](../error/enum.ArnoldError.html) if there are no nodes with that name
 /// # Rust
 /// To get default behaviour:
 /// ```
-/// AiNodeLookUpByName("flatShader1", None)
+/// AiNodeLookUpByName(&universe, "flatShader1", None)
 /// ```
-pub fn AiNodeLookUpByName<T: Into<AtString>>(
+pub fn AiNodeLookUpByName<'u, T>(
+    universe: &'u Universe,
     name: T,
-    parent: Option<*const ai_bindings::AtNode>,
-) -> *mut ai_bindings::AtNode {
-    unsafe { ai_bindings::AiNodeLookUpByName(name.into(), parent.unwrap_or(ptr::null_mut())) }
+    parent: Option<&Node<'u>>,
+) -> Result<Node<'u>, ArnoldError>
+where
+    T: TryInto<AtString>,
+    ArnoldError: From<T::Error>,
+{
+    let name = name.try_into()?;
+    let node = unsafe {
+        Node::from_raw(ai_bindings::AiNodeLookUpByName(
+            universe.as_ptr(),
+            name,
+            parent.map_or(ptr::null_mut(), |p| p.as_ptr()),
+        ))
+    };
+    node.ok_or_else(|| ArnoldError::NodeNotFound {
+        name: name.to_string_lossy().into_owned(),
+    })
 }
 
 /// Declare a user-defined parameter for this node.
@@ -112,16 +391,29 @@ pub fn AiNodeLookUpByName<T: Into<AtString>>(
 /// * `name` - name of the new parameter
 /// * `declaration` - declaration string for the class and type of the new parameter
 /// # Returns
-/// true if the parameter could be succesfully added: it didn't already exist and the declaration string wasn't malformed
+/// `Ok` if the parameter could be succesfully added: it didn't already exist and the declaration string wasn't malformed
 /// # See also
 /// User-Data API
-pub fn AiNodeDeclare<T: Into<AtString>>(
-    node: *mut ai_bindings::AtNode,
+pub fn AiNodeDeclare<T>(
+    node: &Node,
     param: T,
     declaration: &str,
-) -> bool {
-    let declaration = CString::new(declaration).unwrap();
-    unsafe { ai_bindings::AiNodeDeclare(node, param.into(), declaration.as_ptr()) }
+) -> Result<(), ArnoldError>
+where
+    T: TryInto<AtString>,
+    ArnoldError: From<T::Error>,
+{
+    let param = param.try_into()?;
+    let c_declaration = CString::new(declaration)?;
+    if unsafe { ai_bindings::AiNodeDeclare(node.as_ptr(), param, c_declaration.as_ptr()) } {
+        Ok(())
+    } else {
+        Err(ArnoldError::Declaration {
+            node: node.display_name(),
+            param: param.to_string_lossy().into_owned(),
+            declaration: declaration.to_owned(),
+        })
+    }
 }
 
 /// Return the user-defined parameter entry that matches a given name.
@@ -132,14 +424,17 @@ pub fn AiNodeDeclare<T: Into<AtString>>(
 /// * `node` - input node
 /// * `param` - the parameter we are looking for
 /// # Returns
-/// handle to the user-defined parameter entry whose name matches the given string, or NULL if not found
+/// handle to the user-defined parameter entry whose name matches the given string, or NULL if not found or `param` is not a valid AtString
 /// # See also
 /// User-Data API
-pub fn AiNodeLookUpUserParameter<T: Into<AtString>>(
-    node: *mut ai_bindings::AtNode,
+pub fn AiNodeLookUpUserParameter<T: TryInto<AtString>>(
+    node: &Node,
     param: T,
 ) -> *const ai_bindings::AtUserParamEntry {
-    unsafe { ai_bindings::AiNodeLookUpUserParameter(node, param.into()) }
+    match param.try_into() {
+        Ok(param) => unsafe { ai_bindings::AiNodeLookUpUserParameter(node.as_ptr(), param) },
+        Err(_) => ptr::null(),
+    }
 }
 
 /// Compare the node type against a string.
@@ -152,12 +447,15 @@ pub fn AiNodeLookUpUserParameter<T: Into<AtString>>(
 /// }
 /// ```
 /// # Parameters
-/// * `node` - pointer to a node whose type is to be compared
+/// * `node` - the node whose type is to be compared
 /// * `string` - the name of an existing node type
 /// # Returns
-/// true if the node type's name matches the given string
-pub fn AiNodeIs<T: Into<AtString>>(node: *mut ai_bindings::AtNode, name: T) -> bool {
-    unsafe { ai_bindings::AiNodeIs(node, name.into()) }
+/// true if the node type's name matches the given string, false if it does not or `name` is not a valid AtString
+pub fn AiNodeIs<T: TryInto<AtString>>(node: &Node, name: T) -> bool {
+    match name.try_into() {
+        Ok(name) => unsafe { ai_bindings::AiNodeIs(node.as_ptr(), name) },
+        Err(_) => false,
+    }
 }
 
 /// Reset all node parameters to their default values and remove any input links.
@@ -165,9 +463,9 @@ pub fn AiNodeIs<T: Into<AtString>>(node: *mut ai_bindings::AtNode, name: T) -> b
 /// # Note
 /// If the node is a procedural, any parameter that had a explicit value was overriding the values on the children. Resetting these parameters will remove those overrides.
 /// # Parameters
-/// * `node` - pointer to a node to be reset (if the pointer is NULL, the function will simply return)
-pub fn AiNodeReset(node: *mut ai_bindings::AtNode) {
-    unsafe { ai_bindings::AiNodeReset(node) }
+/// * `node` - the node to be reset
+pub fn AiNodeReset(node: &Node) {
+    unsafe { ai_bindings::AiNodeReset(node.as_ptr()) }
 }
 
 /// Reset a node parameter to its default value and remove any links to that parameter.
@@ -177,11 +475,14 @@ pub fn AiNodeReset(node: *mut ai_bindings::AtNode) {
 /// If the node is a procedural, this parameter could have been overriding values on the nodes contained in it. This override will disappear once the parameter is reset.
 ///
 /// # Parameters
-/// * `node` - pointer to a node (if the pointer is NULL, the function will simply return)
+/// * `node` - the node whose parameter is reset
 /// * `param` - name of the parameter to be reset to its default value
-pub fn AiNodeResetParameter(node: *mut ai_bindings::AtNode, param: &str) {
-    let param = CString::new(param).unwrap();
-    unsafe { ai_bindings::AiNodeResetParameter(node, param.as_ptr()) }
+/// # Returns
+/// an error if `param` contains a NUL byte
+pub fn AiNodeResetParameter(node: &Node, param: &str) -> Result<(), ArnoldError> {
+    let param = CString::new(param)?;
+    unsafe { ai_bindings::AiNodeResetParameter(node.as_ptr(), param.as_ptr()) }
+    Ok(())
 }
 
 /// Return an exact clone of a source node.
@@ -193,37 +494,39 @@ pub fn AiNodeResetParameter(node: *mut ai_bindings::AtNode, param: &str) {
 /// # Parameters
 /// * `node` - the source node to be cloned
 /// * `new_name` - the name given to the cloned node
-/// * `parent` - parent of the cloned node, or NULL for none (global scope)
+/// * `parent` - parent of the cloned node, or `None` for none (global scope)
 /// # Returns
-/// a clone of the source node, or NULL if the source was NULL or was non-clonable (like the options node)
-pub fn AiNodeClone(
-    node: *const ai_bindings::AtNode,
+/// a clone of the source node, or `None` if the source was non-clonable (like the options node) or `new_name` contains a NUL byte
+pub fn AiNodeClone<'s>(
+    node: &Node<'s>,
     new_name: &str,
-    parent: Option<*const ai_bindings::AtNode>,
-) -> *mut ai_bindings::AtNode {
+    parent: Option<&Node<'s>>,
+) -> Option<Node<'s>> {
+    let new_name = AtString::try_from(new_name).ok()?;
     unsafe {
-        ai_bindings::AiNodeClone(
-            node,
-            AtString::from(new_name),
-            parent.unwrap_or(ptr::null_mut()),
-        )
+        Node::from_raw(ai_bindings::AiNodeClone(
+            node.as_ptr(),
+            new_name,
+            parent.map_or(ptr::null_mut(), |p| p.as_ptr()),
+        ))
     }
 }
 
 /// Destroy an existing node.
 ///
-/// This function releases all resources associated with an existing node, including all of its memory, parameters, arrays, etc. Attempts to access a destroyed node will cause undefined behaviour.
+/// This function releases all resources associated with an existing node, including all of its memory, parameters, arrays, etc. Taking the universe mutably guarantees that no [`Node`](struct.Node.html) handle from it is still alive.
 ///
 /// For now, this function will do nothing if called during rendering.
 ///
 /// # Note
 /// This function is intended for a single node or very few nodes, created and destroyed during an interactive session. It has an overhead due to memory release and update of data structures, so don't apply this to a large number of nodes, and never to the whole scene. All nodes in the scene are released efficiently during AiEnd().
 /// # Parameters
-/// * `node` - pointer to an existing node, generally coming from AiNode()
+/// * `universe` - the universe the node belongs to
+/// * `node` - the id of an existing node, generally coming from AiNode()
 /// # Returns
-/// true if the node was destroyed, false otherwise
-pub fn AiNodeDestroy(node: *mut ai_bindings::AtNode) -> bool {
-    unsafe { ai_bindings::AiNodeDestroy(node) }
+/// true if the node was destroyed, false otherwise, including when `node` is not in `universe`
+pub fn AiNodeDestroy(universe: &mut Universe, node: NodeId) -> bool {
+    universe.contains(node) && unsafe { ai_bindings::AiNodeDestroy(node.as_ptr()) }
 }
 
 /// Replace an existing node with another, updating all references to that node.
@@ -231,15 +534,18 @@ pub fn AiNodeDestroy(node: *mut ai_bindings::AtNode) -> bool {
 /// For now, this function will do nothing if called during rendering.
 ///
 /// # Parameters
-/// * `old_node` - pointer to an existing node, generally coming from AiNode()
-/// * `new_node` - pointer to an existing node, generally coming from AiNode()
+/// * `universe` - the universe both nodes belong to
+/// * `old_node` - the id of an existing node, generally coming from AiNode()
+/// * `new_node` - the id of an existing node, generally coming from AiNode()
 /// * `remove` - true if the old_node should be destroyed after replacing references
-pub fn AiNodeReplace(
-    node: *mut ai_bindings::AtNode,
-    new_node: *mut ai_bindings::AtNode,
-    remove: bool,
-) {
-    unsafe { ai_bindings::AiNodeReplace(node, new_node, remove) }
+/// # Returns
+/// true if the references were replaced, false if either node is not in `universe`
+pub fn AiNodeReplace(universe: &mut Universe, old_node: NodeId, new_node: NodeId, remove: bool) -> bool {
+    if !universe.contains(old_node) || !universe.contains(new_node) {
+        return false;
+    }
+    unsafe { ai_bindings::AiNodeReplace(old_node.as_ptr(), new_node.as_ptr(), remove) };
+    true
 }
 
 /// Creates a connection between two shader nodes.
@@ -257,18 +563,22 @@ pub fn AiNodeReplace(
 /// # See also
 /// AiNodeLinkOutput
 /// # Parameters
-/// * `src` - a non-NULL pointer to the source node, to be connected to the target node
+/// * `src` - the source node, to be connected to the target node
 /// * `input` - the input parameter specification in the target node, which can optionally include a component specification (e.g. "Kd.r", "dir.x"), an array element index (e.g. "colors\[1\]") or both (e.g. "colors\[1\].r").
-/// * `target` - a non-NULL pointer to the target node whose input parameter will be connected to the output of the source node
+/// * `target` - the target node whose input parameter will be connected to the output of the source node
 /// # Returns
-/// true if the connection was performed successfully, false otherwise
+/// `Ok` if the connection was performed successfully, [`ArnoldError::Link`](../error/enum.ArnoldError.html) otherwise
 pub fn AiNodeLink(
-    src: *mut ai_bindings::AtNode,
+    src: &Node,
     input: &str,
-    target: *mut ai_bindings::AtNode,
-) -> bool {
-    let input = CString::new(input).unwrap();
-    unsafe { ai_bindings::AiNodeLink(src, input.as_ptr(), target) }
+    target: &Node,
+) -> Result<(), ArnoldError> {
+    let c_input = CString::new(input)?;
+    if unsafe { ai_bindings::AiNodeLink(src.as_ptr(), c_input.as_ptr(), target.as_ptr()) } {
+        Ok(())
+    } else {
+        Err(link_error(src, target, input))
+    }
 }
 
 /// Creates a connection between two shader nodes.
@@ -294,21 +604,33 @@ pub fn AiNodeLink(
 /// # See also
 /// AiNodeLink
 /// # Parameters
-/// * `src` - a non-NULL pointer to the source node, to be connected to the target node
+/// * `src` - the source node, to be connected to the target node
 /// * `output` - selects one of the components (e.g "r", "g", ...), or the whole output of the source node (using "")
-/// * `target` - a non-NULL pointer to the target node whose input parameter will be connected to the output of the source node
+/// * `target` - the target node whose input parameter will be connected to the output of the source node
 /// * `input` - the input parameter specification in the target node, which can optionally include a component specification (e.g. "Kd.r", "dir.x"), an array element index (e.g. "colors\[1\]") or both (e.g. "colors\[1\].r").
 /// # Returns
-/// true if the connection was performed successfully, false otherwise
+/// `Ok` if the connection was performed successfully, [`ArnoldError::Link`](../error/enum.ArnoldError.html) otherwise
 pub fn AiNodeLinkOutput(
-    node: *mut ai_bindings::AtNode,
+    node: &Node,
     output: &str,
-    target: *mut ai_bindings::AtNode,
+    target: &Node,
     input: &str,
-) -> bool {
-    let output = CString::new(output).unwrap();
-    let input = CString::new(input).unwrap();
-    unsafe { ai_bindings::AiNodeLinkOutput(node, output.as_ptr(), target, input.as_ptr()) }
+) -> Result<(), ArnoldError> {
+    let c_output = CString::new(output)?;
+    let c_input = CString::new(input)?;
+    if unsafe { ai_bindings::AiNodeLinkOutput(node.as_ptr(), c_output.as_ptr(), target.as_ptr(), c_input.as_ptr()) } {
+        Ok(())
+    } else {
+        Err(link_error(node, target, input))
+    }
+}
+
+fn link_error(src: &Node, target: &Node, input: &str) -> ArnoldError {
+    ArnoldError::Link {
+        source: src.display_name(),
+        target: target.display_name(),
+        input: input.to_owned(),
+    }
 }
 
 /// Removes a connection from a node input parameter.
@@ -316,13 +638,13 @@ pub fn AiNodeLinkOutput(
 /// Using this function you can disconnect, or unlink, a shader input that has been previously linked. If the specified parameter wasn't already linked, this function won't do anything.
 ///
 /// # Parameters
-/// * `node` - a non-NULL pointer to a node
+/// * `node` - the node to unlink
 /// * `input` - the input parameter specification in the target node, which can optionally include a component specification (e.g. "Kd.r", "dir.x"), an array element index (e.g. "colors\[1\]") or both (e.g. "colors\[1\].r").
 /// # Returns
-/// true if the disconnection was performed successfully, false otherwise
-pub fn AiNodeUnlink(node: *mut ai_bindings::AtNode, input: &str) -> bool {
-    let input = CString::new(input).unwrap();
-    unsafe { ai_bindings::AiNodeUnlink(node, input.as_ptr()) }
+/// true if the disconnection was performed successfully, false otherwise, or an error if `input` contains a NUL byte
+pub fn AiNodeUnlink(node: &Node, input: &str) -> Result<bool, ArnoldError> {
+    let input = CString::new(input)?;
+    Ok(unsafe { ai_bindings::AiNodeUnlink(node.as_ptr(), input.as_ptr()) })
 }
 
 /// Returns true if the input parameter is linked.
@@ -334,9 +656,9 @@ pub fn AiNodeUnlink(node: *mut ai_bindings::AtNode, input: &str) -> bool {
 /// * `input` - the input parameter specification in the given node, which can optionally include a component specification (e.g. "Kd_color.r", "dir.x"), an array element index (e.g. "colors\[1\]") or both (e.g. "colors\[1\].r").
 /// # Returns
 /// true if the input specification (or any of its components) is explicitly linked, e.g. querying for "Kd_color" will return true if either "Kd_color" or any of its R, G, or B components are linked, but querying for "Kd_color.r" will return true only if the R component has been explicitly linked)
-pub fn AiNodeIsLinked(node: *mut ai_bindings::AtNode, input: &str) -> bool {
-    let input = CString::new(input).unwrap();
-    unsafe { ai_bindings::AiNodeIsLinked(node, input.as_ptr()) }
+pub fn AiNodeIsLinked(node: &Node, input: &str) -> Result<bool, ArnoldError> {
+    let input = CString::new(input)?;
+    Ok(unsafe { ai_bindings::AiNodeIsLinked(node.as_ptr(), input.as_ptr()) })
 }
 
 /// Returns the node connected to a given node input parameter.
@@ -344,16 +666,22 @@ pub fn AiNodeIsLinked(node: *mut ai_bindings::AtNode, input: &str) -> bool {
 /// # Parameters
 /// * `node` - node to be queried
 /// * `input` - the input parameter specification in the target node, which can optionally include a component specification (e.g. "Kd_color.r", "dir.x"), an array element index (e.g. "colors\[1\]") or both (e.g. "colors\[1\].r").
-/// * `comp` - if not NULL, the specific component selected on the source node output (-1 for the whole output, [0..3] for a single component) will be returned here
+/// * `comp` - if not `None`, the specific component selected on the source node output (-1 for the whole output, [0..3] for a single component) will be returned here
 /// # Returns
-/// pointer to the connected node, or NULL if the parameter or component is not linked
-pub fn AiNodeGetLink(
-    node: *mut ai_bindings::AtNode,
+/// the connected node, or `None` if the parameter or component is not linked
+pub fn AiNodeGetLink<'s>(
+    node: &Node<'s>,
     input: &str,
-    comp: *mut i32,
-) -> *mut ai_bindings::AtNode {
-    let input = CString::new(input).unwrap();
-    unsafe { ai_bindings::AiNodeGetLink(node, input.as_ptr(), comp) }
+    comp: Option<&mut i32>,
+) -> Result<Option<Node<'s>>, ArnoldError> {
+    let input = CString::new(input)?;
+    Ok(unsafe {
+        Node::from_raw(ai_bindings::AiNodeGetLink(
+            node.as_ptr(),
+            input.as_ptr(),
+            comp.map_or(ptr::null_mut(), |c| c),
+        ))
+    })
 }
 
 /// Return the node's name.
@@ -365,13 +693,10 @@ pub fn AiNodeGetLink(
 /// # Parameters
 /// * `node` - input node
 /// # Returns
-/// the node name string, or NULL if the input node was NULL
-pub fn AiNodeGetName(node: *mut ai_bindings::AtNode) -> &'static str {
-    let slice = unsafe {
-        let data = ai_bindings::AiNodeGetName(node);
-        CStr::from_ptr(data)
-    };
-    str::from_utf8(slice.to_bytes()).unwrap()
+/// the node name string, or an error if it is not valid UTF-8
+pub fn AiNodeGetName<'a>(node: &'a Node) -> Result<&'a str, ArnoldError> {
+    let slice = unsafe { CStr::from_ptr(ai_bindings::AiNodeGetName(node.as_ptr())) };
+    Ok(slice.to_str()?)
 }
 
 /// Return the node entry for this node.
@@ -379,9 +704,10 @@ pub fn AiNodeGetName(node: *mut ai_bindings::AtNode) -> &'static str {
 /// # Parameters
 /// * `node` - input node
 /// # Returns
-/// the node entry for this node, or NULL if the input node was NULL
-pub fn AiNodeGetNodeEntry(node: *mut ai_bindings::AtNode) -> *const ai_bindings::AtNodeEntry {
-    unsafe { ai_bindings::AiNodeGetNodeEntry(node) }
+/// the node entry for this node
+pub fn AiNodeGetNodeEntry<'s>(node: &Node<'s>) -> NodeEntry<'s> {
+    unsafe { NodeEntry::from_raw(ai_bindings::AiNodeGetNodeEntry(node.as_ptr())) }
+        .expect("every node has a node entry")
 }
 
 /// Returns a pointer to the local data in the node.
@@ -390,8 +716,8 @@ pub fn AiNodeGetNodeEntry(node: *mut ai_bindings::AtNode) -> *const ai_bindings:
 /// * `node` - input node
 /// # Returns
 /// pointer to local data
-pub fn AiNodeGetLocalData(node: *const ai_bindings::AtNode) -> *mut c_void {
-    unsafe { ai_bindings::AiNodeGetLocalData(node) }
+pub fn AiNodeGetLocalData(node: &Node) -> *mut c_void {
+    unsafe { ai_bindings::AiNodeGetLocalData(node.as_ptr()) }
 }
 
 /// Sets local data pointer in the node.
@@ -401,8 +727,8 @@ pub fn AiNodeGetLocalData(node: *const ai_bindings::AtNode) -> *mut c_void {
 /// # Parameters
 /// * `node` - input node
 /// * `data` - pointer to new local data
-pub fn AiNodeSetLocalData(node: *mut ai_bindings::AtNode, data: *mut c_void) {
-    unsafe { ai_bindings::AiNodeSetLocalData(node, data) }
+pub fn AiNodeSetLocalData(node: &Node, data: *mut c_void) {
+    unsafe { ai_bindings::AiNodeSetLocalData(node.as_ptr(), data) }
 }
 
 /// Returns a pointer to the per plugin data for the node type, as created in the node_plugin_initialize method.
@@ -411,8 +737,8 @@ pub fn AiNodeSetLocalData(node: *mut ai_bindings::AtNode, data: *mut c_void) {
 /// * `node` - input node
 /// # Returns
 /// pointer to plugin data
-pub fn AiNodeGetPluginData(node: *const ai_bindings::AtNode) -> *mut c_void {
-    unsafe { ai_bindings::AiNodeGetPluginData(node) }
+pub fn AiNodeGetPluginData(node: &Node) -> *mut c_void {
+    unsafe { ai_bindings::AiNodeGetPluginData(node.as_ptr()) }
 }
 
 /// Disable or enable any node in the scene.
@@ -424,20 +750,20 @@ pub fn AiNodeGetPluginData(node: *const ai_bindings::AtNode) -> *mut c_void {
 /// * shader : a disabled shader is replaced with the default shader
 /// * light : disabled lights are not used during rendering
 /// # Parameters
-/// * `node` - pointer to an existing node, generally coming from AiNode()
+/// * `node` - an existing node, generally coming from AiNode()
 /// * `disabled` - true to disable this node, false otherwise
-pub fn AiNodeSetDisabled(node: *mut ai_bindings::AtNode, disabled: bool) {
-    unsafe { ai_bindings::AiNodeSetDisabled(node, disabled) }
+pub fn AiNodeSetDisabled(node: &Node, disabled: bool) {
+    unsafe { ai_bindings::AiNodeSetDisabled(node.as_ptr(), disabled) }
 }
 
 /// Check if a node has been disabled or not.
 ///
 /// # Parameters
-/// * `node` - pointer to an existing node, generally coming from AiNode()
+/// * `node` - an existing node, generally coming from AiNode()
 /// # Returns
 /// true if the node is disabled, false otherwise
-pub fn AiNodeIsDisabled(node: *mut ai_bindings::AtNode) -> bool {
-    unsafe { ai_bindings::AiNodeIsDisabled(node) }
+pub fn AiNodeIsDisabled(node: &Node) -> bool {
+    unsafe { ai_bindings::AiNodeIsDisabled(node.as_ptr()) }
 }
 
 /// Returns the procedural parent of a node.
@@ -447,11 +773,11 @@ pub fn AiNodeIsDisabled(node: *mut ai_bindings::AtNode) -> bool {
 /// # Note
 /// Although this will work in the majority of cases, it doesn't support a few corner cases, such as automatic procedural instancing as used by the procedural .ass cache, where the first procedural that was loaded will be returned.
 /// # Parameters
-/// * `node` - pointer to an existing node, generally coming from AiNode()
+/// * `node` - an existing node, generally coming from AiNode()
 /// # Returns
-/// procedural parent node, or NULL if the node does not come from one
-pub fn AiNodeGetParent(node: *mut ai_bindings::AtNode) -> *mut ai_bindings::AtNode {
-    unsafe { ai_bindings::AiNodeGetParent(node) }
+/// procedural parent node, or `None` if the node does not come from one
+pub fn AiNodeGetParent<'s>(node: &Node<'s>) -> Option<Node<'s>> {
+    unsafe { Node::from_raw(ai_bindings::AiNodeGetParent(node.as_ptr())) }
 }
 
 /// Creates and returns a new AtUserParamIterator for this node.
@@ -461,9 +787,9 @@ pub fn AiNodeGetParent(node: *mut ai_bindings::AtNode) -> *mut ai_bindings::AtNo
 /// # Returns
 /// an iterator over all user parameters on this node
 pub fn AiNodeGetUserParamIterator(
-    node: *mut ai_bindings::AtNode,
+    node: &Node,
 ) -> *mut ai_bindings::AtUserParamIterator {
-    unsafe { ai_bindings::AiNodeGetUserParamIterator(node) }
+    unsafe { ai_bindings::AiNodeGetUserParamIterator(node.as_ptr()) }
 }
 
 /// Destroys a user param iterator when it is no longer needed.
@@ -509,108 +835,221 @@ pub fn AiUserParamIteratorFinished(iter: *const ai_bindings::AtUserParamIterator
     unsafe { ai_bindings::AiUserParamIteratorFinished(iter) }
 }
 
-pub fn AiNodeSetByte(node: *mut ai_bindings::AtNode, param: AtString, val: u8) {
-    unsafe { ai_bindings::AiNodeSetByte(node, param, val) }
+pub fn AiNodeSetByte(node: &Node, param: AtString, val: u8) {
+    unsafe { ai_bindings::AiNodeSetByte(node.as_ptr(), param, val) }
 }
-pub fn AiNodeSetInt(node: *mut ai_bindings::AtNode, param: AtString, val: i32) {
-    unsafe { ai_bindings::AiNodeSetInt(node, param, val) }
+pub fn AiNodeSetInt(node: &Node, param: AtString, val: i32) {
+    unsafe { ai_bindings::AiNodeSetInt(node.as_ptr(), param, val) }
 }
-pub fn AiNodeSetUInt(node: *mut ai_bindings::AtNode, param: AtString, val: u32) {
-    unsafe { ai_bindings::AiNodeSetUInt(node, param, val) }
+pub fn AiNodeSetUInt(node: &Node, param: AtString, val: u32) {
+    unsafe { ai_bindings::AiNodeSetUInt(node.as_ptr(), param, val) }
 }
-pub fn AiNodeSetBool(node: *mut ai_bindings::AtNode, param: AtString, val: bool) {
-    unsafe { ai_bindings::AiNodeSetBool(node, param, val) }
+pub fn AiNodeSetBool(node: &Node, param: AtString, val: bool) {
+    unsafe { ai_bindings::AiNodeSetBool(node.as_ptr(), param, val) }
 }
-pub fn AiNodeSetFlt(node: *mut ai_bindings::AtNode, param: AtString, val: f32) {
-    unsafe { ai_bindings::AiNodeSetFlt(node, param, val) }
+pub fn AiNodeSetFlt(node: &Node, param: AtString, val: f32) {
+    unsafe { ai_bindings::AiNodeSetFlt(node.as_ptr(), param, val) }
 }
 pub fn AiNodeSetPtr(
-    node: *mut ai_bindings::AtNode,
+    node: &Node,
     param: AtString,
     val: *mut ::std::os::raw::c_void,
 ) {
-    unsafe { ai_bindings::AiNodeSetPtr(node, param, val) }
+    unsafe { ai_bindings::AiNodeSetPtr(node.as_ptr(), param, val) }
 }
-pub fn AiNodeSetArray(
-    node: *mut ai_bindings::AtNode,
+/// Set an array parameter, handing ownership of the array to the node.
+///
+/// # Parameters
+/// * `node` - the node to modify
+/// * `param` - name of the array parameter
+/// * `val` - the array, which is destroyed together with the node
+pub fn AiNodeSetArray<T: ArrayElement>(
+    node: &Node,
     param: AtString,
-    val: *mut ai_bindings::AtArray,
+    val: Array<T>,
 ) {
-    unsafe { ai_bindings::AiNodeSetArray(node, param, val) }
+    unsafe { ai_bindings::AiNodeSetArray(node.as_ptr(), param, val.into_raw()) }
 }
 pub fn AiNodeSetMatrix(
-    node: *mut ai_bindings::AtNode,
+    node: &Node,
     param: AtString,
     val: ai_bindings::AtMatrix,
 ) {
-    unsafe { ai_bindings::AiNodeSetMatrix(node, param, val) }
+    unsafe { ai_bindings::AiNodeSetMatrix(node.as_ptr(), param, val) }
 }
-pub fn AiNodeSetStr(node: *mut ai_bindings::AtNode, param: AtString, str: AtString) {
-    unsafe { ai_bindings::AiNodeSetStr(node, param, str) }
+pub fn AiNodeSetStr(node: &Node, param: AtString, str: AtString) {
+    unsafe { ai_bindings::AiNodeSetStr(node.as_ptr(), param, str) }
 }
-pub fn AiNodeSetRGB(node: *mut ai_bindings::AtNode, param: AtString, r: f32, g: f32, b: f32) {
-    unsafe { ai_bindings::AiNodeSetRGB(node, param, r, g, b) }
+pub fn AiNodeSetRGB(node: &Node, param: AtString, r: f32, g: f32, b: f32) {
+    unsafe { ai_bindings::AiNodeSetRGB(node.as_ptr(), param, r, g, b) }
 }
 pub fn AiNodeSetRGBA(
-    node: *mut ai_bindings::AtNode,
+    node: &Node,
     param: AtString,
     r: f32,
     g: f32,
     b: f32,
     a: f32,
 ) {
-    unsafe { ai_bindings::AiNodeSetRGBA(node, param, r, g, b, a) }
+    unsafe { ai_bindings::AiNodeSetRGBA(node.as_ptr(), param, r, g, b, a) }
 }
-pub fn AiNodeSetVec(node: *mut ai_bindings::AtNode, param: AtString, x: f32, y: f32, z: f32) {
-    unsafe { ai_bindings::AiNodeSetVec(node, param, x, y, z) }
+pub fn AiNodeSetVec(node: &Node, param: AtString, x: f32, y: f32, z: f32) {
+    unsafe { ai_bindings::AiNodeSetVec(node.as_ptr(), param, x, y, z) }
 }
-pub fn AiNodeSetVec2(node: *mut ai_bindings::AtNode, param: AtString, x: f32, y: f32) {
-    unsafe { ai_bindings::AiNodeSetVec2(node, param, x, y) }
+pub fn AiNodeSetVec2(node: &Node, param: AtString, x: f32, y: f32) {
+    unsafe { ai_bindings::AiNodeSetVec2(node.as_ptr(), param, x, y) }
 }
-pub fn AiNodeSetAttributes(node: *mut ai_bindings::AtNode, attributes: &str) {
-    let attributes = CString::new(attributes).unwrap();
-    unsafe { ai_bindings::AiNodeSetAttributes(node, attributes.as_ptr()) }
+pub fn AiNodeSetAttributes(node: &Node, attributes: &str) -> Result<(), ArnoldError> {
+    let attributes = CString::new(attributes)?;
+    unsafe { ai_bindings::AiNodeSetAttributes(node.as_ptr(), attributes.as_ptr()) }
+    Ok(())
 }
 
-pub fn AiNodeGetByte(node: *const ai_bindings::AtNode, param: &str) -> u8 {
-    unsafe { ai_bindings::AiNodeGetByte(node, AtString::from(param)) }
+pub fn AiNodeGetByte(node: &Node, param: AtString) -> u8 {
+    unsafe { ai_bindings::AiNodeGetByte(node.as_ptr(), param) }
 }
-pub fn AiNodeGetInt(node: *const ai_bindings::AtNode, param: &str) -> i32 {
-    unsafe { ai_bindings::AiNodeGetInt(node, AtString::from(param)) }
+pub fn AiNodeGetInt(node: &Node, param: AtString) -> i32 {
+    unsafe { ai_bindings::AiNodeGetInt(node.as_ptr(), param) }
 }
-pub fn AiNodeGetUInt(node: *const ai_bindings::AtNode, param: &str) -> u32 {
-    unsafe { ai_bindings::AiNodeGetUInt(node, AtString::from(param)) }
+pub fn AiNodeGetUInt(node: &Node, param: AtString) -> u32 {
+    unsafe { ai_bindings::AiNodeGetUInt(node.as_ptr(), param) }
 }
-pub fn AiNodeGetBool(node: *const ai_bindings::AtNode, param: &str) -> bool {
-    unsafe { ai_bindings::AiNodeGetBool(node, AtString::from(param)) }
+pub fn AiNodeGetBool(node: &Node, param: AtString) -> bool {
+    unsafe { ai_bindings::AiNodeGetBool(node.as_ptr(), param) }
 }
-pub fn AiNodeGetFlt(node: *const ai_bindings::AtNode, param: &str) -> f32 {
-    unsafe { ai_bindings::AiNodeGetFlt(node, AtString::from(param)) }
+pub fn AiNodeGetFlt(node: &Node, param: AtString) -> f32 {
+    unsafe { ai_bindings::AiNodeGetFlt(node.as_ptr(), param) }
 }
-pub fn AiNodeGetRGB(node: *const ai_bindings::AtNode, param: &str) -> ai_bindings::AtRGB {
-    unsafe { ai_bindings::AiNodeGetRGB(node, AtString::from(param)) }
+pub fn AiNodeGetRGB(node: &Node, param: AtString) -> ai_bindings::AtRGB {
+    unsafe { ai_bindings::AiNodeGetRGB(node.as_ptr(), param) }
 }
-pub fn AiNodeGetRGBA(node: *const ai_bindings::AtNode, param: &str) -> ai_bindings::AtRGBA {
-    unsafe { ai_bindings::AiNodeGetRGBA(node, AtString::from(param)) }
+pub fn AiNodeGetRGBA(node: &Node, param: AtString) -> ai_bindings::AtRGBA {
+    unsafe { ai_bindings::AiNodeGetRGBA(node.as_ptr(), param) }
 }
-pub fn AiNodeGetVec(node: *const ai_bindings::AtNode, param: &str) -> AtVector {
-    unsafe { ai_bindings::AiNodeGetVec(node, AtString::from(param)) }
+pub fn AiNodeGetVec(node: &Node, param: AtString) -> AtVector {
+    unsafe { ai_bindings::AiNodeGetVec(node.as_ptr(), param) }
 }
-pub fn AiNodeGetVec2(node: *const ai_bindings::AtNode, param: &str) -> AtVector2 {
-    unsafe { ai_bindings::AiNodeGetVec2(node, AtString::from(param)) }
+pub fn AiNodeGetVec2(node: &Node, param: AtString) -> AtVector2 {
+    unsafe { ai_bindings::AiNodeGetVec2(node.as_ptr(), param) }
 }
-pub fn AiNodeGetStr(node: *const ai_bindings::AtNode, param: &str) -> AtString {
-    unsafe { ai_bindings::AiNodeGetStr(node, AtString::from(param)) }
+pub fn AiNodeGetStr(node: &Node, param: AtString) -> AtString {
+    unsafe { ai_bindings::AiNodeGetStr(node.as_ptr(), param) }
 }
-pub fn AiNodeGetPtr(node: *const ai_bindings::AtNode, param: &str) -> *mut c_void {
-    unsafe { ai_bindings::AiNodeGetPtr(node, AtString::from(param)) }
+pub fn AiNodeGetPtr(node: &Node, param: AtString) -> *mut c_void {
+    unsafe { ai_bindings::AiNodeGetPtr(node.as_ptr(), param) }
 }
-pub fn AiNodeGetArray(
-    node: *const ai_bindings::AtNode,
+/// Copy an array parameter.
+///
+/// The array is copied with AiArrayCopy(), because any handle to the node could replace or destroy the array owned by the node while it is being read.
+///
+/// # Parameters
+/// * `node` - the node to read from
+/// * `param` - name of the array parameter
+/// # Returns
+/// a copy of the array owned by the node, or an error if the node has no such array or its elements are not of type `T`
+pub fn AiNodeGetArray<T: ArrayElement>(
+    node: &Node,
     param: AtString,
-) -> *mut ai_bindings::AtArray {
-    unsafe { ai_bindings::AiNodeGetArray(node, param) }
+) -> Result<Array<T>, ArnoldError> {
+    let array = unsafe { ai_bindings::AiNodeGetArray(node.as_ptr(), param) };
+    if array.is_null() {
+        return Err(ArnoldError::UnknownParam {
+            node: node.display_name(),
+            param: param.to_string_lossy().into_owned(),
+        });
+    }
+    let array_type = AiArrayGetType(array);
+    if array_type != T::TYPE {
+        return Err(node.type_mismatch(&param.to_string_lossy(), array_type, T::TYPE));
+    }
+    Ok(unsafe { Array::from_raw(AiArrayCopy(array)) }.expect("arrays can only be read during a session"))
+}
+pub fn AiNodeGetMatrix(node: &Node, param: AtString) -> ai_bindings::AtMatrix {
+    unsafe { ai_bindings::AiNodeGetMatrix(node.as_ptr(), param) }
 }
-pub fn AiNodeGetMatrix(node: *const ai_bindings::AtNode, param: AtString) -> ai_bindings::AtMatrix {
-    unsafe { ai_bindings::AiNodeGetMatrix(node, param) }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_msg::AI_LOG_NONE;
+    use ai_render::AI_SESSION_BATCH;
+    #[test]
+    fn node_handles() {
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let mut universe = Universe::default(&session);
+        let sphere = AiNode(&universe, "sphere", Some("ball"), None).unwrap();
+        assert_eq!(sphere.name(), Ok("ball"));
+        assert_eq!(sphere.entry().name().to_str(), Ok("sphere"));
+        assert!(sphere.parent().is_none());
+        assert_eq!(AiNodeLookUpByName(&universe, "ball", None).as_ref(), Ok(&sphere));
+        let id = sphere.id();
+        assert!(universe.destroy_node(id));
+        assert!(!universe.destroy_node(id));
+        assert_eq!(
+            AiNodeLookUpByName(&universe, "ball", None),
+            Err(ArnoldError::NodeNotFound { name: "ball".to_owned() })
+        );
+        assert_eq!(
+            AiNode(&universe, "not_a_node_type", Some("nothing"), None),
+            Err(ArnoldError::NodeCreation {
+                node_type: "not_a_node_type".to_owned(),
+                name: "nothing".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn typed_params() {
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let universe = Universe::default(&session);
+        let sphere = AiNode(&universe, "sphere", Some("typed_ball"), None).unwrap();
+        sphere.set("radius", 2.5f32).unwrap();
+        assert_eq!(sphere.get::<f32>("radius"), Ok(2.5));
+        match sphere.get::<bool>("radius") {
+            Err(ArnoldError::TypeMismatch { ref param, expected, .. }) => {
+                assert_eq!(param, "radius");
+                assert_eq!(expected, AI_TYPE_FLOAT);
+            }
+            other => panic!("expected a type mismatch, got {:?}", other),
+        }
+        assert!(sphere.set("radius", "big").is_err());
+        assert_eq!(
+            sphere.get::<f32>("no_such_param"),
+            Err(ArnoldError::UnknownParam {
+                node: "typed_ball".to_owned(),
+                param: "no_such_param".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn user_param_iterator() {
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let universe = Universe::default(&session);
+        let sphere = AiNode(&universe, "sphere", Some("user_ball"), None).unwrap();
+        assert_eq!(sphere.user_params().count(), 0);
+        assert!(AiNodeDeclare(&sphere, "tag", "constant STRING").is_ok());
+        assert!(AiNodeDeclare(&sphere, "weights", "constant ARRAY FLOAT").is_ok());
+        assert!(AiNodeDeclare(&sphere, "tag", "constant INT").is_err());
+        let params: Vec<_> = sphere.user_params().map(|param| (param.name().unwrap().to_owned(), param.array_type())).collect();
+        assert_eq!(params.len(), 2);
+        assert!(params.contains(&("tag".to_owned(), None)));
+        assert!(params.contains(&("weights".to_owned(), Some(AI_TYPE_FLOAT))));
+    }
+
+    #[test]
+    fn node_arrays() {
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let universe = Universe::default(&session);
+        let mesh = AiNode(&universe, "polymesh", Some("array_mesh"), None).unwrap();
+        let nsides = Array::from(vec![3u32, 4]);
+        AiNodeSetArray(&mesh, AtString::try_from("nsides").unwrap(), nsides);
+        let mut copy = AiNodeGetArray::<u32>(&mesh, AtString::try_from("nsides").unwrap()).unwrap();
+        assert_eq!(copy.key(0), &[3, 4]);
+        copy.set(0, 5).unwrap();
+        AiNodeSetArray(&mesh, AtString::try_from("nsides").unwrap(), Array::from(vec![6u32]));
+        assert_eq!(copy.key(0), &[5, 4]);
+        assert!(AiNodeGetArray::<f32>(&mesh, AtString::try_from("nsides").unwrap()).is_err());
+        assert!(AiNodeGetArray::<u32>(&mesh, AtString::try_from("no_such_array").unwrap()).is_err());
+    }
 }
@@ -0,0 +1,795 @@
+//! Node Parameter API
+//!
+//! Every node parameter has a type (AI_TYPE_FLOAT, AI_TYPE_RGB, etc) declared by its node entry, or by AiNodeDeclare() for user-defined parameters.
+//! [`ParamValue`](enum.ParamValue.html) holds a value of any of those types, and [`ParamData`](trait.ParamData.html) converts between it and the matching Rust type, so node parameters can be read and written without picking the right AiNodeGet*/AiNodeSet* function by hand:
+//! ```
+//! sphere.set("radius", 2.0)?;
+//! let radius = sphere.get::<f32>("radius")?;
+//! ```
+//!
+//! [`ParamEntry`](struct.ParamEntry.html) describes a parameter declared by a node entry: its name, type, default value and, for enums, the list of choices.
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+
+use ai_array::AiArrayGetType;
+use ai_bindings;
+pub use ai_bindings::{AtEnum, AtParamEntry, AtParamValue, AtUserParamEntry};
+use error::ArnoldError;
+use ai_bindings::{AtArray, AtList, AtMatrix, AtNode, AtRGB, AtRGBA};
+use ai_render::Session;
+use ai_string::{from_c_str, AtString};
+use ai_vector::{AtVector, AtVector2};
+
+use std::error::Error;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::os::raw::{c_char, c_void};
+use std::str::{FromStr, Utf8Error};
+
+/// The type of a parameter, array element or shader output.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParamType {
+    /// `u8`
+    Byte = ai_bindings::AI_TYPE_BYTE as u8,
+    /// `i32`
+    Int = ai_bindings::AI_TYPE_INT as u8,
+    /// `u32`
+    UInt = ai_bindings::AI_TYPE_UINT as u8,
+    /// `bool`
+    Boolean = ai_bindings::AI_TYPE_BOOLEAN as u8,
+    /// `f32`
+    Float = ai_bindings::AI_TYPE_FLOAT as u8,
+    /// [`AtRGB`](../ai_color/struct.AtRGB.html)
+    RGB = ai_bindings::AI_TYPE_RGB as u8,
+    /// [`AtRGBA`](../ai_color/struct.AtRGBA.html)
+    RGBA = ai_bindings::AI_TYPE_RGBA as u8,
+    /// [`AtVector`](../ai_vector/struct.AtVector.html)
+    Vector = ai_bindings::AI_TYPE_VECTOR as u8,
+    /// [`AtVector2`](../ai_vector/struct.AtVector2.html)
+    Vector2 = ai_bindings::AI_TYPE_VECTOR2 as u8,
+    /// `AtString`, read and written as `String`
+    String = ai_bindings::AI_TYPE_STRING as u8,
+    /// `*mut c_void`
+    Pointer = ai_bindings::AI_TYPE_POINTER as u8,
+    /// `*mut AtNode`, read and written through [`Node`](../ai_nodes/struct.Node.html)
+    Node = ai_bindings::AI_TYPE_NODE as u8,
+    /// `*mut AtArray`
+    Array = ai_bindings::AI_TYPE_ARRAY as u8,
+    /// [`AtMatrix`](../ai_bindings/struct.AtMatrix.html)
+    Matrix = ai_bindings::AI_TYPE_MATRIX as u8,
+    /// `i32` index into the enum choices
+    Enum = ai_bindings::AI_TYPE_ENUM as u8,
+    /// Shader closure, not accessible from Rust
+    Closure = ai_bindings::AI_TYPE_CLOSURE as u8,
+    /// `u16`
+    UShort = ai_bindings::AI_TYPE_USHORT as u8,
+    /// Half precision float, stored as `u16`
+    Half = ai_bindings::AI_TYPE_HALF as u8,
+    /// No type, as reported for an unknown or missing parameter
+    Undefined = ai_bindings::AI_TYPE_UNDEFINED as u8,
+}
+
+const PARAM_TYPES: [ParamType; 19] = [
+    ParamType::Byte,
+    ParamType::Int,
+    ParamType::UInt,
+    ParamType::Boolean,
+    ParamType::Float,
+    ParamType::RGB,
+    ParamType::RGBA,
+    ParamType::Vector,
+    ParamType::Vector2,
+    ParamType::String,
+    ParamType::Pointer,
+    ParamType::Node,
+    ParamType::Array,
+    ParamType::Matrix,
+    ParamType::Enum,
+    ParamType::Closure,
+    ParamType::UShort,
+    ParamType::Half,
+    ParamType::Undefined,
+];
+
+impl ParamType {
+    /// Convert a raw AI_TYPE_* code, as passed to and returned by the C API. Unknown codes map to `Undefined`.
+    pub fn from_code(code: u8) -> ParamType {
+        PARAM_TYPES
+            .iter()
+            .cloned()
+            .find(|param_type| param_type.code() == code)
+            .unwrap_or(ParamType::Undefined)
+    }
+
+    /// The raw AI_TYPE_* code, as passed to and returned by the C API.
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+
+    /// The type name as used in .ass files and AiNodeDeclare(), such as "FLOAT".
+    pub fn name(self) -> &'static str {
+        match self {
+            ParamType::Byte => "BYTE",
+            ParamType::Int => "INT",
+            ParamType::UInt => "UINT",
+            ParamType::Boolean => "BOOL",
+            ParamType::Float => "FLOAT",
+            ParamType::RGB => "RGB",
+            ParamType::RGBA => "RGBA",
+            ParamType::Vector => "VECTOR",
+            ParamType::Vector2 => "VECTOR2",
+            ParamType::String => "STRING",
+            ParamType::Pointer => "POINTER",
+            ParamType::Node => "NODE",
+            ParamType::Array => "ARRAY",
+            ParamType::Matrix => "MATRIX",
+            ParamType::Enum => "ENUM",
+            ParamType::Closure => "CLOSURE",
+            ParamType::UShort => "USHORT",
+            ParamType::Half => "HALF",
+            ParamType::Undefined => "UNDEFINED",
+        }
+    }
+
+    /// The size in bytes of one element of this type in an AtArray.
+    pub fn size(self) -> usize {
+        match self {
+            ParamType::Byte => mem::size_of::<u8>(),
+            ParamType::Int | ParamType::Enum => mem::size_of::<i32>(),
+            ParamType::UInt => mem::size_of::<u32>(),
+            ParamType::Boolean => mem::size_of::<bool>(),
+            ParamType::Float => mem::size_of::<f32>(),
+            ParamType::RGB => mem::size_of::<AtRGB>(),
+            ParamType::RGBA => mem::size_of::<AtRGBA>(),
+            ParamType::Vector => mem::size_of::<AtVector>(),
+            ParamType::Vector2 => mem::size_of::<AtVector2>(),
+            ParamType::String => mem::size_of::<AtString>(),
+            ParamType::Matrix => mem::size_of::<AtMatrix>(),
+            ParamType::Pointer | ParamType::Node | ParamType::Array | ParamType::Closure => {
+                mem::size_of::<*mut c_void>()
+            }
+            ParamType::UShort | ParamType::Half => mem::size_of::<u16>(),
+            ParamType::Undefined => 0,
+        }
+    }
+}
+
+impl fmt::Display for ParamType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ParamType {
+    type Err = ParseParamTypeError;
+
+    /// Parse a type name such as "FLOAT" or "vector2", case-insensitively.
+    fn from_str(s: &str) -> Result<ParamType, ParseParamTypeError> {
+        let upper = s.trim().to_uppercase();
+        if upper == "BOOLEAN" {
+            return Ok(ParamType::Boolean);
+        }
+        PARAM_TYPES
+            .iter()
+            .cloned()
+            .find(|param_type| param_type.name() == upper)
+            .ok_or_else(|| ParseParamTypeError { name: s.to_owned() })
+    }
+}
+
+/// Error returned when a string is not a parameter type name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseParamTypeError {
+    name: String,
+}
+
+impl fmt::Display for ParseParamTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown parameter type '{}'", self.name)
+    }
+}
+
+impl Error for ParseParamTypeError {}
+
+pub const AI_TYPE_BYTE: ParamType = ParamType::Byte;
+pub const AI_TYPE_INT: ParamType = ParamType::Int;
+pub const AI_TYPE_UINT: ParamType = ParamType::UInt;
+pub const AI_TYPE_BOOLEAN: ParamType = ParamType::Boolean;
+pub const AI_TYPE_FLOAT: ParamType = ParamType::Float;
+pub const AI_TYPE_RGB: ParamType = ParamType::RGB;
+pub const AI_TYPE_RGBA: ParamType = ParamType::RGBA;
+pub const AI_TYPE_VECTOR: ParamType = ParamType::Vector;
+pub const AI_TYPE_VECTOR2: ParamType = ParamType::Vector2;
+pub const AI_TYPE_STRING: ParamType = ParamType::String;
+pub const AI_TYPE_POINTER: ParamType = ParamType::Pointer;
+pub const AI_TYPE_NODE: ParamType = ParamType::Node;
+pub const AI_TYPE_ARRAY: ParamType = ParamType::Array;
+pub const AI_TYPE_MATRIX: ParamType = ParamType::Matrix;
+pub const AI_TYPE_ENUM: ParamType = ParamType::Enum;
+pub const AI_TYPE_CLOSURE: ParamType = ParamType::Closure;
+pub const AI_TYPE_USHORT: ParamType = ParamType::UShort;
+pub const AI_TYPE_HALF: ParamType = ParamType::Half;
+pub const AI_TYPE_UNDEFINED: ParamType = ParamType::Undefined;
+pub const AI_TYPE_NONE: ParamType = ParamType::Undefined;
+
+/// A node parameter value of any of the Arnold parameter types.
+#[derive(Debug, Clone)]
+pub enum ParamValue {
+    Byte(u8),
+    Int(i32),
+    UInt(u32),
+    Bool(bool),
+    Float(f32),
+    RGB(AtRGB),
+    RGBA(AtRGBA),
+    Vector(AtVector),
+    Vector2(AtVector2),
+    String(String),
+    Matrix(AtMatrix),
+    /// Index of the selected enum choice
+    Enum(i32),
+    /// Node linked through a parameter of type AI_TYPE_NODE
+    Node(*mut AtNode),
+    Pointer(*mut c_void),
+}
+
+impl ParamValue {
+    /// The parameter type of this value.
+    pub fn param_type(&self) -> ParamType {
+        match *self {
+            ParamValue::Byte(_) => AI_TYPE_BYTE,
+            ParamValue::Int(_) => AI_TYPE_INT,
+            ParamValue::UInt(_) => AI_TYPE_UINT,
+            ParamValue::Bool(_) => AI_TYPE_BOOLEAN,
+            ParamValue::Float(_) => AI_TYPE_FLOAT,
+            ParamValue::RGB(_) => AI_TYPE_RGB,
+            ParamValue::RGBA(_) => AI_TYPE_RGBA,
+            ParamValue::Vector(_) => AI_TYPE_VECTOR,
+            ParamValue::Vector2(_) => AI_TYPE_VECTOR2,
+            ParamValue::String(_) => AI_TYPE_STRING,
+            ParamValue::Matrix(_) => AI_TYPE_MATRIX,
+            ParamValue::Enum(_) => AI_TYPE_ENUM,
+            ParamValue::Node(_) => AI_TYPE_NODE,
+            ParamValue::Pointer(_) => AI_TYPE_POINTER,
+        }
+    }
+
+    /// Check if this value can be assigned to a parameter of type `param_type`.
+    ///
+    /// Enum parameters accept both the index (`Int`) and the name (`String`) of a choice.
+    pub fn fits(&self, param_type: ParamType) -> bool {
+        match *self {
+            ParamValue::Int(_) | ParamValue::String(_) if param_type == AI_TYPE_ENUM => true,
+            _ => self.param_type() == param_type,
+        }
+    }
+}
+
+/// Conversion between Rust types and [`ParamValue`](enum.ParamValue.html).
+pub trait ParamData: Sized {
+    /// The parameter type this Rust type maps to.
+    const TYPE: ParamType;
+    /// Wrap `self` in a [`ParamValue`](enum.ParamValue.html).
+    fn into_value(self) -> ParamValue;
+    /// Unwrap a [`ParamValue`](enum.ParamValue.html), returning `None` if it holds a different type.
+    fn from_value(value: ParamValue) -> Option<Self>;
+}
+
+macro_rules! impl_param_data {
+    ($t:ty, $variant:ident, $param_type:expr) => {
+        impl ParamData for $t {
+            const TYPE: ParamType = $param_type;
+            fn into_value(self) -> ParamValue {
+                ParamValue::$variant(self)
+            }
+            fn from_value(value: ParamValue) -> Option<Self> {
+                match value {
+                    ParamValue::$variant(v) => Some(v),
+                    _ => None,
+                }
+            }
+        }
+
+        impl From<$t> for ParamValue {
+            fn from(value: $t) -> ParamValue {
+                ParamValue::$variant(value)
+            }
+        }
+    };
+}
+
+impl_param_data!(u8, Byte, AI_TYPE_BYTE);
+impl_param_data!(u32, UInt, AI_TYPE_UINT);
+impl_param_data!(bool, Bool, AI_TYPE_BOOLEAN);
+impl_param_data!(f32, Float, AI_TYPE_FLOAT);
+impl_param_data!(AtRGB, RGB, AI_TYPE_RGB);
+impl_param_data!(AtRGBA, RGBA, AI_TYPE_RGBA);
+impl_param_data!(AtVector, Vector, AI_TYPE_VECTOR);
+impl_param_data!(AtVector2, Vector2, AI_TYPE_VECTOR2);
+impl_param_data!(String, String, AI_TYPE_STRING);
+impl_param_data!(AtMatrix, Matrix, AI_TYPE_MATRIX);
+
+// Enum parameters are read back as their index, so an i32 accepts both.
+impl ParamData for i32 {
+    const TYPE: ParamType = AI_TYPE_INT;
+    fn into_value(self) -> ParamValue {
+        ParamValue::Int(self)
+    }
+    fn from_value(value: ParamValue) -> Option<Self> {
+        match value {
+            ParamValue::Int(v) | ParamValue::Enum(v) => Some(v),
+            _ => None,
+        }
+    }
+}
+
+impl From<i32> for ParamValue {
+    fn from(value: i32) -> ParamValue {
+        ParamValue::Int(value)
+    }
+}
+
+impl<'a> From<&'a str> for ParamValue {
+    fn from(value: &'a str) -> ParamValue {
+        ParamValue::String(value.to_owned())
+    }
+}
+
+/// A parameter declared by a node entry.
+///
+/// Parameter entries are owned by their node entry and stay valid for the session `'s`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParamEntry<'s> {
+    pentry: *const AtParamEntry,
+    _session: PhantomData<&'s Session>,
+}
+
+impl<'s> ParamEntry<'s> {
+    /// Wrap a raw parameter entry pointer, returning `None` for NULL.
+    ///
+    /// # Safety
+    /// `pentry` must be NULL or point to a parameter entry whose node entry stays installed for `'s`.
+    pub unsafe fn from_raw(pentry: *const AtParamEntry) -> Option<ParamEntry<'s>> {
+        if pentry.is_null() {
+            None
+        } else {
+            Some(ParamEntry {
+                pentry: pentry,
+                _session: PhantomData,
+            })
+        }
+    }
+
+    /// The raw parameter entry pointer, for use with the C API.
+    pub fn as_ptr(&self) -> *const AtParamEntry {
+        self.pentry
+    }
+
+    /// The name of the parameter, such as "Kd".
+    pub fn name(&self) -> AtString {
+        AiParamGetName(self.pentry)
+    }
+
+    /// The type of the parameter (AI_TYPE_FLOAT, etc).
+    pub fn param_type(&self) -> ParamType {
+        AiParamGetType(self.pentry)
+    }
+
+    /// The name of the parameter type, such as "FLOAT".
+    pub fn type_name(&self) -> &'static str {
+        self.param_type().name()
+    }
+
+    /// The element type of an array parameter, or `None` if the parameter is not an array.
+    pub fn array_type(&self) -> Option<ParamType> {
+        if self.param_type() != AI_TYPE_ARRAY {
+            return None;
+        }
+        let array = unsafe { *(AiParamGetDefault(self.pentry) as *const *const AtArray) };
+        if array.is_null() {
+            None
+        } else {
+            Some(AiArrayGetType(array))
+        }
+    }
+
+    /// The default value of the parameter.
+    ///
+    /// Returns `None` for types that [`ParamValue`](enum.ParamValue.html) cannot hold, such as arrays and closures.
+    pub fn default(&self) -> Option<ParamValue> {
+        unsafe { read_value(AiParamGetDefault(self.pentry), self.param_type()) }
+    }
+
+    /// The choices of an enum parameter, in index order. Empty if the parameter is not an enum.
+    ///
+    /// # Returns
+    /// the choices, or an error if one of them is not valid UTF-8
+    pub fn enum_choices(&self) -> Result<Vec<&'s str>, Utf8Error> {
+        let mut choices = Vec::new();
+        if self.param_type() != AI_TYPE_ENUM {
+            return Ok(choices);
+        }
+        let mut choice = AiParamGetEnum(self.pentry) as *const *const c_char;
+        if choice.is_null() {
+            return Ok(choices);
+        }
+        unsafe {
+            while !(*choice).is_null() {
+                choices.push(CStr::from_ptr(*choice).to_str()?);
+                choice = choice.offset(1);
+            }
+        }
+        Ok(choices)
+    }
+}
+
+/// A user-defined parameter declared on a node with AiNodeDeclare().
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UserParamEntry<'s> {
+    upentry: *const AtUserParamEntry,
+    _session: PhantomData<&'s Session>,
+}
+
+impl<'s> UserParamEntry<'s> {
+    /// Wrap a raw user parameter entry pointer, returning `None` for NULL.
+    ///
+    /// # Safety
+    /// `upentry` must be NULL or point to a user parameter entry whose node outlives `'s`.
+    pub unsafe fn from_raw(upentry: *const AtUserParamEntry) -> Option<UserParamEntry<'s>> {
+        if upentry.is_null() {
+            None
+        } else {
+            Some(UserParamEntry {
+                upentry: upentry,
+                _session: PhantomData,
+            })
+        }
+    }
+
+    /// The raw user parameter entry pointer, for use with the C API.
+    pub fn as_ptr(&self) -> *const AtUserParamEntry {
+        self.upentry
+    }
+
+    /// The name of the user parameter, or an error if it is not valid UTF-8.
+    pub fn name(&self) -> Result<&'s str, Utf8Error> {
+        AiUserParamGetName(self)
+    }
+
+    /// The type of the user parameter (AI_TYPE_FLOAT, etc).
+    pub fn param_type(&self) -> ParamType {
+        AiUserParamGetType(self.upentry)
+    }
+
+    /// The element type of an array user parameter, or `None` if the parameter is not an array.
+    pub fn array_type(&self) -> Option<ParamType> {
+        if self.param_type() != AI_TYPE_ARRAY {
+            None
+        } else {
+            Some(AiUserParamGetArrayType(self.upentry))
+        }
+    }
+
+    /// The category of the user parameter (AI_USERDEF_CONSTANT, etc).
+    pub fn category(&self) -> u8 {
+        AiUserParamGetCategory(self.upentry)
+    }
+}
+
+/// Read an `AtParamValue` holding a value of type `param_type`.
+///
+/// Returns `None` for NULL, for strings that are not valid UTF-8 and for types that [`ParamValue`](enum.ParamValue.html) cannot hold, such as arrays and closures.
+///
+/// # Safety
+/// `value` must be NULL or point to a valid `AtParamValue` of type `param_type`.
+pub(crate) unsafe fn read_value(value: *const AtParamValue, param_type: ParamType) -> Option<ParamValue> {
+    if value.is_null() {
+        return None;
+    }
+    // Every member of AtParamValue lives at offset 0 of the union.
+    Some(match param_type {
+        AI_TYPE_BYTE => ParamValue::Byte(*(value as *const u8)),
+        AI_TYPE_INT => ParamValue::Int(*(value as *const i32)),
+        AI_TYPE_UINT => ParamValue::UInt(*(value as *const u32)),
+        AI_TYPE_BOOLEAN => ParamValue::Bool(*(value as *const bool)),
+        AI_TYPE_FLOAT => ParamValue::Float(*(value as *const f32)),
+        AI_TYPE_RGB => ParamValue::RGB(*(value as *const AtRGB)),
+        AI_TYPE_RGBA => ParamValue::RGBA(*(value as *const AtRGBA)),
+        AI_TYPE_VECTOR => ParamValue::Vector(*(value as *const AtVector)),
+        AI_TYPE_VECTOR2 => ParamValue::Vector2(*(value as *const AtVector2)),
+        AI_TYPE_STRING => {
+            let string = *(value as *const AtString);
+            ParamValue::String(if string.data.is_null() || string.empty() {
+                String::new()
+            } else {
+                string.to_str().ok()?.to_owned()
+            })
+        }
+        AI_TYPE_MATRIX => {
+            let matrix = *(value as *const *const AtMatrix);
+            if matrix.is_null() {
+                return None;
+            }
+            ParamValue::Matrix(*matrix)
+        }
+        AI_TYPE_ENUM => ParamValue::Enum(*(value as *const i32)),
+        AI_TYPE_NODE => ParamValue::Node(*(value as *const *mut AtNode)),
+        AI_TYPE_POINTER => ParamValue::Pointer(*(value as *const *mut c_void)),
+        _ => return None,
+    })
+}
+
+/// Parameter declarations of a node type implemented in Rust, such as a [`Shader`](../ai_shaders/trait.Shader.html).
+///
+/// Parameters are numbered in declaration order, starting at 0, and each declaration returns the index of the new parameter, or an error if a name or default contains a NUL byte. Shaders evaluate their parameters by this index:
+/// ```
+/// fn parameters(params: &mut ParamList) -> Result<(), ArnoldError> {
+///     params.rgb("color", AtRGB { r: 1.0, g: 1.0, b: 1.0 })?; // 0
+///     params.float("intensity", 1.0)?; // 1
+///     Ok(())
+/// }
+/// ```
+pub struct ParamList<'a> {
+    params: *mut AtList,
+    count: i32,
+    _list: PhantomData<&'a mut AtList>,
+}
+
+impl<'a> ParamList<'a> {
+    /// Wrap the parameter list passed to a node_parameters method.
+    ///
+    /// # Safety
+    /// `params` must be the list passed to the node_parameters method that is running.
+    pub(crate) unsafe fn from_raw(params: *mut AtList) -> ParamList<'a> {
+        ParamList {
+            params: params,
+            count: 0,
+            _list: PhantomData,
+        }
+    }
+
+    /// Number of parameters declared so far.
+    pub fn len(&self) -> i32 {
+        self.count
+    }
+
+    /// Check if no parameters have been declared.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Declare a parameter through one of the AiNodeParam* functions.
+    fn declare<F: FnOnce(*mut AtList, *const c_char)>(&mut self, name: &str, declare: F) -> Result<i32, ArnoldError> {
+        let name = CString::new(name)?;
+        declare(self.params, name.as_ptr());
+        self.count += 1;
+        Ok(self.count - 1)
+    }
+
+    /// Declare a byte parameter.
+    pub fn byte(&mut self, name: &str, default: u8) -> Result<i32, ArnoldError> {
+        self.declare(name, |params, name| unsafe { ai_bindings::AiNodeParamByte(params, -1, name, default) })
+    }
+
+    /// Declare an integer parameter.
+    pub fn int(&mut self, name: &str, default: i32) -> Result<i32, ArnoldError> {
+        self.declare(name, |params, name| unsafe { ai_bindings::AiNodeParamInt(params, -1, name, default) })
+    }
+
+    /// Declare an unsigned integer parameter.
+    pub fn uint(&mut self, name: &str, default: u32) -> Result<i32, ArnoldError> {
+        self.declare(name, |params, name| unsafe { ai_bindings::AiNodeParamUInt(params, -1, name, default) })
+    }
+
+    /// Declare a boolean parameter.
+    pub fn bool(&mut self, name: &str, default: bool) -> Result<i32, ArnoldError> {
+        self.declare(name, |params, name| unsafe { ai_bindings::AiNodeParamBool(params, -1, name, default) })
+    }
+
+    /// Declare a float parameter.
+    pub fn float(&mut self, name: &str, default: f32) -> Result<i32, ArnoldError> {
+        self.declare(name, |params, name| unsafe { ai_bindings::AiNodeParamFlt(params, -1, name, default) })
+    }
+
+    /// Declare an RGB color parameter.
+    pub fn rgb(&mut self, name: &str, default: AtRGB) -> Result<i32, ArnoldError> {
+        self.declare(name, |params, name| unsafe {
+            ai_bindings::AiNodeParamRGB(params, -1, name, default.r, default.g, default.b)
+        })
+    }
+
+    /// Declare an RGBA color parameter.
+    pub fn rgba(&mut self, name: &str, default: AtRGBA) -> Result<i32, ArnoldError> {
+        self.declare(name, |params, name| unsafe {
+            ai_bindings::AiNodeParamRGBA(params, -1, name, default.r, default.g, default.b, default.a)
+        })
+    }
+
+    /// Declare a vector parameter.
+    pub fn vector(&mut self, name: &str, default: AtVector) -> Result<i32, ArnoldError> {
+        self.declare(name, |params, name| unsafe {
+            ai_bindings::AiNodeParamVec(params, -1, name, default.x, default.y, default.z)
+        })
+    }
+
+    /// Declare a 2D vector parameter.
+    pub fn vector2(&mut self, name: &str, default: AtVector2) -> Result<i32, ArnoldError> {
+        self.declare(name, |params, name| unsafe {
+            ai_bindings::AiNodeParamVec2(params, -1, name, default.x, default.y)
+        })
+    }
+
+    /// Declare a string parameter.
+    pub fn string(&mut self, name: &str, default: &str) -> Result<i32, ArnoldError> {
+        let default = CString::new(default)?;
+        self.declare(name, |params, name| unsafe {
+            ai_bindings::AiNodeParamStr(params, -1, name, default.as_ptr())
+        })
+    }
+}
+
+/// Returns the name of a parameter.
+///
+/// # Parameters
+/// * `pentry` - input parameter entry
+/// # Returns
+/// the parameter name, such as "Kd"
+pub fn AiParamGetName(pentry: *const AtParamEntry) -> AtString {
+    unsafe { ai_bindings::AiParamGetName(pentry) }
+}
+
+/// Returns the type of a parameter.
+///
+/// # Parameters
+/// * `pentry` - input parameter entry
+/// # Returns
+/// the type of the parameter (AI_TYPE_FLOAT, etc)
+pub fn AiParamGetType(pentry: *const AtParamEntry) -> ParamType {
+    ParamType::from_code(unsafe { ai_bindings::AiParamGetType(pentry) })
+}
+
+/// Returns a pointer to the default value of a parameter.
+///
+/// # Parameters
+/// * `pentry` - input parameter entry
+/// # Returns
+/// pointer to the default value of the parameter
+pub fn AiParamGetDefault(pentry: *const AtParamEntry) -> *const AtParamValue {
+    unsafe { ai_bindings::AiParamGetDefault(pentry) }
+}
+
+/// Returns the list of choices of an enum parameter.
+///
+/// # Parameters
+/// * `pentry` - input parameter entry
+/// # Returns
+/// NULL-terminated list of enum choices, or NULL if the parameter is not an enum
+pub fn AiParamGetEnum(pentry: *const AtParamEntry) -> AtEnum {
+    unsafe { ai_bindings::AiParamGetEnum(pentry) }
+}
+
+/// Returns the name of a parameter type.
+///
+/// # Parameters
+/// * `_session` - the active session
+/// * `type` - a parameter type (AI_TYPE_FLOAT, etc)
+/// # Returns
+/// the type name string, such as "FLOAT", or an error if it is not valid UTF-8
+pub fn AiParamGetTypeName(_session: &Session, type_: ParamType) -> Result<&'static str, Utf8Error> {
+    let name = unsafe { from_c_str(ai_bindings::AiParamGetTypeName(type_.code())) }?;
+    Ok(name.unwrap_or(""))
+}
+
+/// Returns the size in bytes of a parameter type.
+///
+/// # Parameters
+/// * `_session` - the active session
+/// * `type` - a parameter type (AI_TYPE_FLOAT, etc)
+/// # Returns
+/// the size of the type, in bytes
+pub fn AiParamGetTypeSize(_session: &Session, type_: ParamType) -> i32 {
+    unsafe { ai_bindings::AiParamGetTypeSize(type_.code()) }
+}
+
+/// Returns the type of a user-defined parameter.
+///
+/// # Parameters
+/// * `upentry` - input user-defined parameter entry
+/// # Returns
+/// the type of the parameter (AI_TYPE_FLOAT, etc)
+pub fn AiUserParamGetType(upentry: *const AtUserParamEntry) -> ParamType {
+    ParamType::from_code(unsafe { ai_bindings::AiUserParamGetType(upentry) } as u8)
+}
+
+/// Returns the name of a user-defined parameter.
+///
+/// # Parameters
+/// * `upentry` - input user-defined parameter entry
+/// # Returns
+/// the name of the user-defined parameter, or an error if it is not valid UTF-8
+pub fn AiUserParamGetName<'a>(upentry: &UserParamEntry<'a>) -> Result<&'a str, Utf8Error> {
+    unsafe { CStr::from_ptr(ai_bindings::AiUserParamGetName(upentry.as_ptr())) }.to_str()
+}
+
+/// Returns the array element type of a user-defined parameter.
+///
+/// # Parameters
+/// * `upentry` - input user-defined parameter entry
+/// # Returns
+/// the type of the array elements (AI_TYPE_FLOAT, etc), only meaningful for array parameters
+pub fn AiUserParamGetArrayType(upentry: *const AtUserParamEntry) -> ParamType {
+    ParamType::from_code(unsafe { ai_bindings::AiUserParamGetArrayType(upentry) } as u8)
+}
+
+/// Returns the category of a user-defined parameter.
+///
+/// # Parameters
+/// * `upentry` - input user-defined parameter entry
+/// # Returns
+/// the category of the parameter (AI_USERDEF_CONSTANT, etc)
+pub fn AiUserParamGetCategory(upentry: *const AtUserParamEntry) -> u8 {
+    unsafe { ai_bindings::AiUserParamGetCategory(upentry) as u8 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_msg::AI_LOG_NONE;
+    use ai_node_entry::AiNodeEntryLookUp;
+    use ai_render::AI_SESSION_BATCH;
+    use std::convert::TryFrom;
+    #[test]
+    fn param_types() {
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        for param_type in PARAM_TYPES.iter().cloned() {
+            assert_eq!(ParamType::from_code(param_type.code()), param_type);
+            assert_eq!(param_type.name().parse(), Ok(param_type));
+        }
+        assert_eq!(AiParamGetTypeName(&session, ParamType::Boolean), Ok(ParamType::Boolean.name()));
+        assert_eq!(ParamType::from_code(200), ParamType::Undefined);
+        assert_eq!("vector2".parse(), Ok(ParamType::Vector2));
+        assert!("FLOAT3".parse::<ParamType>().is_err());
+        assert_eq!(ParamType::RGB.size(), 12);
+        assert_eq!(<f32 as ParamData>::TYPE, ParamType::Float);
+        assert_eq!(AiParamGetTypeSize(&session, ParamType::Matrix) as usize, ParamType::Matrix.size());
+    }
+    #[test]
+    fn param_entries() {
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let polymesh = AiNodeEntryLookUp(&session, AtString::try_from("polymesh").unwrap()).unwrap();
+
+        let subdiv_type = polymesh.param("subdiv_type").unwrap();
+        assert_eq!(subdiv_type.name().to_str(), Ok("subdiv_type"));
+        assert_eq!(subdiv_type.type_name(), "ENUM");
+        assert_eq!(subdiv_type.enum_choices(), Ok(vec!["none", "catclark", "linear"]));
+        match subdiv_type.default() {
+            Some(ParamValue::Enum(0)) => {}
+            other => panic!("unexpected default {:?}", other),
+        }
+
+        let vlist = polymesh.param("vlist").unwrap();
+        assert_eq!(vlist.param_type(), ParamType::Array);
+        assert_eq!(vlist.array_type(), Some(ParamType::Vector));
+        assert_eq!(vlist.enum_choices(), Ok(Vec::new()));
+
+        assert!(polymesh.param("no_such_param").is_none());
+        assert!(polymesh.param_at(polymesh.num_params()).is_none());
+    }
+
+    #[test]
+    fn param_iterators() {
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let sphere = AiNodeEntryLookUp(&session, AtString::try_from("sphere").unwrap()).unwrap();
+        let names: Vec<String> = sphere.params().map(|param| param.name().to_str().unwrap().to_owned()).collect();
+        assert_eq!(names.len() as i32, sphere.num_params());
+        assert!(names.iter().any(|name| name == "radius"));
+        // Dropping a partially consumed iterator frees it
+        assert!(sphere.params().next().is_some());
+        assert_eq!(sphere.metadata(Some("no_such_param")).unwrap().count(), 0);
+        assert!(sphere.metadata(Some("bad\0param")).is_err());
+    }
+}
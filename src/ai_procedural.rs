@@ -0,0 +1,306 @@
+//! Procedural API
+//!
+//! A procedural generates the nodes it contains when the scene is expanded, for example to scatter instances or build crowds on the fly. It can be implemented in Rust with the [`Procedural`](trait.Procedural.html) trait and installed with [`install_procedural`](fn.install_procedural.html):
+//! ```
+//! struct Row { count: usize }
+//!
+//! impl Procedural for Row {
+//!     fn parameters(params: &mut ParamList) -> Result<(), ArnoldError> { params.int("count", 10).map(|_| ()) }
+//!     fn init(procedural: &ProceduralNode) -> Result<Row, ArnoldError> {
+//!         Ok(Row { count: procedural.node().get::<i32>("count")?.max(0) as usize })
+//!     }
+//!     fn num_nodes(&self) -> usize { self.count }
+//!     fn get_node<'a>(&self, procedural: &ProceduralNode<'a>, index: usize) -> Result<Node<'a>, ArnoldError> {
+//!         let sphere = procedural.create_node("sphere", &format!("sphere{}", index))?;
+//!         sphere.set("center", AtVector { x: 2.0 * index as f32, y: 0.0, z: 0.0 })?;
+//!         Ok(sphere)
+//!     }
+//! }
+//!
+//! install_procedural::<Row>(&session, "row")?;
+//! ```
+
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+
+use ai_bindings;
+pub use ai_bindings::AtProcViewportMode;
+use ai_bindings::{AtCommonMethods, AtList, AtNode, AtNodeEntry, AtParamValueMap, AtProceduralNodeMethods, AtRenderErrorCode, AtUniverse};
+use ai_node_entry::{guard_method, install_methods, report, NodeEntry, NodeType};
+use ai_nodes::Node;
+use ai_params::{ParamList, ParamType};
+use ai_render::{Session, AI_ERROR, AI_SUCCESS};
+use ai_string::AtString;
+use ai_universe::Universe;
+use error::ArnoldError;
+
+use std::convert::{TryFrom, TryInto};
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+
+// Viewport representation modes
+/// A box per shape
+pub const AI_PROC_BOXES: AtProcViewportMode = 0;
+/// Points sampled on the shapes
+pub const AI_PROC_POINTS: AtProcViewportMode = 1;
+/// Polygon meshes
+pub const AI_PROC_POLYGONS: AtProcViewportMode = 2;
+
+/// The procedural node being expanded, used to create the nodes it contains.
+pub struct ProceduralNode<'a> {
+    node: Node<'a>,
+    universe: *mut AtUniverse,
+}
+
+impl<'a> ProceduralNode<'a> {
+    /// The procedural node, to read its parameters.
+    pub fn node(&self) -> &Node<'a> {
+        &self.node
+    }
+
+    /// Create a node inside the procedural, with the procedural as its parent.
+    ///
+    /// # Parameters
+    /// * `nentry_name` - name of the type of node to be created ("polymesh", "ginstance", etc)
+    /// * `name` - name of the new node, which only has to be unique within the procedural
+    /// # Returns
+    /// the new node, or an error if the node could not be created
+    pub fn create_node<T>(&self, nentry_name: T, name: &str) -> Result<Node<'a>, ArnoldError>
+    where
+        T: TryInto<AtString>,
+        ArnoldError: From<T::Error>,
+    {
+        let nentry_name = nentry_name.try_into()?;
+        let node = unsafe { Node::from_raw(ai_bindings::AiNode(self.universe, nentry_name, AtString::try_from(name)?, self.node.as_ptr())) };
+        node.ok_or_else(|| ArnoldError::NodeCreation {
+            node_type: nentry_name.to_string_lossy().into_owned(),
+            name: name.to_owned(),
+        })
+    }
+}
+
+/// A procedural implemented in Rust.
+///
+/// Unlike the other [node types implemented in Rust](../ai_node_entry/index.html#node-types-implemented-in-rust), one value is created by [`init`](#tymethod.init) each time a procedural node is expanded, and dropped once all its nodes have been returned by [`get_node`](#tymethod.get_node).
+pub trait Procedural: Send + Sized + 'static {
+    /// Whether [`viewport`](#method.viewport) is implemented. Otherwise AiProceduralViewport() builds the representation from the expanded nodes.
+    const VIEWPORT: bool = false;
+
+    /// Declare the procedural parameters, in addition to the ones shared by all shapes such as "matrix" or "visibility".
+    fn parameters(_params: &mut ParamList) -> Result<(), ArnoldError> {
+        Ok(())
+    }
+
+    /// Prepare the expansion of a procedural node.
+    ///
+    /// # Returns
+    /// the procedural, or an error to abort the expansion, which is logged with AiMsgError()
+    fn init(procedural: &ProceduralNode) -> Result<Self, ArnoldError>;
+
+    /// Number of nodes created by the procedural.
+    fn num_nodes(&self) -> usize;
+
+    /// Return the node at `index`, in [0, [`num_nodes`](#tymethod.num_nodes)).
+    ///
+    /// The node is usually created here with [`ProceduralNode::create_node`](struct.ProceduralNode.html#method.create_node); nodes created in [`init`](#tymethod.init) must be returned as well.
+    /// # Returns
+    /// the node, or an error which is logged with AiMsgError()
+    fn get_node<'a>(&self, procedural: &ProceduralNode<'a>, index: usize) -> Result<Node<'a>, ArnoldError>;
+
+    /// Create a lightweight representation of a procedural node for viewports, see [`AiProceduralViewport`](fn.AiProceduralViewport.html).
+    ///
+    /// Only called if [`VIEWPORT`](#associatedconstant.VIEWPORT) is set. The procedural is not initialized first.
+    /// # Parameters
+    /// * `node` - the procedural node
+    /// * `universe` - the universe to create the representation nodes in, without a parent
+    /// * `mode` - the kind of representation (AI_PROC_BOXES, etc)
+    fn viewport(_node: &Node, _universe: &Universe, _mode: AtProcViewportMode) -> Result<(), ArnoldError> {
+        Ok(())
+    }
+}
+
+/// Install a node type for a Rust [`Procedural`](trait.Procedural.html).
+///
+/// # Parameters
+/// * `session` - the active session
+/// * `name` - name of the new procedural node type
+/// # Returns
+/// the installed node entry, or an error, see [node types implemented in Rust](../ai_node_entry/index.html#node-types-implemented-in-rust)
+pub fn install_procedural<'s, P: Procedural>(session: &'s Session, name: &str) -> Result<NodeEntry<'s>, ArnoldError> {
+    let cmethods = AtCommonMethods {
+        PluginInitialize: None,
+        PluginCleanup: None,
+        Parameters: Some(procedural_parameters::<P>),
+        Initialize: Some(procedural_node_method),
+        Update: Some(procedural_node_method),
+        Finish: Some(procedural_node_method),
+    };
+    let dmethods: &'static AtProceduralNodeMethods = Box::leak(Box::new(AtProceduralNodeMethods {
+        Init: Some(procedural_init::<P>),
+        Cleanup: Some(procedural_cleanup::<P>),
+        NumNodes: Some(procedural_num_nodes::<P>),
+        GetNode: Some(procedural_get_node::<P>),
+        ProceduralViewport: if P::VIEWPORT { Some(procedural_viewport::<P>) } else { None },
+    }));
+    install_methods(session, NodeType::SHAPE_PROCEDURAL, ParamType::Undefined, name, cmethods, dmethods as *const AtProceduralNodeMethods as *const c_void)
+}
+
+/// Create the viewport representation of a procedural node.
+///
+/// # Parameters
+/// * `node` - the procedural node
+/// * `universe` - the universe where the representation nodes are created
+/// * `mode` - the kind of representation (AI_PROC_BOXES, AI_PROC_POINTS or AI_PROC_POLYGONS)
+/// # Returns
+/// an error if the representation could not be created
+pub fn AiProceduralViewport(node: &Node, universe: &Universe, mode: AtProcViewportMode) -> Result<(), ArnoldError> {
+    ArnoldError::check_render(unsafe {
+        ai_bindings::AiProceduralViewport(node.as_ptr(), universe.as_ptr(), mode, ptr::null())
+    })
+}
+
+unsafe fn procedural_node<'a>(node: *const AtNode) -> Option<ProceduralNode<'a>> {
+    Node::from_raw(node as *mut AtNode).map(|node| ProceduralNode {
+        universe: ai_bindings::AiNodeGetUniverse(node.as_ptr()),
+        node: node,
+    })
+}
+
+unsafe extern "C" fn procedural_parameters<P: Procedural>(params: *mut AtList, _nentry: *mut AtNodeEntry) {
+    guard_method("procedural parameters", None, || report("procedural parameters", P::parameters(&mut ParamList::from_raw(params))));
+}
+
+/// Procedurals keep their state in the user pointer set by Init, so the common node methods have nothing to do.
+unsafe extern "C" fn procedural_node_method(_node: *mut AtNode) {}
+
+unsafe extern "C" fn procedural_init<P: Procedural>(node: *mut AtNode, user_ptr: *mut *mut c_void) -> c_int {
+    let procedural = guard_method("procedural init", None, || procedural_node(node).and_then(|procedural| report("procedural init", P::init(&procedural))));
+    match procedural {
+        Some(procedural) => {
+            *user_ptr = Box::into_raw(Box::new(procedural)) as *mut c_void;
+            1
+        }
+        None => 0,
+    }
+}
+
+unsafe extern "C" fn procedural_cleanup<P: Procedural>(_node: *const AtNode, user_ptr: *mut c_void) -> c_int {
+    if !user_ptr.is_null() {
+        guard_method("procedural cleanup", (), || drop(Box::from_raw(user_ptr as *mut P)));
+    }
+    1
+}
+
+unsafe extern "C" fn procedural_num_nodes<P: Procedural>(_node: *const AtNode, user_ptr: *mut c_void) -> c_int {
+    match (user_ptr as *const P).as_ref() {
+        Some(procedural) => guard_method("procedural num_nodes", 0, || procedural.num_nodes().min(c_int::MAX as usize) as c_int),
+        None => 0,
+    }
+}
+
+unsafe extern "C" fn procedural_get_node<P: Procedural>(node: *const AtNode, user_ptr: *mut c_void, i: c_int) -> *mut AtNode {
+    let (procedural, wrapped) = match ((user_ptr as *const P).as_ref(), procedural_node(node)) {
+        (Some(procedural), Some(wrapped)) if i >= 0 => (procedural, wrapped),
+        _ => return ptr::null_mut(),
+    };
+    let child = guard_method("procedural get_node", None, || report("procedural get_node", procedural.get_node(&wrapped, i as usize)));
+    child.map_or(ptr::null_mut(), |child| child.as_ptr())
+}
+
+unsafe extern "C" fn procedural_viewport<P: Procedural>(node: *const AtNode, universe: *mut AtUniverse, mode: AtProcViewportMode, _params: *const AtParamValueMap) -> AtRenderErrorCode {
+    let node = match Node::from_raw(node as *mut AtNode) {
+        Some(node) => node,
+        None => return AI_ERROR,
+    };
+    let universe = Universe::borrow_raw(universe);
+    match guard_method("procedural viewport", None, || report("procedural viewport", P::viewport(&node, &universe, mode))) {
+        Some(()) => AI_SUCCESS,
+        None => AI_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_drivers::TestScene;
+    use ai_msg::AI_LOG_NONE;
+    use ai_node_entry::AI_NODE_SHAPE;
+    use ai_nodes::{AiNode, AiNodeLookUpByName};
+    use ai_render::AI_SESSION_BATCH;
+    use ai_vector::AtVector;
+
+    /// A row of "count" spheres of radius 0.5 along +X, 2 units apart, starting at "start".
+    struct Row {
+        start: AtVector,
+        count: usize,
+    }
+
+    impl Row {
+        fn center(start: AtVector, index: usize) -> AtVector {
+            AtVector { x: start.x + 2.0 * index as f32, y: start.y, z: start.z }
+        }
+    }
+
+    impl Procedural for Row {
+        const VIEWPORT: bool = true;
+        fn parameters(params: &mut ParamList) -> Result<(), ArnoldError> {
+            params.vector("start", AtVector { x: 0.0, y: 0.0, z: 0.0 })?;
+            params.int("count", 1)?;
+            Ok(())
+        }
+        fn init(procedural: &ProceduralNode) -> Result<Row, ArnoldError> {
+            Ok(Row {
+                start: procedural.node().get("start")?,
+                count: procedural.node().get::<i32>("count")?.max(0) as usize,
+            })
+        }
+        fn num_nodes(&self) -> usize {
+            self.count
+        }
+        fn get_node<'a>(&self, procedural: &ProceduralNode<'a>, index: usize) -> Result<Node<'a>, ArnoldError> {
+            let sphere = procedural.create_node("sphere", &format!("sphere{}", index))?;
+            sphere.set("center", Row::center(self.start, index))?;
+            sphere.set("radius", 0.5)?;
+            Ok(sphere)
+        }
+        fn viewport(node: &Node, universe: &Universe, _mode: AtProcViewportMode) -> Result<(), ArnoldError> {
+            let start = node.get("start")?;
+            for index in 0..node.get::<i32>("count")?.max(0) as usize {
+                let center = Row::center(start, index);
+                let bounds = AiNode(universe, "box", Some(&format!("bounds{}", index)), None)?;
+                bounds.set("min", AtVector { x: center.x - 0.5, y: center.y - 0.5, z: center.z - 0.5 })?;
+                bounds.set("max", AtVector { x: center.x + 0.5, y: center.y + 0.5, z: center.z + 0.5 })?;
+            }
+            Ok(())
+        }
+    }
+    #[test]
+    fn rust_procedural() {
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let entry = install_procedural::<Row>(&session, "sphere_row").unwrap();
+        assert_eq!(entry.node_type(), NodeType::SHAPE_PROCEDURAL);
+        assert!(entry.param("count").is_some());
+
+        let universe = Universe::default(&session);
+        let camera = AiNode(&universe, "persp_camera", Some("camera"), None).unwrap();
+        let scene = TestScene::new(&universe, &camera);
+        let row = AiNode(&universe, "sphere_row", Some("row"), None).unwrap();
+        row.set("start", AtVector { x: -1.0, y: 0.0, z: -5.0 }).unwrap();
+        row.set("count", 2).unwrap();
+
+        // Both children are rendered, about 3 pixels left and right of the center, with a gap between them.
+        let framebuffer = scene.render();
+        assert_eq!(framebuffer.pixel(4, 8).a, 1.0);
+        assert_eq!(framebuffer.pixel(11, 8).a, 1.0);
+        assert_eq!(framebuffer.pixel(8, 8).a, 0.0);
+        assert_eq!(framebuffer.pixel(0, 0).a, 0.0);
+        let sphere = AiNodeLookUpByName(&universe, "sphere1", Some(&row)).unwrap();
+        assert_eq!(sphere.parent().map(|parent| parent.as_ptr()), Some(row.as_ptr()));
+        assert_eq!(sphere.get::<AtVector>("center").unwrap(), AtVector { x: 1.0, y: 0.0, z: -5.0 });
+
+        let viewport = Universe::new(&session);
+        AiProceduralViewport(&row, &viewport, AI_PROC_BOXES).unwrap();
+        assert_eq!(viewport.nodes(AI_NODE_SHAPE).count(), 2);
+    }
+}
@@ -4,9 +4,26 @@
 #[allow(dead_code)]
 
 use ai_bindings;
+use ai_drivers::install_builtin_drivers;
+use ai_msg::{log_literal, AiMsgSetConsoleFlags, AiMsgWarning, LogFlags};
+use ai_nodes::Node;
+use ai_params::ParamValue;
+use error::ArnoldError;
+use ai_universe::{reset_default_universe, Universe};
 
+use std::any::Any;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
 use std::ptr;
 use std::os::raw::c_void;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError, TryLockError};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::{Duration, Instant};
 
 
 use ai_bindings::AtSessionMode;
@@ -19,56 +36,941 @@ pub use ai_bindings::AtRenderMode::AI_RENDER_MODE_FREE;
 
 use ai_bindings::AtRenderErrorCode;
 use ai_bindings::AtRenderUpdateCallback;
+use ai_bindings::{AtRenderStatus, AtRenderUpdateInfo, AtRenderUpdateType};
+
+// Render error codes
+/// Success
+pub const AI_SUCCESS: AtRenderErrorCode = 0x00;
+/// Render aborted
+pub const AI_ABORT: AtRenderErrorCode = 0x01;
+/// Camera not defined
+pub const AI_ERROR_NO_CAMERA: AtRenderErrorCode = 0x02;
+/// Bad camera data
+pub const AI_ERROR_BAD_CAMERA: AtRenderErrorCode = 0x03;
+/// Unable to validate the license
+pub const AI_ERROR_VALIDATION: AtRenderErrorCode = 0x04;
+/// Invalid render region
+pub const AI_ERROR_RENDER_REGION: AtRenderErrorCode = 0x05;
+/// Render interrupted by user
+pub const AI_INTERRUPT: AtRenderErrorCode = 0x07;
+/// No rendering outputs
+pub const AI_ERROR_NO_OUTPUTS: AtRenderErrorCode = 0x08;
+/// Cannot create GPU context
+pub const AI_ERROR_UNAVAILABLE_DEVICE: AtRenderErrorCode = 0x09;
+/// Generic error
+pub const AI_ERROR: AtRenderErrorCode = 0x10;
+
+// Render status
+/// Render has not started yet
+pub const AI_RENDER_STATUS_NOT_STARTED: AtRenderStatus = 0;
+/// Render is paused
+pub const AI_RENDER_STATUS_PAUSED: AtRenderStatus = 1;
+/// Render is restarting
+pub const AI_RENDER_STATUS_RESTARTING: AtRenderStatus = 2;
+/// Render is in progress
+pub const AI_RENDER_STATUS_RENDERING: AtRenderStatus = 3;
+/// Render has finished
+pub const AI_RENDER_STATUS_FINISHED: AtRenderStatus = 4;
+/// Render has failed
+pub const AI_RENDER_STATUS_FAILED: AtRenderStatus = 5;
+
+// Render update types
+/// Render was interrupted
+pub const AI_RENDER_UPDATE_INTERRUPT: AtRenderUpdateType = 0;
+/// A render pass is about to start
+pub const AI_RENDER_UPDATE_BEFORE_PASS: AtRenderUpdateType = 1;
+/// A render pass is in progress
+pub const AI_RENDER_UPDATE_DURING_PASS: AtRenderUpdateType = 2;
+/// A render pass has finished
+pub const AI_RENDER_UPDATE_AFTER_PASS: AtRenderUpdateType = 3;
+/// The render has finished
+pub const AI_RENDER_UPDATE_FINISHED: AtRenderUpdateType = 4;
+/// The render has failed
+pub const AI_RENDER_UPDATE_ERROR: AtRenderUpdateType = 5;
 
 use ai_bindings::AtBlockingCall;
 pub use ai_bindings::AtBlockingCall::AI_NON_BLOCKING;
 pub use ai_bindings::AtBlockingCall::AI_BLOCKING;
 
-pub fn AiBegin(mode: AtSessionMode){
+/// Arnold keeps a single set of global state per process, so only one session may be active at a time.
+static SESSION_LOCK: Mutex<()> = Mutex::new(());
+
+/// Number of sessions started so far, used to tell sessions apart.
+static SESSION_COUNT: AtomicUsize = AtomicUsize::new(0);
+/// Id of the active session, 0 when no session is active.
+static ACTIVE_SESSION: AtomicUsize = AtomicUsize::new(0);
+
+/// Return the id of the active session, or `None` outside of a session.
+///
+/// Data that is released by AiEnd(), such as arrays, can record this id on creation so that it is not released twice.
+pub(crate) fn active_session() -> Option<usize> {
+    match ACTIVE_SESSION.load(Ordering::SeqCst) {
+        0 => None,
+        id => Some(id),
+    }
+}
+
+/// An active Arnold session.
+///
+/// Calls AiBegin() on creation and AiEnd() when dropped. All nodes, node entries and arrays are released when the session ends.
+///
+/// Only one session can exist per process. [`Session::begin`](#method.begin) blocks until any other session has been dropped, while [`Session::try_begin`](#method.try_begin) returns `None` instead of waiting.
+///
+/// Wrappers that touch the scene take a `&Session` or a [`Universe`](../ai_universe/struct.Universe.html) borrowed from one, so they cannot be called outside an active session. The exceptions are:
+/// * wrappers taking a [`Node`](../ai_nodes/struct.Node.html), [`NodeEntry`](../ai_node_entry/struct.NodeEntry.html) or [`ParamEntry`](../ai_params/struct.ParamEntry.html), which borrow the session they came from, or an [`Array`](../ai_array/struct.Array.html), which can only be created inside a session
+/// * wrappers taking raw `AtArray` or iterator pointers, which can only be obtained from such a handle
+/// * AiMsg*() and the AiMalloc() family, which Arnold allows outside a session, and the math helpers of `ai_vector`, `ai_color` and `ai_bbox`
+///
+/// # Example
+/// ```
+/// let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+/// let universe = Universe::default(&session);
+/// AiASSLoad(&universe, "scene.ass", AI_NODE_ALL);
+/// AiRender(&universe, AI_RENDER_MODE_CAMERA);
+/// // AiEnd() is called when `session` goes out of scope
+/// ```
+pub struct Session {
+    mode: AtSessionMode,
+    log_flags: LogFlags,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Session {
+    /// Start a new session, blocking until any other session has ended.
+    ///
+    /// # Note
+    /// Calling this while the current thread already holds a session will deadlock, use [`try_begin`](#method.try_begin) if that can happen.
+    /// # Parameters
+    /// * `mode` - session mode (AI_SESSION_BATCH or AI_SESSION_INTERACTIVE)
+    /// * `log_flags` - console log flags (AI_LOG_NONE, AI_LOG_ALL, etc)
+    pub fn begin(mode: AtSessionMode, log_flags: LogFlags) -> Session {
+        let lock = SESSION_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        Session::start(mode, log_flags, lock)
+    }
+
+    /// Start a new session if no other session is active.
+    ///
+    /// # Parameters
+    /// * `mode` - session mode (AI_SESSION_BATCH or AI_SESSION_INTERACTIVE)
+    /// * `log_flags` - console log flags (AI_LOG_NONE, AI_LOG_ALL, etc)
+    /// # Returns
+    /// the new session, or `None` if another session is still active
+    pub fn try_begin(mode: AtSessionMode, log_flags: LogFlags) -> Option<Session> {
+        let lock = match SESSION_LOCK.try_lock() {
+            Ok(lock) => lock,
+            Err(TryLockError::Poisoned(err)) => err.into_inner(),
+            Err(TryLockError::WouldBlock) => return None,
+        };
+        Some(Session::start(mode, log_flags, lock))
+    }
+
+    fn start(mode: AtSessionMode, log_flags: LogFlags, lock: MutexGuard<'static, ()>) -> Session {
+        AiBegin(mode);
+        AiMsgSetConsoleFlags(log_flags);
+        ACTIVE_SESSION.store(SESSION_COUNT.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+        reset_default_universe();
+        let session = Session {
+            mode: mode,
+            log_flags: log_flags,
+            _lock: lock,
+        };
+        if let Err(err) = install_builtin_drivers(&session) {
+            log_literal(AiMsgWarning, &err.to_string());
+        }
+        session
+    }
+
+    /// The mode this session was started with.
+    pub fn mode(&self) -> AtSessionMode {
+        self.mode
+    }
+
+    /// The console log flags currently in use.
+    pub fn log_flags(&self) -> LogFlags {
+        self.log_flags
+    }
+
+    /// Change the console log flags for the rest of the session.
+    pub fn set_log_flags(&mut self, log_flags: LogFlags) {
+        AiMsgSetConsoleFlags(log_flags);
+        self.log_flags = log_flags;
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        ACTIVE_SESSION.store(0, Ordering::SeqCst);
+        AiEnd();
+    }
+}
+
+fn AiBegin(mode: AtSessionMode){
     unsafe{
         ai_bindings::AiBegin(mode)
     }
 }
 
-pub fn AiEnd(){
+fn AiEnd(){
     unsafe{
         ai_bindings::AiEnd()
     }
 }
 
-pub fn AiGetSessionMode() -> AtSessionMode {
+pub fn AiGetSessionMode(_session: &Session) -> AtSessionMode {
     unsafe{ 
         ai_bindings::AiGetSessionMode() 
     }
 }
 
-pub fn AiRenderBegin(mode: AtRenderMode, update_callback: AtRenderUpdateCallback, _callback_private_data: *mut c_void) -> AtRenderErrorCode {
-    unsafe {
-        ai_bindings::AiRenderBegin(mode, update_callback, ptr::null_mut())
+/// Arnold renders the default universe only, other universes can be edited while it renders.
+fn check_renderable(universe: &Universe) -> Result<(), ArnoldError> {
+    if universe.is_default() {
+        Ok(())
+    } else {
+        Err(ArnoldError::NotRenderable)
     }
 }
 
-pub fn AiRenderEnd() -> AtRenderErrorCode {
-    unsafe {
+pub fn AiRenderBegin(universe: &Universe, mode: AtRenderMode, update_callback: AtRenderUpdateCallback, callback_private_data: *mut c_void) -> Result<(), ArnoldError> {
+    check_renderable(universe)?;
+    ArnoldError::check_render(unsafe {
+        ai_bindings::AiRenderBegin(mode, update_callback, callback_private_data)
+    })
+}
+
+pub fn AiRenderEnd(universe: &Universe) -> Result<(), ArnoldError> {
+    check_renderable(universe)?;
+    ArnoldError::check_render(unsafe {
         ai_bindings::AiRenderEnd()
-    }
+    })
 }
 
-pub fn AiRenderInterrupt(blocking: AtBlockingCall){
+/// Interrupt the render, stopping the render threads without ending the render, so it can be restarted.
+///
+/// # Parameters
+/// * `universe` - the universe being rendered
+/// * `blocking` - AI_BLOCKING to wait for the render threads to stop, AI_NON_BLOCKING to return right away
+/// # Returns
+/// `ArnoldError::NotRenderable` if `universe` is not the default universe
+pub fn AiRenderInterrupt(universe: &Universe, blocking: AtBlockingCall) -> Result<(), ArnoldError> {
+    check_renderable(universe)?;
     unsafe {
         ai_bindings::AiRenderInterrupt(blocking)
     }
+    Ok(())
 }
 
-pub fn AiRenderAbort(blocking: AtBlockingCall){
+/// Abort the render, cancelling the remaining passes.
+///
+/// # Parameters
+/// * `universe` - the universe being rendered
+/// * `blocking` - AI_BLOCKING to wait for the render threads to stop, AI_NON_BLOCKING to return right away
+/// # Returns
+/// `ArnoldError::NotRenderable` if `universe` is not the default universe
+pub fn AiRenderAbort(universe: &Universe, blocking: AtBlockingCall) -> Result<(), ArnoldError> {
+    check_renderable(universe)?;
     unsafe {
         ai_bindings::AiRenderAbort(blocking)
     }
+    Ok(())
 }
 
-pub fn AiRender(mode: AtRenderMode) -> AtRenderErrorCode {
-    unsafe {
+/// Restart an interrupted or finished render, picking up any scene changes made in the meantime.
+///
+/// # Parameters
+/// * `universe` - the universe being rendered
+pub fn AiRenderRestart(universe: &Universe) -> Result<(), ArnoldError> {
+    check_renderable(universe)?;
+    ArnoldError::check_render(unsafe {
+        ai_bindings::AiRenderRestart()
+    })
+}
+
+/// Get the current status of the render.
+///
+/// # Parameters
+/// * `universe` - the universe being rendered
+/// # Returns
+/// the render status, `RenderStatus::NotStarted` for universes that cannot be rendered
+pub fn AiRenderGetStatus(universe: &Universe) -> RenderStatus {
+    if universe.is_default() {
+        RenderStatus::from_raw(unsafe { ai_bindings::AiRenderGetStatus() })
+    } else {
+        RenderStatus::NotStarted
+    }
+}
+
+pub fn AiRender(universe: &Universe, mode: AtRenderMode) -> Result<(), ArnoldError> {
+    check_renderable(universe)?;
+    ArnoldError::check_render(unsafe {
         ai_bindings::AiRender(mode)
+    })
+}
+
+/// Reason for a call to the render update callback, see [`render_begin`](fn.render_begin.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderUpdate {
+    /// The render was interrupted
+    Interrupt,
+    /// A render pass is about to start
+    BeforePass,
+    /// A render pass is in progress
+    DuringPass,
+    /// A render pass has finished
+    AfterPass,
+    /// The render has finished
+    Finished,
+    /// The render has failed
+    Error,
+    /// An update type this crate does not know about
+    Other(AtRenderUpdateType),
+}
+
+impl RenderUpdate {
+    /// Convert an AI_RENDER_UPDATE_* value.
+    pub fn from_raw(update_type: AtRenderUpdateType) -> RenderUpdate {
+        match update_type {
+            AI_RENDER_UPDATE_INTERRUPT => RenderUpdate::Interrupt,
+            AI_RENDER_UPDATE_BEFORE_PASS => RenderUpdate::BeforePass,
+            AI_RENDER_UPDATE_DURING_PASS => RenderUpdate::DuringPass,
+            AI_RENDER_UPDATE_AFTER_PASS => RenderUpdate::AfterPass,
+            AI_RENDER_UPDATE_FINISHED => RenderUpdate::Finished,
+            AI_RENDER_UPDATE_ERROR => RenderUpdate::Error,
+            other => RenderUpdate::Other(other),
+        }
+    }
+}
+
+/// Status of a render, as returned by the render update callback to steer the render.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderStatus {
+    /// Render has not started yet
+    NotStarted,
+    /// Render is paused
+    Paused,
+    /// Render is restarting
+    Restarting,
+    /// Render is in progress
+    Rendering,
+    /// Render has finished
+    Finished,
+    /// Render has failed
+    Failed,
+}
+
+impl RenderStatus {
+    /// Convert an AI_RENDER_STATUS_* value, unknown values are reported as `Failed`.
+    pub fn from_raw(status: AtRenderStatus) -> RenderStatus {
+        match status {
+            AI_RENDER_STATUS_NOT_STARTED => RenderStatus::NotStarted,
+            AI_RENDER_STATUS_PAUSED => RenderStatus::Paused,
+            AI_RENDER_STATUS_RESTARTING => RenderStatus::Restarting,
+            AI_RENDER_STATUS_RENDERING => RenderStatus::Rendering,
+            AI_RENDER_STATUS_FINISHED => RenderStatus::Finished,
+            _ => RenderStatus::Failed,
+        }
+    }
+
+    /// The matching AI_RENDER_STATUS_* value.
+    pub fn as_raw(self) -> AtRenderStatus {
+        match self {
+            RenderStatus::NotStarted => AI_RENDER_STATUS_NOT_STARTED,
+            RenderStatus::Paused => AI_RENDER_STATUS_PAUSED,
+            RenderStatus::Restarting => AI_RENDER_STATUS_RESTARTING,
+            RenderStatus::Rendering => AI_RENDER_STATUS_RENDERING,
+            RenderStatus::Finished => AI_RENDER_STATUS_FINISHED,
+            RenderStatus::Failed => AI_RENDER_STATUS_FAILED,
+        }
+    }
+}
+
+type UpdateCallback = Box<dyn FnMut(RenderUpdate) -> RenderStatus + Send>;
+
+/// Private data handed to Arnold for [`render_begin`](fn.render_begin.html).
+struct CallbackData {
+    callback: Mutex<UpdateCallback>,
+    panic: Mutex<Option<Box<dyn Any + Send>>>,
+}
+
+impl CallbackData {
+    fn take_panic(&self) -> Option<Box<dyn Any + Send>> {
+        self.panic.lock().unwrap_or_else(PoisonError::into_inner).take()
+    }
+}
+
+/// Forward a render update to the boxed closure, catching panics so they never unwind into Arnold.
+unsafe extern "C" fn render_update_callback(
+    private_data: *mut c_void,
+    update_type: AtRenderUpdateType,
+    _update_info: *const AtRenderUpdateInfo,
+) -> AtRenderStatus {
+    let data = &*(private_data as *const CallbackData);
+    let mut panic = data.panic.lock().unwrap_or_else(PoisonError::into_inner);
+    if panic.is_some() {
+        return AI_RENDER_STATUS_FAILED;
+    }
+    let update = RenderUpdate::from_raw(update_type);
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let mut callback = data.callback.lock().unwrap_or_else(PoisonError::into_inner);
+        (*callback)(update)
+    }));
+    match result {
+        Ok(status) => status.as_raw(),
+        Err(payload) => {
+            *panic = Some(payload);
+            AI_RENDER_STATUS_FAILED
+        }
+    }
+}
+
+/// Start a render, calling `callback` with every render update.
+///
+/// The closure is boxed and passed to AiRenderBegin() as the callback private data. Its return value tells Arnold how to continue, for instance returning `RenderStatus::Paused` after `RenderUpdate::AfterPass` pauses an interactive render.
+///
+/// A panic inside the closure is caught before it reaches Arnold, the render is reported as failed and the panic is resumed by [`ActiveRender::end`](struct.ActiveRender.html#method.end).
+///
+/// ```
+/// let render = render_begin(&universe, AI_RENDER_MODE_CAMERA, |update| {
+///     println!("render update: {:?}", update);
+///     RenderStatus::Rendering
+/// })?;
+/// render.end()?;
+/// ```
+/// # Parameters
+/// * `universe` - the universe to render, which must be the default universe
+/// * `mode` - render mode
+/// * `callback` - closure called with each render update
+/// # Returns
+/// a handle that ends the render when dropped, or an error if the render could not be started
+pub fn render_begin<'u, F>(universe: &'u Universe, mode: AtRenderMode, callback: F) -> Result<ActiveRender<'u>, ArnoldError>
+where
+    F: FnMut(RenderUpdate) -> RenderStatus + Send + 'static,
+{
+    let data = Box::new(CallbackData {
+        callback: Mutex::new(Box::new(callback)),
+        panic: Mutex::new(None),
+    });
+    AiRenderBegin(
+        universe,
+        mode,
+        Some(render_update_callback),
+        &*data as *const CallbackData as *mut c_void,
+    )?;
+    Ok(ActiveRender {
+        target: RenderTarget::new(universe),
+        data: Some(data),
+    })
+}
+
+/// The default universe, the only one that can be rendered, as a handle that can be sent to other threads.
+///
+/// Arnold identifies the default universe with a NULL pointer, so the handle only borrows the session for `'u` instead of holding the `Universe`, which is not `Sync`.
+#[derive(Debug, Clone, Copy)]
+struct RenderTarget<'u> {
+    _session: PhantomData<&'u Session>,
+}
+
+impl<'u> RenderTarget<'u> {
+    /// The target for `universe`, which has been checked to be the default universe by AiRenderBegin().
+    fn new(universe: &'u Universe) -> RenderTarget<'u> {
+        debug_assert!(universe.is_default());
+        RenderTarget { _session: PhantomData }
+    }
+
+    /// The default universe, for the render wrappers.
+    fn universe(&self) -> ManuallyDrop<Universe<'u>> {
+        // NULL is the default universe, which lives until AiEnd() and so for as long as the session is borrowed.
+        unsafe { Universe::borrow_raw(ptr::null_mut()) }
+    }
+}
+
+/// A render started by [`render_begin`](fn.render_begin.html).
+///
+/// Dropping the handle waits for the render with AiRenderEnd(), then frees the callback. A panic caught in the callback is only resumed by [`end`](#method.end).
+pub struct ActiveRender<'u> {
+    target: RenderTarget<'u>,
+    data: Option<Box<CallbackData>>,
+}
+
+impl<'u> ActiveRender<'u> {
+    /// Wait for the render to finish and release it.
+    ///
+    /// # Returns
+    /// the render result
+    /// # Panics
+    /// if the callback panicked, with the original panic payload
+    pub fn end(mut self) -> Result<(), ArnoldError> {
+        let result = AiRenderEnd(&self.target.universe());
+        if let Some(payload) = self.data.take().and_then(|data| data.take_panic()) {
+            panic::resume_unwind(payload);
+        }
+        result
+    }
+}
+
+impl<'u> Drop for ActiveRender<'u> {
+    fn drop(&mut self) {
+        if self.data.is_some() {
+            let _ = AiRenderEnd(&self.target.universe());
+        }
+    }
+}
+
+/// Controller for interactive, progressive renders.
+///
+/// The controller starts the render with [`render_begin`](fn.render_begin.html) and owns it until [`end`](#method.end) or drop. Scene edits made while Arnold renders must go through [`edit`](#method.edit), which interrupts the render, waits for the render threads to stop, applies the edits and restarts, so an edit never races the renderer.
+///
+/// ```
+/// let session = Session::begin(AI_SESSION_INTERACTIVE, AI_LOG_WARNINGS);
+/// let universe = Universe::default(&session);
+/// let mut controller = RenderController::start(&universe, AI_RENDER_MODE_CAMERA, |_| RenderStatus::Rendering)?;
+/// controller.set(&light, "intensity", 2.0)?;
+/// println!("{:?} after {} passes", controller.status(), controller.passes());
+/// controller.end()?;
+/// ```
+pub struct RenderController<'u> {
+    universe: &'u Universe<'u>,
+    render: ActiveRender<'u>,
+    passes: Arc<AtomicUsize>,
+}
+
+impl<'u> RenderController<'u> {
+    /// Start a progressive render in an interactive session.
+    ///
+    /// Progressive rendering is enabled on the options node, and `callback` is called with every render update as in [`render_begin`](fn.render_begin.html).
+    /// # Returns
+    /// the controller, or an error if the session is not interactive or the render could not be started
+    pub fn start<F>(universe: &'u Universe, mode: AtRenderMode, mut callback: F) -> Result<RenderController<'u>, ArnoldError>
+    where
+        F: FnMut(RenderUpdate) -> RenderStatus + Send + 'static,
+    {
+        if unsafe { ai_bindings::AiGetSessionMode() } != AI_SESSION_INTERACTIVE {
+            return Err(ArnoldError::NotInteractive);
+        }
+        universe.options().set("enable_progressive_render", true)?;
+        let passes = Arc::new(AtomicUsize::new(0));
+        let counter = passes.clone();
+        let render = render_begin(universe, mode, move |update| {
+            if update == RenderUpdate::AfterPass {
+                counter.fetch_add(1, Ordering::SeqCst);
+            }
+            callback(update)
+        })?;
+        Ok(RenderController {
+            universe: universe,
+            render: render,
+            passes: passes,
+        })
+    }
+
+    /// The universe being rendered.
+    pub fn universe(&self) -> &'u Universe<'u> {
+        self.universe
+    }
+
+    /// The current render status, see [`AiRenderGetStatus`](fn.AiRenderGetStatus.html).
+    pub fn status(&self) -> RenderStatus {
+        AiRenderGetStatus(self.universe)
+    }
+
+    /// Number of render passes completed since the render started, across restarts.
+    pub fn passes(&self) -> usize {
+        self.passes.load(Ordering::SeqCst)
+    }
+
+    /// Stop the render and wait for the render threads, without ending it.
+    pub fn interrupt(&self) -> Result<(), ArnoldError> {
+        AiRenderInterrupt(self.universe, AI_BLOCKING)
+    }
+
+    /// Restart the render, picking up scene changes.
+    pub fn restart(&self) -> Result<(), ArnoldError> {
+        AiRenderRestart(self.universe)
+    }
+
+    /// Apply scene edits and restart the render.
+    ///
+    /// The render is always interrupted first, waiting for the render threads to stop, so `edits` never runs while Arnold reads the scene. Interrupting a render that is already stopped does nothing.
+    /// # Returns
+    /// the value returned by `edits`, or an error if the render could not be interrupted or restarted
+    pub fn edit<R, E>(&mut self, edits: E) -> Result<R, ArnoldError>
+    where
+        E: FnOnce(&'u Universe<'u>) -> R,
+    {
+        self.interrupt()?;
+        let result = edits(self.universe);
+        self.restart()?;
+        Ok(result)
+    }
+
+    /// Set a single parameter and restart the render, see [`edit`](#method.edit).
+    pub fn set<T: Into<ParamValue>>(&mut self, node: &Node, param: &str, value: T) -> Result<(), ArnoldError> {
+        self.edit(|_| node.set(param, value))?
+    }
+
+    /// Wait for the render to finish and end it, see [`ActiveRender::end`](struct.ActiveRender.html#method.end).
+    pub fn end(self) -> Result<(), ArnoldError> {
+        self.render.end()
+    }
+}
+
+/// Statistics of a finished render, see [`render_async`](fn.render_async.html).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderSummary {
+    /// Number of render passes completed
+    pub passes: usize,
+    /// Time from the start of the render until it finished
+    pub elapsed: Duration,
+}
+
+/// Progress shared between the render update callback and a [`RenderFuture`](struct.RenderFuture.html).
+#[derive(Debug, Default)]
+struct AsyncProgress {
+    passes: usize,
+    finished: Option<RenderUpdate>,
+    waker: Option<Waker>,
+}
+
+/// Render without blocking the calling thread.
+///
+/// The render is started with a non-blocking AiRenderBegin(), and the returned future completes when the render update callback reports that the render has finished. The future only relies on the `Waker` it is polled with, so it runs on any executor:
+/// ```
+/// let summary = render_async(&universe, AI_RENDER_MODE_CAMERA).await?;
+/// println!("rendered {} passes in {:?}", summary.passes, summary.elapsed);
+/// ```
+/// Dropping the future before it completes aborts the render with AiRenderAbort(AI_NON_BLOCKING), then waits for the render threads to stop.
+///
+/// The future is `Send`, so it can be polled on any executor thread: it only holds the progress shared with the render callback and a handle to the render of the default universe, which borrows the session for as long as `universe` is borrowed.
+/// # Parameters
+/// * `universe` - the universe to render, which must be the default universe
+/// * `mode` - render mode
+pub fn render_async<'u>(universe: &'u Universe, mode: AtRenderMode) -> RenderFuture<'u> {
+    let progress = Arc::new(Mutex::new(AsyncProgress::default()));
+    let shared = progress.clone();
+    let render = render_begin(universe, mode, move |update| {
+        let mut progress = shared.lock().unwrap_or_else(PoisonError::into_inner);
+        let status = match update {
+            RenderUpdate::AfterPass => {
+                progress.passes += 1;
+                return RenderStatus::Rendering;
+            }
+            RenderUpdate::Finished => RenderStatus::Finished,
+            RenderUpdate::Interrupt => RenderStatus::Paused,
+            RenderUpdate::Error => RenderStatus::Failed,
+            _ => return RenderStatus::Rendering,
+        };
+        progress.finished = Some(update);
+        if let Some(waker) = progress.waker.take() {
+            waker.wake();
+        }
+        status
+    });
+    RenderFuture {
+        render: render.map(Some),
+        progress: progress,
+        started: Instant::now(),
+    }
+}
+
+/// A render in progress, see [`render_async`](fn.render_async.html).
+///
+/// Completes with the render statistics once the render has finished, or with an error if it failed or was interrupted. Polling it again after it completed returns `Poll::Pending`.
+pub struct RenderFuture<'u> {
+    render: Result<Option<ActiveRender<'u>>, ArnoldError>,
+    progress: Arc<Mutex<AsyncProgress>>,
+    started: Instant,
+}
+
+impl<'u> Future for RenderFuture<'u> {
+    type Output = Result<RenderSummary, ArnoldError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let render = match this.render {
+            Ok(ref mut render) => render,
+            Err(ref err) => return Poll::Ready(Err(err.clone())),
+        };
+        let (passes, finished) = {
+            let mut progress = this.progress.lock().unwrap_or_else(PoisonError::into_inner);
+            match progress.finished {
+                Some(finished) => (progress.passes, finished),
+                None => {
+                    progress.waker = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+        };
+        // The render has stopped, so AiRenderEnd() returns right away.
+        let active = match render.take() {
+            Some(active) => active,
+            None => return Poll::Pending,
+        };
+        active.end()?;
+        Poll::Ready(match finished {
+            RenderUpdate::Finished => Ok(RenderSummary {
+                passes: passes,
+                elapsed: this.started.elapsed(),
+            }),
+            RenderUpdate::Interrupt => Err(ArnoldError::Render { code: AI_INTERRUPT }),
+            _ => Err(ArnoldError::Render { code: AI_ERROR }),
+        })
+    }
+}
+
+impl<'u> Drop for RenderFuture<'u> {
+    fn drop(&mut self) {
+        if let Ok(Some(ref render)) = self.render {
+            // render_begin() only accepts the default universe, so this cannot fail.
+            let _ = AiRenderAbort(&render.target.universe(), AI_NON_BLOCKING);
+        }
+    }
+}
+
+/// How often the watchdog checks for SIGINT while waiting for the render.
+const WATCHDOG_POLL: Duration = Duration::from_millis(50);
+
+/// Why the watchdog stopped a render.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WatchdogStop {
+    Timeout,
+    Signal,
+}
+
+/// State shared between a budgeted render and its watchdog thread.
+#[derive(Default)]
+struct Watchdog {
+    done: Mutex<bool>,
+    cond: Condvar,
+    completed: AtomicBool,
+}
+
+impl Watchdog {
+    fn finish(&self) {
+        *self.done.lock().unwrap_or_else(PoisonError::into_inner) = true;
+        self.cond.notify_all();
+    }
+
+    /// Record that the render finished all its passes, even if the watchdog stops it at the same time.
+    fn complete(&self) {
+        self.completed.store(true, Ordering::SeqCst);
+        self.finish();
+    }
+
+    /// Check if the render finished all its passes.
+    fn completed(&self) -> bool {
+        self.completed.load(Ordering::SeqCst)
+    }
+
+    /// Wait until the render is done, the budget runs out or SIGINT is received.
+    fn wait(&self, deadline: Instant) -> Option<WatchdogStop> {
+        let mut done = self.done.lock().unwrap_or_else(PoisonError::into_inner);
+        loop {
+            if *done {
+                return None;
+            }
+            if sigint::received() {
+                return Some(WatchdogStop::Signal);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Some(WatchdogStop::Timeout);
+            }
+            let timeout = (deadline - now).min(WATCHDOG_POLL);
+            done = self.cond.wait_timeout(done, timeout).unwrap_or_else(PoisonError::into_inner).0;
+        }
+    }
+}
+
+/// Render with a time budget.
+///
+/// The render is started with [`render_begin`](fn.render_begin.html), and a watchdog thread stops it once `budget` has elapsed: it calls AiRenderInterrupt() so the render threads stop at the next bucket, then AiRenderAbort() to cancel the remaining passes. The render is still ended with AiRenderEnd(), so drivers are closed and the buckets finished so far are written out.
+///
+/// While the render runs, SIGINT (Ctrl+C in a Windows console) is handled the same way instead of killing the process mid-write, and the previous handler is restored afterwards. On other targets SIGINT is not handled.
+///
+/// A render that finished all its passes returns its statistics, even if the budget ran out while it was ending.
+/// ```
+/// match render_with_budget(&universe, AI_RENDER_MODE_CAMERA, Duration::from_secs(600)) {
+///     Err(ArnoldError::Timeout { passes, .. }) => println!("frame timed out after {} passes", passes),
+///     result => result.map(|_| ())?,
+/// }
+/// ```
+/// # Parameters
+/// * `universe` - the universe to render, which must be the default universe
+/// * `mode` - render mode
+/// * `budget` - maximum render time
+/// # Returns
+/// the render statistics, `ArnoldError::Timeout` if the budget ran out, `ArnoldError::Interrupted` on SIGINT, or the render error
+pub fn render_with_budget(universe: &Universe, mode: AtRenderMode, budget: Duration) -> Result<RenderSummary, ArnoldError> {
+    let started = Instant::now();
+    let watchdog = Arc::new(Watchdog::default());
+    let passes = Arc::new(AtomicUsize::new(0));
+    // Installed before the render starts, so SIGINT never kills the process once the drivers are open.
+    let _sigint = sigint::Handler::install();
+    let render = {
+        let watchdog = watchdog.clone();
+        let passes = passes.clone();
+        render_begin(universe, mode, move |update| match update {
+            RenderUpdate::AfterPass => {
+                passes.fetch_add(1, Ordering::SeqCst);
+                RenderStatus::Rendering
+            }
+            RenderUpdate::Finished => {
+                watchdog.complete();
+                RenderStatus::Finished
+            }
+            RenderUpdate::Error => {
+                watchdog.finish();
+                RenderStatus::Failed
+            }
+            RenderUpdate::Interrupt => RenderStatus::Paused,
+            _ => RenderStatus::Rendering,
+        })?
+    };
+    let target = render.target;
+    let (result, stop) = thread::scope(|scope| {
+        let thread = scope.spawn(|| {
+            let stop = watchdog.wait(started + budget);
+            let universe = target.universe();
+            if stop.is_some() && AiRenderGetStatus(&universe) != RenderStatus::Finished {
+                let _ = AiRenderInterrupt(&universe, AI_BLOCKING);
+                let _ = AiRenderAbort(&universe, AI_BLOCKING);
+            }
+            stop
+        });
+        let result = render.end();
+        watchdog.finish();
+        (result, thread.join().unwrap_or(None))
+    });
+    let passes = passes.load(Ordering::SeqCst);
+    // The render's own outcome wins over a watchdog that fired as it was finishing.
+    match stop {
+        Some(WatchdogStop::Timeout) if !watchdog.completed() => Err(ArnoldError::Timeout {
+            budget: budget,
+            passes: passes,
+        }),
+        Some(WatchdogStop::Signal) if !watchdog.completed() => Err(ArnoldError::Interrupted { passes: passes }),
+        _ => result.map(|()| RenderSummary {
+            passes: passes,
+            elapsed: started.elapsed(),
+        }),
+    }
+}
+
+/// SIGINT handling for [`render_with_budget`](fn.render_with_budget.html).
+#[cfg(unix)]
+mod sigint {
+    use std::os::raw::c_int;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    const SIGINT: c_int = 2;
+    const SIG_ERR: usize = !0;
+
+    extern "C" {
+        fn signal(signum: c_int, handler: usize) -> usize;
+    }
+
+    static RECEIVED: AtomicBool = AtomicBool::new(false);
+
+    extern "C" fn on_sigint(_signum: c_int) {
+        RECEIVED.store(true, Ordering::SeqCst);
+    }
+
+    /// Check if SIGINT was received since the handler was installed.
+    pub fn received() -> bool {
+        RECEIVED.load(Ordering::SeqCst)
+    }
+
+    /// Installed SIGINT handler, restoring the previous one when dropped.
+    pub struct Handler {
+        previous: usize,
+    }
+
+    impl Handler {
+        pub fn install() -> Option<Handler> {
+            RECEIVED.store(false, Ordering::SeqCst);
+            let handler = on_sigint as extern "C" fn(c_int) as usize;
+            match unsafe { signal(SIGINT, handler) } {
+                SIG_ERR => None,
+                previous => Some(Handler { previous: previous }),
+            }
+        }
+    }
+
+    impl Drop for Handler {
+        fn drop(&mut self) {
+            unsafe { signal(SIGINT, self.previous) };
+        }
+    }
+}
+
+/// Ctrl+C handling for [`render_with_budget`](fn.render_with_budget.html), through a console control handler.
+#[cfg(windows)]
+mod sigint {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    type BOOL = i32;
+    type DWORD = u32;
+    type HandlerRoutine = unsafe extern "system" fn(ctrl_type: DWORD) -> BOOL;
+
+    const CTRL_C_EVENT: DWORD = 0;
+    const TRUE: BOOL = 1;
+    const FALSE: BOOL = 0;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn SetConsoleCtrlHandler(handler: Option<HandlerRoutine>, add: BOOL) -> BOOL;
+    }
+
+    static RECEIVED: AtomicBool = AtomicBool::new(false);
+
+    unsafe extern "system" fn on_ctrl(ctrl_type: DWORD) -> BOOL {
+        if ctrl_type == CTRL_C_EVENT {
+            RECEIVED.store(true, Ordering::SeqCst);
+            TRUE
+        } else {
+            // Let the next handler deal with closing the console or logging off.
+            FALSE
+        }
+    }
+
+    /// Check if Ctrl+C was received since the handler was installed.
+    pub fn received() -> bool {
+        RECEIVED.load(Ordering::SeqCst)
+    }
+
+    /// Installed console control handler, removed when dropped so the previous handlers run again.
+    pub struct Handler;
+
+    impl Handler {
+        pub fn install() -> Option<Handler> {
+            RECEIVED.store(false, Ordering::SeqCst);
+            match unsafe { SetConsoleCtrlHandler(Some(on_ctrl), TRUE) } {
+                FALSE => None,
+                _ => Some(Handler),
+            }
+        }
+    }
+
+    impl Drop for Handler {
+        fn drop(&mut self) {
+            unsafe { SetConsoleCtrlHandler(Some(on_ctrl), FALSE) };
+        }
+    }
+}
+
+/// Targets without SIGINT or console control handlers: the render is only stopped by its budget.
+#[cfg(not(any(unix, windows)))]
+mod sigint {
+    pub fn received() -> bool {
+        false
+    }
+
+    pub struct Handler;
+
+    impl Handler {
+        pub fn install() -> Option<Handler> {
+            None
+        }
     }
 }
 
@@ -136,11 +1038,212 @@ pub fn AiRenderFree() -> AtRenderErrorCode {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ai_drivers::TestScene;
+    use ai_msg::AI_LOG_NONE;
+    use ai_nodes::AiNode;
+    use ai_vector::AtVector;
+    use std::sync::atomic::AtomicBool;
+    use std::task::Wake;
+    use std::thread;
     #[test]
-    fn render_testing(){        
-        AiBegin(AI_SESSION_BATCH);
-        AiRender(AI_RENDER_MODE_FREE);
-        AiRenderEnd();
-        AiEnd();
+    fn render_testing(){
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let universe = Universe::default(&session);
+        let camera = AiNode(&universe, "persp_camera", Some("camera"), None).unwrap();
+        let scene = TestScene::new(&universe, &camera);
+        scene.sphere("sphere", AtVector { x: 0.0, y: 0.0, z: -5.0 }, 1.0);
+        let framebuffer = scene.render();
+        // The sphere covers the center of the image but not the corners, and is shaded by the default shader.
+        assert_eq!(framebuffer.pixel(8, 8).a, 1.0);
+        assert!(framebuffer.pixel(8, 8).r > 0.0);
+        assert_eq!(framebuffer.pixel(0, 0).a, 0.0);
+        assert_eq!(framebuffer.pixel(15, 15).a, 0.0);
+    }
+    #[test]
+    fn render_other_universe(){
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let universe = Universe::new(&session);
+        assert_eq!(AiRender(&universe, AI_RENDER_MODE_FREE), Err(ArnoldError::NotRenderable));
+        assert_eq!(AiRenderInterrupt(&universe, AI_BLOCKING), Err(ArnoldError::NotRenderable));
+        assert_eq!(AiRenderAbort(&universe, AI_NON_BLOCKING), Err(ArnoldError::NotRenderable));
+    }
+    #[test]
+    fn render_status_values(){
+        for status in [RenderStatus::NotStarted, RenderStatus::Paused, RenderStatus::Restarting, RenderStatus::Rendering, RenderStatus::Finished, RenderStatus::Failed].iter() {
+            assert_eq!(RenderStatus::from_raw(status.as_raw()), *status);
+        }
+        assert_eq!(RenderUpdate::from_raw(AI_RENDER_UPDATE_AFTER_PASS), RenderUpdate::AfterPass);
+        assert_eq!(RenderUpdate::from_raw(42), RenderUpdate::Other(42));
+    }
+    #[test]
+    fn render_callback(){
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let universe = Universe::default(&session);
+        let camera = AiNode(&universe, "persp_camera", Some("camera"), None).unwrap();
+        let scene = TestScene::new(&universe, &camera);
+        scene.sphere("sphere", AtVector { x: 0.0, y: 0.0, z: -5.0 }, 1.0);
+        let updates = Arc::new(Mutex::new(Vec::new()));
+        let seen = updates.clone();
+        let render = render_begin(&universe, AI_RENDER_MODE_CAMERA, move |update| {
+            seen.lock().unwrap().push(update);
+            RenderStatus::Rendering
+        }).unwrap();
+        render.end().unwrap();
+        let updates = updates.lock().unwrap();
+        assert!(updates.contains(&RenderUpdate::AfterPass));
+        assert_eq!(updates.last(), Some(&RenderUpdate::Finished));
+        assert!(render_begin(&Universe::new(&session), AI_RENDER_MODE_FREE, |_| RenderStatus::Rendering).is_err());
+    }
+    #[test]
+    #[should_panic(expected = "render callback failed")]
+    fn render_callback_panic(){
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let universe = Universe::default(&session);
+        let camera = AiNode(&universe, "persp_camera", Some("camera"), None).unwrap();
+        let scene = TestScene::new(&universe, &camera);
+        scene.sphere("sphere", AtVector { x: 0.0, y: 0.0, z: -5.0 }, 1.0);
+        let render = render_begin(&universe, AI_RENDER_MODE_CAMERA, |_| -> RenderStatus {
+            panic!("render callback failed")
+        }).unwrap();
+        // The panic is caught in the callback, and resumed here with its original payload.
+        let _ = render.end();
+    }
+    #[test]
+    fn render_controller(){
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let universe = Universe::default(&session);
+        assert!(match RenderController::start(&universe, AI_RENDER_MODE_FREE, |_| RenderStatus::Rendering) {
+            Err(ArnoldError::NotInteractive) => true,
+            _ => false,
+        });
+    }
+    #[test]
+    fn interactive_edits(){
+        let session = Session::begin(AI_SESSION_INTERACTIVE, AI_LOG_NONE);
+        let universe = Universe::default(&session);
+        let camera = AiNode(&universe, "persp_camera", Some("camera"), None).unwrap();
+        let scene = TestScene::new(&universe, &camera);
+        let sphere = scene.sphere("sphere", AtVector { x: 0.0, y: 0.0, z: -5.0 }, 1.0);
+        universe.options().set("AA_samples", 4).unwrap();
+        assert_eq!(AiRenderGetStatus(&universe), RenderStatus::NotStarted);
+
+        let updates = Arc::new(Mutex::new(Vec::new()));
+        let editing = Arc::new(AtomicBool::new(false));
+        let (seen, edit_requested) = (updates.clone(), editing.clone());
+        let mut controller = RenderController::start(&universe, AI_RENDER_MODE_CAMERA, move |update| {
+            seen.lock().unwrap().push(update);
+            if update == RenderUpdate::AfterPass && !edit_requested.load(Ordering::SeqCst) {
+                // Hold the render after its first pass until the edit starts, so the edit lands mid-render.
+                while !edit_requested.load(Ordering::SeqCst) {
+                    thread::sleep(Duration::from_millis(1));
+                }
+                thread::sleep(Duration::from_millis(100));
+            }
+            RenderStatus::Rendering
+        }).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while controller.passes() == 0 {
+            assert!(Instant::now() < deadline, "the render never completed a pass");
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(controller.status(), RenderStatus::Rendering);
+        editing.store(true, Ordering::SeqCst);
+        controller.set(&sphere, "radius", 2.0).unwrap();
+        assert!(match controller.status() {
+            RenderStatus::Rendering | RenderStatus::Restarting | RenderStatus::Finished => true,
+            _ => false,
+        });
+        controller.end().unwrap();
+
+        // The edit interrupted the render, which restarted and ran to the end with the bigger sphere.
+        let updates = updates.lock().unwrap();
+        let interrupt = updates
+            .iter()
+            .position(|update| *update == RenderUpdate::Interrupt)
+            .expect("the edit did not interrupt the render");
+        assert!(updates[interrupt..].contains(&RenderUpdate::AfterPass));
+        assert_eq!(updates.last(), Some(&RenderUpdate::Finished));
+        assert_eq!(scene.framebuffer().pixel(8, 3).a, 1.0);
+    }
+    /// Make the scene take seconds to render, so it is still running when the test stops it.
+    fn slow_down(universe: &Universe) {
+        let options = universe.options();
+        options.set("xres", 512).unwrap();
+        options.set("yres", 512).unwrap();
+        options.set("AA_samples", 32).unwrap();
+    }
+    struct ThreadWaker(thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    /// Minimal executor, parking the test thread until the future is woken.
+    fn block_on<F: Future + Unpin>(mut future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            match Pin::new(&mut future).poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+    #[test]
+    fn async_render(){
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let universe = Universe::default(&session);
+        let camera = AiNode(&universe, "persp_camera", Some("camera"), None).unwrap();
+        let scene = TestScene::new(&universe, &camera);
+        scene.sphere("sphere", AtVector { x: 0.0, y: 0.0, z: -5.0 }, 1.0);
+        // The future can be polled on another thread.
+        let mut future = render_async(&universe, AI_RENDER_MODE_CAMERA);
+        let summary = thread::scope(|scope| scope.spawn(|| block_on(&mut future)).join().unwrap()).unwrap();
+        assert!(summary.passes >= 1);
+        assert!(summary.elapsed > Duration::new(0, 0));
+        assert_eq!(scene.framebuffer().pixel(8, 8).a, 1.0);
+        // Polling a completed future does not panic.
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        assert!(Pin::new(&mut future).poll(&mut Context::from_waker(&waker)).is_pending());
+        drop(future);
+        // A render interrupted long before it can finish completes with an error.
+        slow_down(&universe);
+        let future = render_async(&universe, AI_RENDER_MODE_CAMERA);
+        AiRenderInterrupt(&universe, AI_BLOCKING).unwrap();
+        assert_eq!(block_on(future), Err(ArnoldError::Render { code: AI_INTERRUPT }));
+        let other = Universe::new(&session);
+        assert_eq!(block_on(render_async(&other, AI_RENDER_MODE_FREE)), Err(ArnoldError::NotRenderable));
+        // Dropping a pending render aborts it.
+        drop(render_async(&universe, AI_RENDER_MODE_FREE));
+    }
+    #[test]
+    fn budget_render(){
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let universe = Universe::default(&session);
+        let camera = AiNode(&universe, "persp_camera", Some("camera"), None).unwrap();
+        let scene = TestScene::new(&universe, &camera);
+        scene.sphere("sphere", AtVector { x: 0.0, y: 0.0, z: -5.0 }, 1.0);
+        let summary = render_with_budget(&universe, AI_RENDER_MODE_CAMERA, Duration::from_secs(60)).unwrap();
+        assert!(summary.passes >= 1);
+        assert!(summary.elapsed < Duration::from_secs(60));
+        let other = Universe::new(&session);
+        assert_eq!(render_with_budget(&other, AI_RENDER_MODE_FREE, Duration::from_secs(1)), Err(ArnoldError::NotRenderable));
+        // A scene taking seconds to render always runs out of a 50ms budget.
+        slow_down(&universe);
+        let budget = Duration::from_millis(50);
+        let started = Instant::now();
+        match render_with_budget(&universe, AI_RENDER_MODE_CAMERA, budget) {
+            Err(ArnoldError::Timeout { budget: timed_out, .. }) => assert_eq!(timed_out, budget),
+            result => panic!("expected a timeout, got {:?}", result),
+        }
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+    #[test]
+    fn session_is_exclusive(){
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        assert!(Session::try_begin(AI_SESSION_BATCH, AI_LOG_NONE).is_none());
+        assert_eq!(session.mode(), AI_SESSION_BATCH);
     }
 }
\ No newline at end of file
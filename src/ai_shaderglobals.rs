@@ -0,0 +1,199 @@
+//! Shader Globals
+//!
+//! [`ShaderGlobals`](struct.ShaderGlobals.html) describes the shading point a [`Shader`](../ai_shaders/trait.Shader.html) is evaluated at, and evaluates the shader parameters there, following any links to other shaders.
+
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+
+use ai_bindings;
+pub use ai_bindings::AtShaderGlobals;
+use ai_bindings::{AtNode, AtRGB, AtRGBA};
+use ai_nodes::Node;
+use ai_params::ParamType;
+use ai_string::AtString;
+use ai_vector::{AtVector, AtVector2};
+use error::ArnoldError;
+
+use std::marker::PhantomData;
+
+/// The shading context passed to [`Shader::evaluate`](../ai_shaders/trait.Shader.html#tymethod.evaluate).
+///
+/// Parameters are evaluated by index, in the order they were declared in [`Shader::parameters`](../ai_shaders/trait.Shader.html#tymethod.parameters). The index and type are checked against the node entry before Arnold evaluates the parameter: the `eval_*` methods return `ArnoldError::IndexOutOfBounds` if the shader has no parameter at that index, and `ArnoldError::TypeMismatch` if it has another type. Evaluating a linked parameter runs the upstream shader at the same shading point.
+///
+/// The shader node and the shaded object are only lent to the shader, as references, so it cannot destroy them while Arnold renders.
+pub struct ShaderGlobals<'a> {
+    sg: *mut AtShaderGlobals,
+    node: Node<'a>,
+    object: Option<Node<'a>>,
+    _sg: PhantomData<&'a AtShaderGlobals>,
+}
+
+impl<'a> ShaderGlobals<'a> {
+    /// Wrap the shader globals passed to a shader_evaluate method.
+    ///
+    /// # Safety
+    /// `sg` must be NULL or point to valid shader globals, and `node` must be NULL or point to the shader being evaluated, both for `'a`.
+    /// # Returns
+    /// the shader globals, or `None` if either pointer is NULL
+    pub unsafe fn from_raw(sg: *mut AtShaderGlobals, node: *mut AtNode) -> Option<ShaderGlobals<'a>> {
+        if sg.is_null() {
+            return None;
+        }
+        Some(ShaderGlobals {
+            sg: sg,
+            node: Node::from_raw(node)?,
+            object: Node::from_raw((*sg).Op),
+            _sg: PhantomData,
+        })
+    }
+
+    /// The raw shader globals pointer, for use with the C API.
+    pub fn as_ptr(&self) -> *mut AtShaderGlobals {
+        self.sg
+    }
+
+    /// The raw shader globals, for the members without an accessor.
+    pub fn raw(&self) -> &AtShaderGlobals {
+        unsafe { &*self.sg }
+    }
+
+    /// The shader node being evaluated.
+    pub fn node(&self) -> &Node<'a> {
+        &self.node
+    }
+
+    /// The object being shaded, if any.
+    pub fn object(&self) -> Option<&Node<'a>> {
+        self.object.as_ref()
+    }
+
+    /// Raster-space pixel coordinates.
+    pub fn pixel(&self) -> (i32, i32) {
+        (self.raw().x, self.raw().y)
+    }
+
+    /// Id of the thread evaluating the shader.
+    pub fn tid(&self) -> u16 {
+        self.raw().tid
+    }
+
+    /// Ray origin.
+    pub fn ray_origin(&self) -> AtVector {
+        self.raw().Ro
+    }
+
+    /// Ray direction, normalized.
+    pub fn ray_direction(&self) -> AtVector {
+        self.raw().Rd
+    }
+
+    /// Shading point, in world space.
+    pub fn point(&self) -> AtVector {
+        self.raw().P
+    }
+
+    /// Shading normal.
+    pub fn normal(&self) -> AtVector {
+        self.raw().N
+    }
+
+    /// Shading normal, facing the viewer.
+    pub fn normal_forward(&self) -> AtVector {
+        self.raw().Nf
+    }
+
+    /// Geometric normal.
+    pub fn geometric_normal(&self) -> AtVector {
+        self.raw().Ng
+    }
+
+    /// Smoothed normal, before bump mapping.
+    pub fn smoothed_normal(&self) -> AtVector {
+        self.raw().Ns
+    }
+
+    /// Surface parametric coordinates.
+    pub fn uv(&self) -> (f32, f32) {
+        (self.raw().u, self.raw().v)
+    }
+
+    /// Check that the shader declares parameter `index` with type `requested`, so Arnold evaluates it as a value of that type.
+    fn check_param(&self, index: i32, requested: ParamType) -> Result<(), ArnoldError> {
+        let entry = self.node.entry();
+        let param = match if index >= 0 { entry.param_at(index) } else { None } {
+            Some(param) => param,
+            None => {
+                return Err(ArnoldError::IndexOutOfBounds {
+                    index: index as usize,
+                    len: entry.num_params() as usize,
+                })
+            }
+        };
+        match (param.param_type(), requested) {
+            (found, requested) if found == requested => Ok(()),
+            (ParamType::Enum, ParamType::Int) => Ok(()),
+            (found, requested) => Err(self.node.type_mismatch(&param.name().to_string_lossy(), found, requested)),
+        }
+    }
+
+    /// Evaluate a byte parameter.
+    pub fn eval_byte(&self, index: i32) -> Result<u8, ArnoldError> {
+        self.check_param(index, ParamType::Byte)?;
+        Ok(unsafe { ai_bindings::AiShaderEvalParamFuncByte(self.sg, self.node.as_ptr(), index) })
+    }
+
+    /// Evaluate an integer or enum parameter.
+    pub fn eval_int(&self, index: i32) -> Result<i32, ArnoldError> {
+        self.check_param(index, ParamType::Int)?;
+        Ok(unsafe { ai_bindings::AiShaderEvalParamFuncInt(self.sg, self.node.as_ptr(), index) })
+    }
+
+    /// Evaluate an unsigned integer parameter.
+    pub fn eval_uint(&self, index: i32) -> Result<u32, ArnoldError> {
+        self.check_param(index, ParamType::UInt)?;
+        Ok(unsafe { ai_bindings::AiShaderEvalParamFuncUInt(self.sg, self.node.as_ptr(), index) })
+    }
+
+    /// Evaluate a boolean parameter.
+    pub fn eval_bool(&self, index: i32) -> Result<bool, ArnoldError> {
+        self.check_param(index, ParamType::Boolean)?;
+        Ok(unsafe { ai_bindings::AiShaderEvalParamFuncBool(self.sg, self.node.as_ptr(), index) })
+    }
+
+    /// Evaluate a float parameter.
+    pub fn eval_float(&self, index: i32) -> Result<f32, ArnoldError> {
+        self.check_param(index, ParamType::Float)?;
+        Ok(unsafe { ai_bindings::AiShaderEvalParamFuncFlt(self.sg, self.node.as_ptr(), index) })
+    }
+
+    /// Evaluate an RGB color parameter.
+    pub fn eval_rgb(&self, index: i32) -> Result<AtRGB, ArnoldError> {
+        self.check_param(index, ParamType::RGB)?;
+        Ok(unsafe { *ai_bindings::AiShaderEvalParamFuncRGB(self.sg, self.node.as_ptr(), index) })
+    }
+
+    /// Evaluate an RGBA color parameter.
+    pub fn eval_rgba(&self, index: i32) -> Result<AtRGBA, ArnoldError> {
+        self.check_param(index, ParamType::RGBA)?;
+        Ok(unsafe { *ai_bindings::AiShaderEvalParamFuncRGBA(self.sg, self.node.as_ptr(), index) })
+    }
+
+    /// Evaluate a vector parameter.
+    pub fn eval_vector(&self, index: i32) -> Result<AtVector, ArnoldError> {
+        self.check_param(index, ParamType::Vector)?;
+        Ok(unsafe { *ai_bindings::AiShaderEvalParamFuncVec(self.sg, self.node.as_ptr(), index) })
+    }
+
+    /// Evaluate a 2D vector parameter.
+    pub fn eval_vector2(&self, index: i32) -> Result<AtVector2, ArnoldError> {
+        self.check_param(index, ParamType::Vector2)?;
+        Ok(unsafe { *ai_bindings::AiShaderEvalParamFuncVec2(self.sg, self.node.as_ptr(), index) })
+    }
+
+    /// Evaluate a string parameter.
+    pub fn eval_string(&self, index: i32) -> Result<AtString, ArnoldError> {
+        self.check_param(index, ParamType::String)?;
+        Ok(unsafe { ai_bindings::AiShaderEvalParamFuncStr(self.sg, self.node.as_ptr(), index) })
+    }
+}
@@ -0,0 +1,224 @@
+//! Shader API
+//!
+//! A shader can be implemented in Rust with the [`Shader`](trait.Shader.html) trait and installed with [`install_shader`](fn.install_shader.html). The type of [`Shader::Output`](trait.Shader.html#associatedtype.Output) sets the output type of the node, so it links to inputs of the same type:
+//! ```
+//! struct IdMatte;
+//!
+//! impl Shader for IdMatte {
+//!     type Output = AtRGB;
+//!     fn parameters(params: &mut ParamList) -> Result<(), ArnoldError> {
+//!         params.int("id", 0)?;
+//!         Ok(())
+//!     }
+//!     fn initialize(_node: &Node) -> IdMatte { IdMatte }
+//!     fn evaluate(&self, sg: &ShaderGlobals) -> AtRGB {
+//!         let id = sg.eval_int(0).unwrap_or(0);
+//!         AtRGB { r: (id & 1) as f32, g: (id & 2) as f32, b: (id & 4) as f32 }
+//!     }
+//! }
+//!
+//! install_shader::<IdMatte>(&session, "id_matte")?;
+//! ```
+
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+
+use ai_bindings::{AtCommonMethods, AtList, AtNode, AtNodeEntry, AtRGB, AtRGBA, AtShaderNodeMethods};
+use ai_node_entry::{drop_instance, guard_method, install_methods, instance, instance_mut, report, set_instance, NodeEntry, NodeType};
+use ai_nodes::Node;
+use ai_params::{ParamList, ParamType};
+use ai_render::Session;
+use ai_shaderglobals::{AtShaderGlobals, ShaderGlobals};
+use ai_vector::{AtVector, AtVector2};
+use error::ArnoldError;
+
+use std::os::raw::c_void;
+use std::ptr;
+
+/// A value a shader can output, stored in the shader globals `out` member.
+///
+/// # Safety
+/// `TYPE` must be the AI_TYPE_* whose output slot holds a `Self`.
+pub unsafe trait ShaderOutput: Copy {
+    /// The matching output type.
+    const TYPE: ParamType;
+}
+
+unsafe impl ShaderOutput for u8 {
+    const TYPE: ParamType = ParamType::Byte;
+}
+unsafe impl ShaderOutput for i32 {
+    const TYPE: ParamType = ParamType::Int;
+}
+unsafe impl ShaderOutput for u32 {
+    const TYPE: ParamType = ParamType::UInt;
+}
+unsafe impl ShaderOutput for bool {
+    const TYPE: ParamType = ParamType::Boolean;
+}
+unsafe impl ShaderOutput for f32 {
+    const TYPE: ParamType = ParamType::Float;
+}
+unsafe impl ShaderOutput for AtRGB {
+    const TYPE: ParamType = ParamType::RGB;
+}
+unsafe impl ShaderOutput for AtRGBA {
+    const TYPE: ParamType = ParamType::RGBA;
+}
+unsafe impl ShaderOutput for AtVector {
+    const TYPE: ParamType = ParamType::Vector;
+}
+unsafe impl ShaderOutput for AtVector2 {
+    const TYPE: ParamType = ParamType::Vector2;
+}
+
+/// A shader implemented in Rust.
+///
+/// Shaders follow the [lifecycle of node types implemented in Rust](../ai_node_entry/index.html#node-types-implemented-in-rust), with [`finish`](#method.finish) called right before the shader is dropped.
+pub trait Shader: Send + Sync + Sized + 'static {
+    /// Type of the shader output, which sets the output type of the node entry.
+    type Output: ShaderOutput;
+
+    /// Declare the shader parameters, numbered in declaration order.
+    fn parameters(params: &mut ParamList) -> Result<(), ArnoldError>;
+
+    /// Create the shader for a new shader node.
+    fn initialize(node: &Node) -> Self;
+
+    /// Read again the parameters that do not vary per shading point.
+    fn update(&mut self, _node: &Node) {}
+
+    /// Called when the node is destroyed, before the shader is dropped.
+    fn finish(&mut self, _node: &Node) {}
+
+    /// Compute the shader output at a shading point.
+    fn evaluate(&self, sg: &ShaderGlobals) -> Self::Output;
+}
+
+/// Install a node type for a Rust [`Shader`](trait.Shader.html).
+///
+/// The node entry output type is the AI_TYPE_* of `S::Output`.
+/// # Parameters
+/// * `session` - the active session
+/// * `name` - name of the new shader node type
+/// # Returns
+/// the installed node entry, or an error, see [node types implemented in Rust](../ai_node_entry/index.html#node-types-implemented-in-rust)
+pub fn install_shader<'s, S: Shader>(session: &'s Session, name: &str) -> Result<NodeEntry<'s>, ArnoldError> {
+    let cmethods = AtCommonMethods {
+        PluginInitialize: None,
+        PluginCleanup: None,
+        Parameters: Some(shader_parameters::<S>),
+        Initialize: Some(shader_initialize::<S>),
+        Update: Some(shader_update::<S>),
+        Finish: Some(shader_finish::<S>),
+    };
+    let dmethods: &'static AtShaderNodeMethods = Box::leak(Box::new(AtShaderNodeMethods {
+        Evaluate: Some(shader_evaluate::<S>),
+    }));
+    install_methods(session, NodeType::SHADER, S::Output::TYPE, name, cmethods, dmethods as *const AtShaderNodeMethods as *const c_void)
+}
+
+unsafe extern "C" fn shader_parameters<S: Shader>(params: *mut AtList, _nentry: *mut AtNodeEntry) {
+    guard_method("shader parameters", None, || report("shader parameters", S::parameters(&mut ParamList::from_raw(params))));
+}
+
+unsafe extern "C" fn shader_initialize<S: Shader>(node: *mut AtNode) {
+    let shader = guard_method("shader initialize", None, || Node::from_raw(node).map(|node| S::initialize(&node)));
+    if let Some(shader) = shader {
+        set_instance(node, shader);
+    }
+}
+
+unsafe extern "C" fn shader_update<S: Shader>(node: *mut AtNode) {
+    if let (Some(shader), Some(node)) = (instance_mut::<S>(node), Node::from_raw(node)) {
+        guard_method("shader update", (), || shader.update(&node))
+    }
+}
+
+unsafe extern "C" fn shader_finish<S: Shader>(node: *mut AtNode) {
+    if let (Some(shader), Some(node)) = (instance_mut::<S>(node), Node::from_raw(node)) {
+        guard_method("shader finish", (), || shader.finish(&node))
+    }
+    guard_method("shader finish", (), || drop_instance::<S>(node));
+}
+
+unsafe extern "C" fn shader_evaluate<S: Shader>(node: *mut AtNode, sg: *mut AtShaderGlobals) {
+    let shader = match instance::<S>(node) {
+        Some(shader) => shader,
+        None => return,
+    };
+    let globals = match ShaderGlobals::from_raw(sg, node) {
+        Some(globals) => globals,
+        None => return,
+    };
+    if let Some(output) = guard_method("shader evaluate", None, || Some(shader.evaluate(&globals))) {
+        let out = ptr::addr_of_mut!((*sg).out) as *mut S::Output;
+        out.write_unaligned(output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_drivers::TestScene;
+    use ai_msg::AI_LOG_NONE;
+    use ai_node_entry::AiNodeEntryGetOutputType;
+    use ai_nodes::AiNode;
+    use ai_render::AI_SESSION_BATCH;
+    use ai_universe::Universe;
+
+    /// Flat color shader, with an intensity multiplier.
+    struct FlatColor;
+
+    impl Shader for FlatColor {
+        type Output = AtRGB;
+        fn parameters(params: &mut ParamList) -> Result<(), ArnoldError> {
+            assert_eq!(params.rgb("color", AtRGB { r: 1.0, g: 1.0, b: 1.0 }), Ok(0));
+            assert_eq!(params.float("intensity", 1.0), Ok(1));
+            assert!(params.float("bad\0name", 0.0).is_err());
+            Ok(())
+        }
+        fn initialize(_node: &Node) -> FlatColor {
+            FlatColor
+        }
+        fn evaluate(&self, sg: &ShaderGlobals) -> AtRGB {
+            // The shader and the object are only borrowed from the shader globals.
+            assert!(sg.node().is("flat_color"));
+            assert!(sg.object().map_or(false, |object| object.is("sphere")));
+            let (color, intensity) = (sg.eval_rgb(0).unwrap(), sg.eval_float(1).unwrap());
+            // Parameters are checked against the declaration before Arnold evaluates them.
+            let len = sg.node().entry().num_params();
+            assert_eq!(sg.eval_rgb(len).err(), Some(ArnoldError::IndexOutOfBounds { index: len as usize, len: len as usize }));
+            assert!(sg.eval_rgb(-1).is_err());
+            match sg.eval_float(0) {
+                Err(ArnoldError::TypeMismatch { expected, found, .. }) => assert_eq!((expected, found), (ParamType::RGB, ParamType::Float)),
+                other => panic!("unexpected {:?}", other),
+            }
+            AtRGB { r: color.r * intensity, g: color.g * intensity, b: color.b * intensity }
+        }
+    }
+    #[test]
+    fn rust_shader() {
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let entry = install_shader::<FlatColor>(&session, "flat_color").unwrap();
+        assert_eq!(AiNodeEntryGetOutputType(&entry), ParamType::RGB);
+        assert_eq!(entry.node_type(), NodeType::SHADER);
+        assert!(entry.param("color").is_some());
+
+        let universe = Universe::default(&session);
+        let camera = AiNode(&universe, "persp_camera", Some("camera"), None).unwrap();
+        let scene = TestScene::new(&universe, &camera);
+        let sphere = scene.sphere("sphere", AtVector { x: 0.0, y: 0.0, z: -5.0 }, 1.0);
+        let shader = AiNode(&universe, "flat_color", Some("shader"), None).unwrap();
+        shader.set("color", AtRGB { r: 0.125, g: 0.25, b: 0.5 }).unwrap();
+        shader.set("intensity", 2.0).unwrap();
+        sphere.set("shader", &shader).unwrap();
+        let framebuffer = scene.render();
+
+        // The sphere is shaded with the flat color scaled by the intensity.
+        let center = framebuffer.pixel(8, 8);
+        assert_eq!((center.r, center.g, center.b, center.a), (0.25, 0.5, 1.0, 1.0));
+        assert_eq!(framebuffer.pixel(0, 0).a, 0.0);
+    }
+}
@@ -8,10 +8,16 @@ use ai_bindings::AiAtStringLength;
 use ai_bindings::AiCreateAtStringData_private;
 pub use ai_bindings::AtString;
 
-use std::ffi::CStr;
+use error::ArnoldError;
+
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::ffi::{CStr, CString};
 use std::fmt;
+use std::os::raw::c_char;
 use std::ptr;
 use std::str;
+use std::str::Utf8Error;
 
 impl AtString {
     pub fn new() -> Self {
@@ -32,45 +38,68 @@ impl AtString {
         unsafe { AiAtStringHash(self.data) }
     }
 
-    pub fn to_str(&self) -> &str {
+    /// Borrow the string, or return an error if it is not valid UTF-8. NULL is the empty string.
+    pub fn to_str(&self) -> Result<&str, Utf8Error> {
+        if self.data.is_null() {
+            return Ok("");
+        }
         let slice = unsafe {
             CStr::from_ptr(self.data)
         };
-        slice.to_str().unwrap()
+        slice.to_str()
+    }
 
+    /// Borrow the string, replacing invalid UTF-8 sequences with U+FFFD. NULL is the empty string.
+    pub fn to_string_lossy(&self) -> Cow<'_, str> {
+        if self.data.is_null() {
+            return Cow::Borrowed("");
+        }
+        unsafe { CStr::from_ptr(self.data) }.to_string_lossy()
     }
 }
 
-impl<'a> From<&'a str> for AtString {
-    fn from(s: &'a str) -> Self {
-        if s.len() == 0 {
-            AtString { data: ptr::null() }
-        } else {
-            AtString {
-                data: unsafe { AiCreateAtStringData_private(s.to_owned().as_ptr() as *const i8) },
-            }
-        }
+/// Borrow a C string returned by Arnold.
+///
+/// # Safety
+/// `data` must be NULL or point to a NUL-terminated string that lives for `'a`.
+/// # Returns
+/// `None` for NULL, or an error if the string is not valid UTF-8
+pub(crate) unsafe fn from_c_str<'a>(data: *const c_char) -> Result<Option<&'a str>, Utf8Error> {
+    if data.is_null() {
+        Ok(None)
+    } else {
+        CStr::from_ptr(data).to_str().map(Some)
     }
 }
 
-impl From<String> for AtString {
-    fn from(s: String) -> Self {
-        if s.len() == 0 {
-            AtString { data: ptr::null() }
-        } else {
-            AtString {
-                data: unsafe { AiCreateAtStringData_private(s.as_ptr() as *const i8) },
-            }
+impl<'a> TryFrom<&'a str> for AtString {
+    type Error = ArnoldError;
+
+    /// Intern `s` as an AtString, or return an error if it contains a NUL byte. The empty string is NULL.
+    fn try_from(s: &'a str) -> Result<Self, ArnoldError> {
+        if s.is_empty() {
+            return Ok(AtString { data: ptr::null() });
         }
+        // Arnold copies the string into its own table, so the CString only has to outlive the call.
+        let c_str = CString::new(s)?;
+        Ok(AtString {
+            data: unsafe { AiCreateAtStringData_private(c_str.as_ptr()) },
+        })
+    }
+}
+
+impl TryFrom<String> for AtString {
+    type Error = ArnoldError;
+
+    /// Intern `s` as an AtString, or return an error if it contains a NUL byte. The empty string is NULL.
+    fn try_from(s: String) -> Result<Self, ArnoldError> {
+        AtString::try_from(s.as_str())
     }
 }
 
 impl fmt::Display for AtString {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        unsafe {
-            let slice = CStr::from_ptr(self.data);
-            write!(f, "{}", str::from_utf8(slice.to_bytes()).unwrap())
-        }
+        write!(f, "{}", self.to_string_lossy())
     }
 }
 
@@ -83,50 +112,54 @@ impl PartialEq for AtString {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ai_msg::{AiMsgSetConsoleFlags, AI_LOG_NONE};
-    use ai_render::{AiBegin, AiEnd, AI_SESSION_BATCH};
+    use ai_msg::AI_LOG_NONE;
+    use ai_render::{Session, AI_SESSION_BATCH};
     #[test]
     fn string_length() {
-        AiBegin(AI_SESSION_BATCH);
-        AiMsgSetConsoleFlags(AI_LOG_NONE);
+        let _session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
         let rust_str = "TEST";
         let rust_string = rust_str.to_string();
-        let at_string_str = AtString::from(rust_str);
-        let at_string_string = AtString::from(rust_string);
+        let at_string_str = AtString::try_from(rust_str).unwrap();
+        let at_string_string = AtString::try_from(rust_string).unwrap();
         assert_eq!(at_string_str.length(), 4);
         assert_eq!(at_string_string.length(), 4);
-        AiEnd();
     }
     #[test]
     fn string_cmp() {
-        AiBegin(AI_SESSION_BATCH);
-        AiMsgSetConsoleFlags(AI_LOG_NONE);
+        let _session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
         let rust_str = "TEST";
         let rust_string = rust_str.to_string();
-        let at_string_str = AtString::from(rust_str);
-        let at_string_string = AtString::from(rust_string);
+        let at_string_str = AtString::try_from(rust_str).unwrap();
+        let at_string_string = AtString::try_from(rust_string).unwrap();
         assert_eq!(at_string_str, at_string_string);
-        assert_eq!(AtString::from("åäö"), AtString::from("åäö"));
-        AiEnd();
+        assert_eq!(AtString::try_from("åäö").unwrap(), AtString::try_from("åäö").unwrap());
     }
     #[test]
     fn string_empty() {
-        AiBegin(AI_SESSION_BATCH);
-        AiMsgSetConsoleFlags(AI_LOG_NONE);
+        let _session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
         let rust_str = "";
-        let at_string_str = AtString::from(rust_str);
-        let at_string_string = AtString::from("");
+        let at_string_str = AtString::try_from(rust_str).unwrap();
+        let at_string_string = AtString::try_from("").unwrap();
         assert!(at_string_str.empty());
         assert!(at_string_string.empty());
-        assert!(AtString::from("").empty());
-        assert!(AtString::from(format!("")).empty());
-        AiEnd();
+        assert!(AtString::try_from("").unwrap().empty());
+        assert!(AtString::try_from(format!("")).unwrap().empty());
     }
     #[test]
     fn string_hash() {
-        AiBegin(AI_SESSION_BATCH);
-        AiMsgSetConsoleFlags(AI_LOG_NONE);
-        assert_eq!(AtString::from("åäö").hash(), 6401777111767391186);
-        AiEnd();
+        let _session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        assert_eq!(AtString::try_from("åäö").unwrap().hash(), 6401777111767391186);
+    }
+    #[test]
+    fn string_nul() {
+        let _session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        assert_eq!(
+            AtString::try_from("a\0b"),
+            Err(ArnoldError::InvalidString {
+                string: "a\0b".to_owned(),
+                position: 1
+            })
+        );
+        assert!(AtString::try_from("a\0b".to_owned()).is_err());
     }
 }
@@ -0,0 +1,579 @@
+//! AtUniverse API
+//!
+//! The universe is the container of all the nodes in a scene: the options node, cameras, shapes, lights, shaders, etc. It can be queried for its nodes, the installed node entries, the AOVs that are being rendered and a few scene-wide properties:
+//! ```
+//! let universe = Universe::default(&session);
+//! for node in universe.nodes(AI_NODE_SHAPE) {
+//!     println!("{}", node.name());
+//! }
+//! ```
+//!
+//! Besides the default universe, any number of universes can be created with [`Universe::new`](struct.Universe.html#method.new), for example to load a reference scene next to a working scene:
+//! ```
+//! let reference = Universe::new(&session);
+//! AiASSLoad(&reference, "reference.ass", AI_NODE_ALL);
+//! let working = Universe::default(&session);
+//! AiASSLoad(&working, "working.ass", AI_NODE_ALL);
+//! ```
+//! Only the default universe can be rendered.
+
+#![allow(non_upper_case_globals)]
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+
+use ai_bbox::AtBBox;
+use ai_array::AiArrayCopy;
+use ai_bindings;
+pub use ai_bindings::{AtAOVEntry, AtAOVIterator, AtNodeEntryIterator, AtNodeIterator, AtUniverse};
+use error::ArnoldError;
+use ai_node_entry::{NodeEntry, NodeType, AI_NODE_ALL};
+use ai_nodes::{AiNode, AiNodeDestroy, AiNodeReplace, Node, NodeId};
+use ai_params::{ParamType, ParamValue};
+use ai_render::Session;
+
+use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set while a [`Universe::default`](struct.Universe.html#method.default) handle is alive.
+static DEFAULT_UNIVERSE_TAKEN: AtomicBool = AtomicBool::new(false);
+
+/// Forget any default universe handle left over from a previous session, such as one passed to `mem::forget`.
+pub(crate) fn reset_default_universe() {
+    DEFAULT_UNIVERSE_TAKEN.store(false, Ordering::SeqCst);
+}
+
+/// A universe of nodes.
+///
+/// A universe created with [`new`](#method.new) is destroyed, together with all its nodes, when dropped. The default universe lives until AiEnd().
+/// Nodes borrow the universe they were created in, so they cannot outlive it, and nodes can only be destroyed while no handle to them is alive, see [`destroy_node`](#method.destroy_node).
+#[derive(Debug, PartialEq)]
+pub struct Universe<'s> {
+    universe: *mut AtUniverse,
+    _session: PhantomData<&'s Session>,
+}
+
+impl<'s> Universe<'s> {
+    /// The default universe, the one that is rendered.
+    ///
+    /// # Panics
+    /// Panics if another handle to the default universe is still alive, as it could be used to destroy nodes borrowed from this one.
+    pub fn default(_session: &'s Session) -> Universe<'s> {
+        assert!(
+            !DEFAULT_UNIVERSE_TAKEN.swap(true, Ordering::SeqCst),
+            "the default universe is already in use"
+        );
+        Universe {
+            universe: ptr::null_mut(),
+            _session: PhantomData,
+        }
+    }
+
+    /// Create a new, empty universe, see [`AiUniverse`](fn.AiUniverse.html).
+    pub fn new(session: &'s Session) -> Universe<'s> {
+        Universe {
+            universe: AiUniverse(session),
+            _session: PhantomData,
+        }
+    }
+
+    /// Wrap a universe owned by Arnold, such as the one passed to a node method, without destroying it on drop.
+    ///
+    /// # Safety
+    /// `universe` must be NULL or a valid universe for `'s`.
+    pub(crate) unsafe fn borrow_raw(universe: *mut AtUniverse) -> ManuallyDrop<Universe<'s>> {
+        ManuallyDrop::new(Universe {
+            universe: universe,
+            _session: PhantomData,
+        })
+    }
+
+    /// The raw universe pointer, for use with the C API. NULL stands for the default universe.
+    pub fn as_ptr(&self) -> *mut AtUniverse {
+        self.universe
+    }
+
+    /// Check if this is the default universe.
+    pub fn is_default(&self) -> bool {
+        self.universe.is_null()
+    }
+
+    /// Check if `node` is still a node of this universe.
+    pub fn contains(&self, node: NodeId) -> bool {
+        self.nodes(AI_NODE_ALL).any(|n| n.id() == node)
+    }
+
+    /// Destroy a node of this universe, see [`AiNodeDestroy`](../ai_nodes/fn.AiNodeDestroy.html).
+    ///
+    /// ```
+    /// let sphere = AiNode(&universe, "sphere", Some("ball"), None)?;
+    /// universe.destroy_node(sphere.id());
+    /// ```
+    /// # Returns
+    /// true if the node was destroyed, false if it is not in this universe or could not be destroyed
+    pub fn destroy_node(&mut self, node: NodeId) -> bool {
+        AiNodeDestroy(self, node)
+    }
+
+    /// Replace `old_node` with `new_node` in every reference, see [`AiNodeReplace`](../ai_nodes/fn.AiNodeReplace.html).
+    ///
+    /// # Returns
+    /// true if the references were replaced, false if either node is not in this universe
+    pub fn replace_node(&mut self, old_node: NodeId, new_node: NodeId, remove: bool) -> bool {
+        AiNodeReplace(self, old_node, new_node, remove)
+    }
+
+    /// Copy `node` from any universe into this one, under the name `name`.
+    ///
+    /// All built-in parameters are copied, except for links and node references, which cannot point across universes. User-defined parameters are not copied.
+    /// # Returns
+    /// the new node, or an error if the node could not be created. If some parameters could not be copied, the
+    /// partial copy is destroyed and `ArnoldError::NodeCopy` lists the failures.
+    pub fn copy_node<'u>(&'u self, node: &Node, name: &str) -> Result<Node<'u>, ArnoldError> {
+        let entry = node.entry();
+        let copy = AiNode(self, entry.name(), Some(name), None)?;
+        let mut errors = Vec::new();
+        for param in entry.params() {
+            let param_name = param.name();
+            let param_str = match param_name.to_str() {
+                Ok("name") => continue,
+                Ok(param_str) => param_str,
+                Err(err) => {
+                    errors.push(err.into());
+                    continue;
+                }
+            };
+            match param.array_type() {
+                // Node arrays, such as a polymesh "shader", would point into the source universe.
+                Some(ParamType::Node) => continue,
+                Some(_) => {
+                    unsafe {
+                        let array = ai_bindings::AiNodeGetArray(node.as_ptr(), param_name);
+                        if !array.is_null() {
+                            ai_bindings::AiNodeSetArray(copy.as_ptr(), param_name, AiArrayCopy(array));
+                        }
+                    }
+                    continue;
+                }
+                None => {}
+            }
+            match node.get_value(param_str) {
+                Ok(ParamValue::Node(_)) | Ok(ParamValue::Pointer(_)) | Err(ArnoldError::UnsupportedType { .. }) => {}
+                Ok(value) => {
+                    if let Err(err) = copy.set(param_str, value) {
+                        errors.push(err);
+                    }
+                }
+                Err(err) => errors.push(err),
+            }
+        }
+        if errors.is_empty() {
+            Ok(copy)
+        } else {
+            // The copy was just created and is dropped here, so no other handle to it exists.
+            unsafe {
+                ai_bindings::AiNodeDestroy(copy.as_ptr());
+            }
+            Err(ArnoldError::NodeCopy {
+                name: name.to_owned(),
+                errors: errors,
+            })
+        }
+    }
+
+    /// The options node of the universe.
+    pub fn options<'u>(&'u self) -> Node<'u> {
+        AiUniverseGetOptions(self)
+    }
+
+    /// The active camera, or `None` if no camera is set in the options.
+    pub fn camera<'u>(&'u self) -> Option<Node<'u>> {
+        AiUniverseGetCamera(self)
+    }
+
+    /// The bounding box of all the shapes in the universe.
+    pub fn scene_bounds(&self) -> AtBBox {
+        AiUniverseGetSceneBounds(self)
+    }
+
+    /// Iterate over the nodes whose type matches `node_mask` (AI_NODE_SHAPE | AI_NODE_LIGHT, etc).
+    pub fn nodes<'u>(&'u self, node_mask: NodeType) -> NodeIter<'u> {
+        NodeIter {
+            iter: AiUniverseGetNodeIterator(self, node_mask),
+            _universe: PhantomData,
+        }
+    }
+
+    /// Iterate over the installed node entries whose type matches `node_mask` (AI_NODE_SHADER, etc).
+    pub fn node_entries(&self, node_mask: NodeType) -> NodeEntryIter<'s> {
+        NodeEntryIter {
+            iter: AiUniverseGetNodeEntryIterator(node_mask),
+            _session: PhantomData,
+        }
+    }
+
+    /// Iterate over the AOVs registered in the universe.
+    pub fn aovs(&self) -> AOVIter<'s> {
+        AOVIter {
+            iter: AiUniverseGetAOVIterator(self),
+            _session: PhantomData,
+        }
+    }
+}
+
+impl<'s> Drop for Universe<'s> {
+    fn drop(&mut self) {
+        if self.is_default() {
+            DEFAULT_UNIVERSE_TAKEN.store(false, Ordering::SeqCst);
+        } else {
+            AiUniverseDestroy(self.universe);
+        }
+    }
+}
+
+/// Iterator over the nodes of a universe, see [`Universe::nodes`](struct.Universe.html#method.nodes).
+///
+/// The underlying AtNodeIterator is destroyed when the iterator is dropped.
+#[derive(Debug)]
+pub struct NodeIter<'u> {
+    iter: *mut AtNodeIterator,
+    _universe: PhantomData<&'u Session>,
+}
+
+impl<'u> Iterator for NodeIter<'u> {
+    type Item = Node<'u>;
+
+    fn next(&mut self) -> Option<Node<'u>> {
+        if AiNodeIteratorFinished(self.iter) {
+            return None;
+        }
+        unsafe { Node::from_raw(AiNodeIteratorGetNext(self.iter)) }
+    }
+}
+
+impl<'u> Drop for NodeIter<'u> {
+    fn drop(&mut self) {
+        AiNodeIteratorDestroy(self.iter);
+    }
+}
+
+/// Iterator over the installed node entries, see [`Universe::node_entries`](struct.Universe.html#method.node_entries).
+///
+/// The underlying AtNodeEntryIterator is destroyed when the iterator is dropped.
+#[derive(Debug)]
+pub struct NodeEntryIter<'s> {
+    iter: *mut AtNodeEntryIterator,
+    _session: PhantomData<&'s Session>,
+}
+
+impl<'s> Iterator for NodeEntryIter<'s> {
+    type Item = NodeEntry<'s>;
+
+    fn next(&mut self) -> Option<NodeEntry<'s>> {
+        if AiNodeEntryIteratorFinished(self.iter) {
+            return None;
+        }
+        unsafe { NodeEntry::from_raw(AiNodeEntryIteratorGetNext(self.iter) as *const _) }
+    }
+}
+
+impl<'s> Drop for NodeEntryIter<'s> {
+    fn drop(&mut self) {
+        AiNodeEntryIteratorDestroy(self.iter);
+    }
+}
+
+/// Iterator over the AOVs of a universe, see [`Universe::aovs`](struct.Universe.html#method.aovs).
+///
+/// The underlying AtAOVIterator is destroyed when the iterator is dropped.
+#[derive(Debug)]
+pub struct AOVIter<'s> {
+    iter: *mut AtAOVIterator,
+    _session: PhantomData<&'s Session>,
+}
+
+impl<'s> Iterator for AOVIter<'s> {
+    type Item = &'s AtAOVEntry;
+
+    fn next(&mut self) -> Option<&'s AtAOVEntry> {
+        if AiAOVIteratorFinished(self.iter) {
+            return None;
+        }
+        unsafe { AiAOVIteratorGetNext(self.iter).as_ref() }
+    }
+}
+
+impl<'s> Drop for AOVIter<'s> {
+    fn drop(&mut self) {
+        AiAOVIteratorDestroy(self.iter);
+    }
+}
+
+/// Create a new, empty universe.
+///
+/// The new universe only contains its own options node. Nodes are added to it by passing it to AiNode() or AiASSLoad().
+///
+/// # Parameters
+/// * `_session` - the active session
+/// # Returns
+/// the new universe, to be released with AiUniverseDestroy()
+pub fn AiUniverse(_session: &Session) -> *mut AtUniverse {
+    unsafe { ai_bindings::AiUniverse() }
+}
+
+/// Destroy a universe created with AiUniverse(), together with all its nodes.
+///
+/// # Parameters
+/// * `universe` - the universe to destroy
+pub fn AiUniverseDestroy(universe: *mut AtUniverse) {
+    unsafe { ai_bindings::AiUniverseDestroy(universe) }
+}
+
+/// Check whether the universe has been created.
+///
+/// # Parameters
+/// * `_session` - the active session
+/// # Returns
+/// true if the universe has been created, false otherwise
+pub fn AiUniverseIsActive(_session: &Session) -> bool {
+    unsafe { ai_bindings::AiUniverseIsActive() }
+}
+
+/// Gets the options node of a universe.
+///
+/// # Parameters
+/// * `universe` - the universe to query
+/// # Returns
+/// the options node
+pub fn AiUniverseGetOptions<'u>(universe: &'u Universe) -> Node<'u> {
+    unsafe { Node::from_raw(ai_bindings::AiUniverseGetOptions(universe.as_ptr())) }
+        .expect("universe has no options node")
+}
+
+/// Gets the active camera of a universe.
+///
+/// # Parameters
+/// * `universe` - the universe to query
+/// # Returns
+/// the active camera node, or `None` if no camera is set
+pub fn AiUniverseGetCamera<'u>(universe: &'u Universe) -> Option<Node<'u>> {
+    unsafe { Node::from_raw(ai_bindings::AiUniverseGetCamera(universe.as_ptr())) }
+}
+
+/// Gets the bounding box of the scene.
+///
+/// Only the shapes that are visible to some ray type contribute to the bounds.
+///
+/// # Parameters
+/// * `universe` - the universe to query
+/// # Returns
+/// the bounding box of the scene
+pub fn AiUniverseGetSceneBounds(universe: &Universe) -> AtBBox {
+    unsafe { ai_bindings::AiUniverseGetSceneBounds(universe.as_ptr()) }
+}
+
+/// Creates a new node iterator for a universe.
+///
+/// # Parameters
+/// * `universe` - the universe whose nodes will be iterated over
+/// * `node_mask` - only iterate over nodes whose type matches the mask (AI_NODE_SHAPE | AI_NODE_LIGHT, etc)
+/// # Returns
+/// an iterator over the nodes in the universe
+pub fn AiUniverseGetNodeIterator(universe: &Universe, node_mask: NodeType) -> *mut AtNodeIterator {
+    unsafe { ai_bindings::AiUniverseGetNodeIterator(universe.as_ptr(), node_mask.bits()) }
+}
+
+/// Creates a new node entry iterator.
+///
+/// # Parameters
+/// * `node_mask` - only iterate over node entries whose type matches the mask (AI_NODE_SHADER, etc)
+/// # Returns
+/// an iterator over the installed node entries
+pub fn AiUniverseGetNodeEntryIterator(node_mask: NodeType) -> *mut AtNodeEntryIterator {
+    unsafe { ai_bindings::AiUniverseGetNodeEntryIterator(node_mask.bits()) }
+}
+
+/// Creates a new AOV iterator for a universe.
+///
+/// # Parameters
+/// * `universe` - the universe whose AOVs will be iterated over
+/// # Returns
+/// an iterator over the AOVs in the universe
+pub fn AiUniverseGetAOVIterator(universe: &Universe) -> *mut AtAOVIterator {
+    unsafe { ai_bindings::AiUniverseGetAOVIterator(universe.as_ptr()) }
+}
+
+/// Destroys a node iterator when it is no longer needed.
+///
+/// # Parameters
+/// * `iter` - node iterator that will be deallocated
+pub fn AiNodeIteratorDestroy(iter: *mut AtNodeIterator) {
+    unsafe { ai_bindings::AiNodeIteratorDestroy(iter) }
+}
+
+/// Returns current node and points node iterator to the next one.
+///
+/// # Parameters
+/// * `iter` - a node iterator
+/// # Returns
+/// the current node pointed by the iterator, or NULL if there are no more nodes to iterate over
+pub fn AiNodeIteratorGetNext(iter: *mut AtNodeIterator) -> *mut ai_bindings::AtNode {
+    unsafe { ai_bindings::AiNodeIteratorGetNext(iter) }
+}
+
+/// Returns true if there are no more nodes to iterate over.
+///
+/// # Parameters
+/// * `iter` - a node iterator
+/// # Returns
+/// true if the node iterator has moved past the last node
+pub fn AiNodeIteratorFinished(iter: *const AtNodeIterator) -> bool {
+    unsafe { ai_bindings::AiNodeIteratorFinished(iter) }
+}
+
+/// Destroys a node entry iterator when it is no longer needed.
+///
+/// # Parameters
+/// * `iter` - node entry iterator that will be deallocated
+pub fn AiNodeEntryIteratorDestroy(iter: *mut AtNodeEntryIterator) {
+    unsafe { ai_bindings::AiNodeEntryIteratorDestroy(iter) }
+}
+
+/// Returns current node entry and points node entry iterator to the next one.
+///
+/// # Parameters
+/// * `iter` - a node entry iterator
+/// # Returns
+/// the current node entry pointed by the iterator, or NULL if there are no more node entries to iterate over
+pub fn AiNodeEntryIteratorGetNext(iter: *mut AtNodeEntryIterator) -> *mut ai_bindings::AtNodeEntry {
+    unsafe { ai_bindings::AiNodeEntryIteratorGetNext(iter) }
+}
+
+/// Returns true if there are no more node entries to iterate over.
+///
+/// # Parameters
+/// * `iter` - a node entry iterator
+/// # Returns
+/// true if the node entry iterator has moved past the last node entry
+pub fn AiNodeEntryIteratorFinished(iter: *const AtNodeEntryIterator) -> bool {
+    unsafe { ai_bindings::AiNodeEntryIteratorFinished(iter) }
+}
+
+/// Destroys an AOV iterator when it is no longer needed.
+///
+/// # Parameters
+/// * `iter` - AOV iterator that will be deallocated
+pub fn AiAOVIteratorDestroy(iter: *mut AtAOVIterator) {
+    unsafe { ai_bindings::AiAOVIteratorDestroy(iter) }
+}
+
+/// Returns current AOV entry and points AOV iterator to the next one.
+///
+/// # Parameters
+/// * `iter` - an AOV iterator
+/// # Returns
+/// the current AOV entry pointed by the iterator, or NULL if there are no more AOVs to iterate over
+pub fn AiAOVIteratorGetNext(iter: *mut AtAOVIterator) -> *const AtAOVEntry {
+    unsafe { ai_bindings::AiAOVIteratorGetNext(iter) }
+}
+
+/// Returns true if there are no more AOVs to iterate over.
+///
+/// # Parameters
+/// * `iter` - an AOV iterator
+/// # Returns
+/// true if the AOV iterator has moved past the last AOV
+pub fn AiAOVIteratorFinished(iter: *const AtAOVIterator) -> bool {
+    unsafe { ai_bindings::AiAOVIteratorFinished(iter) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_msg::AI_LOG_NONE;
+    use ai_node_entry::{AI_NODE_OPTIONS, AI_NODE_SHAPE};
+    use ai_array::{AiArrayAllocate, AiArrayGetNumElements, AiArraySetPtr};
+    use ai_nodes::{AiNodeGetArray, AiNodeLookUpByName, AiNodeSetArray};
+    use ai_params::AI_TYPE_NODE;
+    use ai_render::AI_SESSION_BATCH;
+    use ai_string::AtString;
+    use std::convert::TryFrom;
+    use std::os::raw::c_void;
+    #[test]
+    fn universe_iterators() {
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        assert!(AiUniverseIsActive(&session));
+        let universe = Universe::default(&session);
+        let sphere = AiNode(&universe, "sphere", Some("universe_ball"), None).unwrap();
+        assert_eq!(universe.options().entry().node_type(), AI_NODE_OPTIONS);
+        assert!(universe.camera().is_none());
+
+        let shapes: Vec<_> = universe.nodes(AI_NODE_SHAPE).collect();
+        assert_eq!(shapes, vec![sphere]);
+        assert!(universe.nodes(AI_NODE_ALL).count() > 1);
+        assert!(universe
+            .node_entries(AI_NODE_SHAPE)
+            .any(|entry| entry.name().to_str() == Ok("polymesh")));
+        assert!(universe
+            .node_entries(AI_NODE_SHAPE)
+            .all(|entry| entry.node_type() == AI_NODE_SHAPE));
+    }
+
+    #[test]
+    fn separate_universes() {
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let working = Universe::default(&session);
+        let sphere = AiNode(&working, "sphere", Some("shared_ball"), None).unwrap();
+        sphere.set("radius", 3.0f32).unwrap();
+        {
+            let reference = Universe::new(&session);
+            assert!(!reference.is_default());
+            assert_ne!(reference.options(), working.options());
+            assert!(AiNodeLookUpByName(&reference, "shared_ball", None).is_err());
+            let copy = reference.copy_node(&sphere, "shared_ball").unwrap();
+            assert_eq!(copy.get::<f32>("radius"), Ok(3.0));
+            assert_eq!(reference.nodes(AI_NODE_SHAPE).count(), 1);
+        }
+        assert_eq!(AiNodeLookUpByName(&working, "shared_ball", None), Ok(sphere));
+    }
+
+    #[test]
+    fn copy_skips_node_arrays() {
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let working = Universe::default(&session);
+        let shader = AiNode(&working, "lambert", Some("mesh_shader"), None).unwrap();
+        let mesh = AiNode(&working, "polymesh", Some("shaded_mesh"), None).unwrap();
+        let shaders = AiArrayAllocate(&session, 1, 1, AI_TYPE_NODE);
+        AiArraySetPtr(shaders, 0, shader.as_ptr() as *mut c_void);
+        let shader_param = AtString::try_from("shader").unwrap();
+        unsafe { ai_bindings::AiNodeSetArray(mesh.as_ptr(), shader_param, shaders) };
+        AiNodeSetArray(&mesh, AtString::try_from("nsides").unwrap(), arnold_array![3u32]);
+
+        let reference = Universe::new(&session);
+        let copy = reference.copy_node(&mesh, "shaded_mesh").unwrap();
+        let copied_shaders = unsafe { ai_bindings::AiNodeGetArray(copy.as_ptr(), shader_param) };
+        assert!(copied_shaders.is_null() || AiArrayGetNumElements(copied_shaders) == 0);
+        assert_eq!(AiNodeGetArray::<u32>(&copy, AtString::try_from("nsides").unwrap()).unwrap().key(0), &[3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "already in use")]
+    fn default_universe_is_unique() {
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let _first = Universe::default(&session);
+        let _second = Universe::default(&session);
+    }
+
+    #[test]
+    fn replace_nodes() {
+        let session = Session::begin(AI_SESSION_BATCH, AI_LOG_NONE);
+        let mut universe = Universe::default(&session);
+        let old = AiNode(&universe, "lambert", Some("old_shader"), None).unwrap().id();
+        let new = AiNode(&universe, "lambert", Some("new_shader"), None).unwrap().id();
+        assert!(universe.replace_node(old, new, true));
+        assert!(!universe.contains(old));
+        assert!(!universe.destroy_node(old));
+        assert!(universe.destroy_node(new));
+    }
+}
@@ -0,0 +1,247 @@
+//! Error handling
+//!
+//! The wrappers that can fail return a `Result` with an [`ArnoldError`](enum.ArnoldError.html), so calls can be chained with `?` instead of checking return codes:
+//! ```
+//! fn render_scene(universe: &Universe, filename: &str) -> Result<(), ArnoldError> {
+//!     AiASSLoad(universe, filename, AI_NODE_ALL)?;
+//!     AiRender(universe, AI_RENDER_MODE_CAMERA)?;
+//!     Ok(())
+//! }
+//! ```
+
+use ai_bindings::AtRenderErrorCode;
+use ai_params::ParamType;
+use ai_render::{
+    AI_ABORT, AI_ERROR, AI_ERROR_BAD_CAMERA, AI_ERROR_NO_CAMERA, AI_ERROR_NO_OUTPUTS, AI_ERROR_RENDER_REGION,
+    AI_ERROR_UNAVAILABLE_DEVICE, AI_ERROR_VALIDATION, AI_INTERRUPT, AI_SUCCESS,
+};
+
+use std::convert::Infallible;
+use std::error::Error;
+use std::ffi::NulError;
+use std::fmt;
+use std::str::Utf8Error;
+use std::time::Duration;
+
+/// Errors returned by the Arnold wrappers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArnoldError {
+    /// A render call returned an error code other than AI_SUCCESS.
+    Render { code: AtRenderErrorCode },
+    /// The render ran out of its time budget and was aborted after `passes` completed passes.
+    Timeout { budget: Duration, passes: usize },
+    /// The render was interrupted by SIGINT after `passes` completed passes.
+    Interrupted { passes: usize },
+    /// Only the default universe can be rendered.
+    NotRenderable,
+    /// Interactive rendering needs a session started with AI_SESSION_INTERACTIVE.
+    NotInteractive,
+    /// AiASSLoad() could not read the file.
+    AssLoad { path: String },
+    /// AiASSWrite() could not write the file.
+    AssWrite { path: String },
+    /// A string passed to Arnold contains a NUL byte.
+    InvalidString { string: String, position: usize },
+    /// A string returned by Arnold is not valid UTF-8.
+    InvalidUtf8(Utf8Error),
+    /// AiNode() could not create a node of this type.
+    NodeCreation { node_type: String, name: String },
+    /// No node with this name exists in the universe.
+    NodeNotFound { name: String },
+    /// No driver writes files with this extension.
+    DriverNotFound { extension: String },
+    /// AiNodeEntryInstall() did not install a node type implemented in Rust.
+    Install { name: String },
+    /// The node has neither a built-in nor a user-defined parameter with this name.
+    UnknownParam { node: String, param: String },
+    /// The parameter exists but holds a different type than the one requested.
+    TypeMismatch {
+        node: String,
+        param: String,
+        expected: ParamType,
+        found: ParamType,
+    },
+    /// The parameter type cannot be read or written through [`ParamValue`](../ai_params/enum.ParamValue.html).
+    UnsupportedType {
+        node: String,
+        param: String,
+        param_type: ParamType,
+    },
+    /// AiNodeDeclare() rejected the declaration, because the parameter exists or the declaration is malformed.
+    Declaration {
+        node: String,
+        param: String,
+        declaration: String,
+    },
+    /// An array index is past the end of the array.
+    IndexOutOfBounds { index: usize, len: usize },
+    /// The array holds elements of another type than the one requested.
+    ArrayType { array_type: ParamType, requested: ParamType },
+    /// A motion key does not have the same number of elements as the array.
+    KeyLength { expected: usize, found: usize },
+    /// Arrays hold at most 255 motion keys.
+    TooManyKeys { nkeys: usize },
+    /// AiNodeLink() or AiNodeLinkOutput() could not connect the nodes.
+    Link {
+        source: String,
+        target: String,
+        input: String,
+    },
+    /// Some parameters could not be copied to the node `name`.
+    NodeCopy { name: String, errors: Vec<ArnoldError> },
+}
+
+impl ArnoldError {
+    /// Turn a render error code into a `Result`, AI_SUCCESS being the only success.
+    pub fn check_render(code: AtRenderErrorCode) -> Result<(), ArnoldError> {
+        if code == AI_SUCCESS {
+            Ok(())
+        } else {
+            Err(ArnoldError::Render { code: code })
+        }
+    }
+}
+
+fn render_message(code: AtRenderErrorCode) -> &'static str {
+    match code {
+        AI_ABORT => "render aborted",
+        AI_ERROR_NO_CAMERA => "camera not defined",
+        AI_ERROR_BAD_CAMERA => "bad camera data",
+        AI_ERROR_VALIDATION => "unable to validate the license",
+        AI_ERROR_RENDER_REGION => "invalid render region",
+        AI_INTERRUPT => "render interrupted by user",
+        AI_ERROR_NO_OUTPUTS => "no rendering outputs",
+        AI_ERROR_UNAVAILABLE_DEVICE => "cannot create GPU context",
+        AI_ERROR => "generic render error",
+        _ => "unknown render error",
+    }
+}
+
+impl fmt::Display for ArnoldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ArnoldError::Render { code } => write!(f, "{} (code {:#x})", render_message(code), code),
+            ArnoldError::Timeout { budget, passes } => write!(
+                f,
+                "render exceeded its budget of {:?} after {} completed passes",
+                budget, passes
+            ),
+            ArnoldError::Interrupted { passes } => {
+                write!(f, "render interrupted by SIGINT after {} completed passes", passes)
+            }
+            ArnoldError::NotRenderable => f.write_str("only the default universe can be rendered"),
+            ArnoldError::NotInteractive => f.write_str("interactive rendering needs an interactive session"),
+            ArnoldError::AssLoad { ref path } => write!(f, "could not load .ass file '{}'", path),
+            ArnoldError::AssWrite { ref path } => write!(f, "could not write .ass file '{}'", path),
+            ArnoldError::InvalidString { ref string, position } => {
+                write!(f, "string {:?} contains a NUL byte at position {}", string, position)
+            }
+            ArnoldError::InvalidUtf8(ref err) => write!(f, "string returned by Arnold is not UTF-8: {}", err),
+            ArnoldError::NodeCreation { ref node_type, ref name } => {
+                write!(f, "could not create {} node '{}'", node_type, name)
+            }
+            ArnoldError::NodeNotFound { ref name } => write!(f, "no node named '{}'", name),
+            ArnoldError::DriverNotFound { ref extension } => write!(f, "no driver for extension '{}'", extension),
+            ArnoldError::Install { ref name } => write!(f, "could not install node type '{}'", name),
+            ArnoldError::UnknownParam { ref node, ref param } => {
+                write!(f, "node '{}' has no parameter '{}'", node, param)
+            }
+            ArnoldError::TypeMismatch { ref node, ref param, expected, found } => write!(
+                f,
+                "parameter '{}' on node '{}' is of type {}, not {}",
+                param, node, expected, found
+            ),
+            ArnoldError::UnsupportedType { ref node, ref param, param_type } => write!(
+                f,
+                "parameter '{}' on node '{}' has unsupported type {}",
+                param, node, param_type
+            ),
+            ArnoldError::Declaration { ref node, ref param, ref declaration } => write!(
+                f,
+                "could not declare parameter '{}' as '{}' on node '{}'",
+                param, declaration, node
+            ),
+            ArnoldError::IndexOutOfBounds { index, len } => {
+                write!(f, "index {} is out of bounds for an array of {} elements", index, len)
+            }
+            ArnoldError::ArrayType { array_type, requested } => {
+                write!(f, "array holds {} elements, not {}", array_type, requested)
+            }
+            ArnoldError::KeyLength { expected, found } => {
+                write!(f, "motion key has {} elements, expected {}", found, expected)
+            }
+            ArnoldError::TooManyKeys { nkeys } => write!(f, "arrays hold at most 255 motion keys, got {}", nkeys),
+            ArnoldError::Link { ref source, ref target, ref input } => {
+                write!(f, "could not link node '{}' to '{}.{}'", source, target, input)
+            }
+            ArnoldError::NodeCopy { ref name, ref errors } => {
+                write!(f, "could not copy {} parameters to node '{}'", errors.len(), name)?;
+                for err in errors {
+                    write!(f, "; {}", err)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for ArnoldError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            ArnoldError::InvalidUtf8(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<Utf8Error> for ArnoldError {
+    fn from(err: Utf8Error) -> ArnoldError {
+        ArnoldError::InvalidUtf8(err)
+    }
+}
+
+impl From<Infallible> for ArnoldError {
+    fn from(err: Infallible) -> ArnoldError {
+        match err {}
+    }
+}
+
+impl From<NulError> for ArnoldError {
+    fn from(err: NulError) -> ArnoldError {
+        let position = err.nul_position();
+        ArnoldError::InvalidString {
+            string: String::from_utf8_lossy(&err.into_vec()).into_owned(),
+            position: position,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+    #[test]
+    fn error_conversions() {
+        let err: ArnoldError = CString::new("bad\0name").unwrap_err().into();
+        assert_eq!(
+            err,
+            ArnoldError::InvalidString {
+                string: "bad\0name".to_owned(),
+                position: 3
+            }
+        );
+        assert_eq!(ArnoldError::check_render(AI_SUCCESS), Ok(()));
+        assert_eq!(
+            ArnoldError::check_render(AI_ERROR_NO_CAMERA).unwrap_err().to_string(),
+            "camera not defined (code 0x2)"
+        );
+        let err = ArnoldError::NodeCopy {
+            name: "copy".to_owned(),
+            errors: vec![ArnoldError::NodeNotFound { name: "ball".to_owned() }],
+        };
+        assert_eq!(
+            err.to_string(),
+            "could not copy 1 parameters to node 'copy'; no node named 'ball'"
+        );
+    }
+}
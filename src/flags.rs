@@ -0,0 +1,214 @@
+//! Bit flag types
+//!
+//! Arnold uses integer bit masks for node types, log flags, etc. The types declared with `ai_bitflags!` wrap such masks, so that a mask of one kind cannot be passed where another is expected:
+//! ```
+//! AiASSWrite(&universe, "lightsncams.ass", NodeType::LIGHT | NodeType::CAMERA, false, false);
+//! ```
+//! They print as the names of the flags they hold, such as `LIGHT | CAMERA`, and parse back from the same form, so they can be used directly as command line arguments.
+
+use std::error::Error;
+use std::fmt;
+
+/// Error returned when a string does not name a known flag.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseFlagsError {
+    flag: String,
+}
+
+impl ParseFlagsError {
+    #[doc(hidden)]
+    pub fn new(flag: &str) -> ParseFlagsError {
+        ParseFlagsError {
+            flag: flag.to_owned(),
+        }
+    }
+
+    /// The part of the input that could not be parsed.
+    pub fn flag(&self) -> &str {
+        &self.flag
+    }
+}
+
+impl fmt::Display for ParseFlagsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown flag '{}'", self.flag)
+    }
+}
+
+impl Error for ParseFlagsError {}
+
+/// Declare a bit flag type over an integer mask.
+///
+/// Single-bit flags are listed under `flags`, named combinations under `composites`. The empty mask is always available as `NONE`.
+/// Parsing accepts flag names separated by `|`, `,` or `+`, case-insensitively, as well as plain integers.
+macro_rules! ai_bitflags {
+    (
+        $(#[$attr:meta])*
+        pub struct $name:ident: $t:ty {
+            flags {
+                $($(#[$flag_attr:meta])* $flag:ident = $flag_value:expr,)+
+            }
+            composites {
+                $($(#[$composite_attr:meta])* $composite:ident = $composite_value:expr,)*
+            }
+        }
+    ) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+        pub struct $name($t);
+
+        impl $name {
+            /// No flags set.
+            pub const NONE: $name = $name(0);
+            $($(#[$flag_attr])* pub const $flag: $name = $name($flag_value);)+
+            $($(#[$composite_attr])* pub const $composite: $name = $name($composite_value);)*
+
+            /// Wrap a raw mask, as passed to and returned by the C API.
+            pub fn from_bits(bits: $t) -> $name {
+                $name(bits)
+            }
+
+            /// The raw mask, as passed to and returned by the C API.
+            pub fn bits(&self) -> $t {
+                self.0
+            }
+
+            /// Check if no flags are set.
+            pub fn is_empty(&self) -> bool {
+                self.0 == 0
+            }
+
+            /// Check if all flags in `other` are set.
+            pub fn contains(&self, other: $name) -> bool {
+                self.0 & other.0 == other.0
+            }
+
+            /// Check if any flag in `other` is set.
+            pub fn intersects(&self, other: $name) -> bool {
+                self.0 & other.0 != 0
+            }
+
+            /// Set the flags in `other`.
+            pub fn insert(&mut self, other: $name) {
+                self.0 |= other.0;
+            }
+
+            /// Clear the flags in `other`.
+            pub fn remove(&mut self, other: $name) {
+                self.0 &= !other.0;
+            }
+        }
+
+        impl ::std::ops::BitOr for $name {
+            type Output = $name;
+            fn bitor(self, other: $name) -> $name {
+                $name(self.0 | other.0)
+            }
+        }
+
+        impl ::std::ops::BitOrAssign for $name {
+            fn bitor_assign(&mut self, other: $name) {
+                self.0 |= other.0;
+            }
+        }
+
+        impl ::std::ops::BitAnd for $name {
+            type Output = $name;
+            fn bitand(self, other: $name) -> $name {
+                $name(self.0 & other.0)
+            }
+        }
+
+        impl ::std::ops::BitAndAssign for $name {
+            fn bitand_assign(&mut self, other: $name) {
+                self.0 &= other.0;
+            }
+        }
+
+        impl ::std::ops::Sub for $name {
+            type Output = $name;
+            fn sub(self, other: $name) -> $name {
+                $name(self.0 & !other.0)
+            }
+        }
+
+        impl ::std::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                if self.is_empty() {
+                    return f.write_str("NONE");
+                }
+                $(
+                    if *self == $name::$composite {
+                        return f.write_str(stringify!($composite));
+                    }
+                )*
+                let mut rest = self.0;
+                let mut first = true;
+                $(
+                    if rest & $flag_value == $flag_value {
+                        if !first {
+                            f.write_str(" | ")?;
+                        }
+                        f.write_str(stringify!($flag))?;
+                        rest &= !$flag_value;
+                        first = false;
+                    }
+                )+
+                if rest != 0 {
+                    if !first {
+                        f.write_str(" | ")?;
+                    }
+                    write!(f, "{:#x}", rest)?;
+                }
+                Ok(())
+            }
+        }
+
+        impl ::std::str::FromStr for $name {
+            type Err = ::flags::ParseFlagsError;
+
+            fn from_str(s: &str) -> Result<$name, ::flags::ParseFlagsError> {
+                let mut flags = $name::NONE;
+                for part in s.split(|c| c == '|' || c == ',' || c == '+') {
+                    let part = part.trim();
+                    let upper = part.to_uppercase();
+                    flags |= match upper.as_str() {
+                        "NONE" => $name::NONE,
+                        $(stringify!($flag) => $name::$flag,)+
+                        $(stringify!($composite) => $name::$composite,)*
+                        _ => match part.parse::<$t>() {
+                            Ok(bits) => $name(bits),
+                            Err(_) => return Err(::flags::ParseFlagsError::new(part)),
+                        },
+                    };
+                }
+                Ok(flags)
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ai_msg::LogFlags;
+    use ai_node_entry::NodeType;
+    #[test]
+    fn flags_display() {
+        assert_eq!((NodeType::LIGHT | NodeType::CAMERA).to_string(), "CAMERA | LIGHT");
+        assert_eq!(NodeType::ALL.to_string(), "ALL");
+        assert_eq!(LogFlags::NONE.to_string(), "NONE");
+        assert_eq!(LogFlags::from_bits(LogFlags::ERRORS.bits() | 1 << 20).to_string(), "ERRORS | 0x100000");
+    }
+    #[test]
+    fn flags_parse() {
+        assert_eq!("LIGHT | CAMERA".parse(), Ok(NodeType::LIGHT | NodeType::CAMERA));
+        assert_eq!("warnings,errors".parse(), Ok(LogFlags::WARNINGS | LogFlags::ERRORS));
+        assert_eq!("all".parse(), Ok(NodeType::ALL));
+        assert_eq!("12".parse(), Ok(LogFlags::ERRORS | LogFlags::DEBUG));
+        assert_eq!("LIGHT | LAMP".parse::<NodeType>(), Err(ParseFlagsError::new("LAMP")));
+        let shapes = NodeType::ALL - NodeType::SHAPE;
+        assert!(!shapes.contains(NodeType::SHAPE));
+        assert!(shapes.intersects(NodeType::SHAPE | NodeType::LIGHT));
+    }
+}
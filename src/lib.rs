@@ -1,16 +1,28 @@
 #[allow(dead_code)]
 
 pub mod ai_bindings;
+#[macro_use]
+pub mod flags;
+pub mod error;
 
+pub mod ai_allocate;
+#[macro_use]
 pub mod ai_array;
 pub mod ai_bbox;
+pub mod ai_cameras;
 pub mod ai_color;
 pub mod ai_constants;
 pub mod ai_dotass;
 pub mod ai_drivers;
+pub mod ai_filters;
 pub mod ai_msg;
 pub mod ai_node_entry;
 pub mod ai_nodes;
+pub mod ai_params;
+pub mod ai_procedural;
 pub mod ai_render;
+pub mod ai_shaderglobals;
+pub mod ai_shaders;
 pub mod ai_string;
+pub mod ai_universe;
 pub mod ai_vector;